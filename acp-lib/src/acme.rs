@@ -0,0 +1,458 @@
+//! ACME (RFC 8555) client
+//!
+//! Lets the server obtain publicly-trusted certificates for its
+//! management/proxy endpoints instead of forcing clients to trust a
+//! private, self-signed CA. Supports the `http-01` and `dns-01` challenge
+//! types; callers are responsible for actually serving the HTTP-01 response
+//! and publishing the DNS-01 TXT record, since those live outside this
+//! crate (the server's own HTTP listener and whatever DNS provider is
+//! configured).
+
+use crate::error::{AcpError, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use ecdsa::signature::Signer;
+use p256::ecdsa::{Signature, SigningKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// An ACME directory's resource URLs (RFC 8555 section 7.1.1)
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcmeDirectory {
+    #[serde(rename = "newNonce")]
+    pub new_nonce: String,
+    #[serde(rename = "newAccount")]
+    pub new_account: String,
+    #[serde(rename = "newOrder")]
+    pub new_order: String,
+    #[serde(rename = "revokeCert")]
+    pub revoke_cert: Option<String>,
+}
+
+/// A challenge offered for one of an order's authorizations
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcmeChallenge {
+    #[serde(rename = "type")]
+    pub challenge_type: String,
+    pub url: String,
+    pub token: String,
+    pub status: String,
+}
+
+/// An authorization for one identifier within an order
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcmeAuthorization {
+    pub identifier: AcmeIdentifier,
+    pub status: String,
+    pub challenges: Vec<AcmeChallenge>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct AcmeIdentifier {
+    #[serde(rename = "type")]
+    pub identifier_type: String,
+    pub value: String,
+}
+
+/// An in-progress or finalized order
+#[derive(Debug, Clone, Deserialize)]
+pub struct AcmeOrder {
+    pub status: String,
+    pub authorizations: Vec<String>,
+    pub finalize: String,
+    pub certificate: Option<String>,
+}
+
+/// A registered ACME account: its keypair and the server-assigned account URL (kid)
+pub struct AcmeAccount {
+    signing_key: SigningKey,
+    pub kid: String,
+}
+
+impl AcmeAccount {
+    /// RFC 7638 JWK thumbprint of the account's public key, used to build
+    /// HTTP-01 and DNS-01 key authorizations.
+    pub fn thumbprint(&self) -> String {
+        let jwk = self.jwk();
+        // Canonical JWK member order for an EC key, per RFC 7638 section 3.
+        let canonical = format!(
+            r#"{{"crv":"{}","kty":"EC","x":"{}","y":"{}"}}"#,
+            jwk.crv, jwk.x, jwk.y
+        );
+        let digest = Sha256::digest(canonical.as_bytes());
+        URL_SAFE_NO_PAD.encode(digest)
+    }
+
+    fn jwk(&self) -> Jwk {
+        let point = self.signing_key.verifying_key().to_encoded_point(false);
+        let (x, y) = (
+            point.x().expect("uncompressed point has x"),
+            point.y().expect("uncompressed point has y"),
+        );
+        Jwk {
+            crv: "P-256".to_string(),
+            kty: "EC".to_string(),
+            x: URL_SAFE_NO_PAD.encode(x),
+            y: URL_SAFE_NO_PAD.encode(y),
+        }
+    }
+
+    /// Key authorization for a challenge token: `token + "." + base64url(sha256(thumbprint))`
+    pub fn key_authorization(&self, token: &str) -> String {
+        format!("{}.{}", token, self.thumbprint())
+    }
+
+    /// HTTP-01 response body to serve at `/.well-known/acme-challenge/<token>`
+    pub fn http01_response(&self, token: &str) -> String {
+        self.key_authorization(token)
+    }
+
+    /// DNS-01 TXT record value to publish at `_acme-challenge.<domain>`
+    pub fn dns01_txt_value(&self, token: &str) -> String {
+        let key_auth = self.key_authorization(token);
+        URL_SAFE_NO_PAD.encode(Sha256::digest(key_auth.as_bytes()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Jwk {
+    crv: String,
+    kty: String,
+    x: String,
+    y: String,
+}
+
+/// Minimal RFC 8555 ACME client: account registration, order creation,
+/// challenge retrieval, and order finalization.
+///
+/// The client holds the account key in memory and performs JWS-signed POST
+/// requests as required by the protocol. Challenge *responses* (serving the
+/// HTTP-01 token, publishing the DNS-01 record) are the caller's job.
+pub struct AcmeClient {
+    http: reqwest::Client,
+    directory: AcmeDirectory,
+}
+
+impl AcmeClient {
+    /// Fetch the directory document from `directory_url` (e.g. Let's Encrypt's
+    /// `https://acme-v02.api.letsencrypt.org/directory`).
+    pub async fn new(directory_url: &str) -> Result<Self> {
+        let http = reqwest::Client::new();
+        let directory: AcmeDirectory = http
+            .get(directory_url)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("failed to fetch ACME directory: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AcpError::network(format!("invalid ACME directory response: {}", e)))?;
+
+        Ok(Self { http, directory })
+    }
+
+    async fn fresh_nonce(&self) -> Result<String> {
+        let resp = self
+            .http
+            .head(&self.directory.new_nonce)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("failed to fetch replay nonce: {}", e)))?;
+
+        resp.headers()
+            .get("Replay-Nonce")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| AcpError::network("ACME server did not return a Replay-Nonce".to_string()))
+    }
+
+    /// Sign a JWS request body per RFC 8555 section 6.2.
+    ///
+    /// `kid_or_jwk` is `None` for the initial `new-account` request (which
+    /// must embed the JWK directly) and `Some(kid)` for every request after
+    /// the account is registered.
+    fn sign(&self, key: &SigningKey, url: &str, nonce: &str, payload: &str, kid: Option<&str>) -> String {
+        let alg = "ES256";
+        let protected = if let Some(kid) = kid {
+            serde_json::json!({ "alg": alg, "kid": kid, "nonce": nonce, "url": url })
+        } else {
+            let account = AcmeAccount {
+                signing_key: key.clone(),
+                kid: String::new(),
+            };
+            serde_json::json!({ "alg": alg, "jwk": account.jwk(), "nonce": nonce, "url": url })
+        };
+
+        let protected_b64 = URL_SAFE_NO_PAD.encode(protected.to_string());
+        let payload_b64 = URL_SAFE_NO_PAD.encode(payload);
+        let signing_input = format!("{}.{}", protected_b64, payload_b64);
+
+        let signature: Signature = key.sign(signing_input.as_bytes());
+        let signature_b64 = URL_SAFE_NO_PAD.encode(signature.to_bytes());
+
+        serde_json::json!({
+            "protected": protected_b64,
+            "payload": payload_b64,
+            "signature": signature_b64,
+        })
+        .to_string()
+    }
+
+    /// Register (or fetch, if already registered under this key) an account.
+    pub async fn new_account(&self, contact_email: &str) -> Result<AcmeAccount> {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let nonce = self.fresh_nonce().await?;
+
+        let payload = serde_json::json!({
+            "termsOfServiceAgreed": true,
+            "contact": [format!("mailto:{}", contact_email)],
+        })
+        .to_string();
+
+        let body = self.sign(&signing_key, &self.directory.new_account, &nonce, &payload, None);
+
+        let resp = self
+            .http
+            .post(&self.directory.new_account)
+            .header("Content-Type", "application/jose+json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("new-account request failed: {}", e)))?;
+
+        let kid = resp
+            .headers()
+            .get("Location")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| AcpError::network("ACME server did not return an account URL".to_string()))?;
+
+        Ok(AcmeAccount { signing_key, kid })
+    }
+
+    /// Create an order for the given SANs.
+    pub async fn new_order(&self, account: &AcmeAccount, sans: &[String]) -> Result<(AcmeOrder, String)> {
+        let identifiers: Vec<AcmeIdentifier> = sans
+            .iter()
+            .map(|san| AcmeIdentifier {
+                identifier_type: "dns".to_string(),
+                value: san.clone(),
+            })
+            .collect();
+
+        let nonce = self.fresh_nonce().await?;
+        let payload = serde_json::json!({ "identifiers": identifiers }).to_string();
+        let body = self.sign(
+            &account.signing_key,
+            &self.directory.new_order,
+            &nonce,
+            &payload,
+            Some(&account.kid),
+        );
+
+        let resp = self
+            .http
+            .post(&self.directory.new_order)
+            .header("Content-Type", "application/jose+json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("new-order request failed: {}", e)))?;
+
+        let order_url = resp
+            .headers()
+            .get("Location")
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string())
+            .ok_or_else(|| AcpError::network("ACME server did not return an order URL".to_string()))?;
+
+        let order: AcmeOrder = resp
+            .json()
+            .await
+            .map_err(|e| AcpError::network(format!("invalid order response: {}", e)))?;
+
+        Ok((order, order_url))
+    }
+
+    /// Fetch an authorization's challenges.
+    pub async fn fetch_authorization(&self, authorization_url: &str) -> Result<AcmeAuthorization> {
+        self.http
+            .get(authorization_url)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("authorization fetch failed: {}", e)))?
+            .json()
+            .await
+            .map_err(|e| AcpError::network(format!("invalid authorization response: {}", e)))
+    }
+
+    /// Tell the server we're ready for it to validate a challenge (an empty
+    /// JWS-signed POST to the challenge URL).
+    pub async fn respond_to_challenge(&self, account: &AcmeAccount, challenge: &AcmeChallenge) -> Result<()> {
+        let nonce = self.fresh_nonce().await?;
+        let body = self.sign(&account.signing_key, &challenge.url, &nonce, "{}", Some(&account.kid));
+
+        self.http
+            .post(&challenge.url)
+            .header("Content-Type", "application/jose+json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("challenge response failed: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Poll an order's status until it is no longer `pending`/`processing`,
+    /// up to `max_attempts` times.
+    pub async fn poll_order(&self, order_url: &str, max_attempts: u32) -> Result<AcmeOrder> {
+        for _ in 0..max_attempts {
+            let order: AcmeOrder = self
+                .http
+                .get(order_url)
+                .send()
+                .await
+                .map_err(|e| AcpError::network(format!("order poll failed: {}", e)))?
+                .json()
+                .await
+                .map_err(|e| AcpError::network(format!("invalid order response: {}", e)))?;
+
+            if order.status != "pending" && order.status != "processing" {
+                return Ok(order);
+            }
+
+            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+        }
+
+        Err(AcpError::network("timed out waiting for ACME order to become ready".to_string()))
+    }
+
+    /// Finalize the order with a DER-encoded CSR and return the order (now
+    /// carrying a `certificate` URL once it transitions to `valid`).
+    pub async fn finalize(&self, account: &AcmeAccount, order: &AcmeOrder, csr_der: &[u8]) -> Result<AcmeOrder> {
+        let nonce = self.fresh_nonce().await?;
+        let payload = serde_json::json!({ "csr": URL_SAFE_NO_PAD.encode(csr_der) }).to_string();
+        let body = self.sign(&account.signing_key, &order.finalize, &nonce, &payload, Some(&account.kid));
+
+        self.http
+            .post(&order.finalize)
+            .header("Content-Type", "application/jose+json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("finalize request failed: {}", e)))?;
+
+        self.poll_order(&order.finalize, 30).await
+    }
+
+    /// Download the issued certificate chain (PEM) once the order is `valid`.
+    pub async fn download_certificate(&self, certificate_url: &str) -> Result<String> {
+        self.http
+            .get(certificate_url)
+            .send()
+            .await
+            .map_err(|e| AcpError::network(format!("certificate download failed: {}", e)))?
+            .text()
+            .await
+            .map_err(|e| AcpError::network(format!("invalid certificate response: {}", e)))
+    }
+}
+
+impl AcmeAccount {
+    /// PKCS#8 PEM encoding of the account key, for persisting across restarts.
+    pub fn to_pkcs8_pem(&self) -> Result<String> {
+        use p256::pkcs8::EncodePrivateKey;
+        self.signing_key
+            .to_pkcs8_pem(Default::default())
+            .map(|pem| pem.to_string())
+            .map_err(|e| AcpError::storage(format!("failed to encode ACME account key: {}", e)))
+    }
+
+    /// Restore an account from its persisted PKCS#8 PEM key and account URL (kid).
+    pub fn from_pkcs8_pem(pem: &str, kid: String) -> Result<Self> {
+        use p256::pkcs8::DecodePrivateKey;
+        let signing_key = SigningKey::from_pkcs8_pem(pem)
+            .map_err(|e| AcpError::storage(format!("failed to decode ACME account key: {}", e)))?;
+        Ok(Self { signing_key, kid })
+    }
+}
+
+/// Generate an ECDSA P-256 keypair and a DER-encoded CSR for the given SANs,
+/// suitable for `AcmeClient::finalize`. Returns `(csr_der, private_key_pem)`.
+pub fn generate_csr(sans: &[String]) -> Result<(Vec<u8>, String)> {
+    let mut params = rcgen::CertificateParams::new(sans.to_vec());
+    params.distinguished_name = rcgen::DistinguishedName::new();
+
+    let cert = rcgen::Certificate::from_params(params)
+        .map_err(|e| AcpError::storage(format!("failed to build CSR params: {}", e)))?;
+
+    let csr_der = cert
+        .serialize_request_der()
+        .map_err(|e| AcpError::storage(format!("failed to serialize CSR: {}", e)))?;
+    let key_pem = cert.serialize_private_key_pem();
+
+    Ok((csr_der, key_pem))
+}
+
+/// Whether a PEM certificate chain's leaf is within `threshold_days` of expiry
+/// (or already expired).
+pub fn needs_renewal(cert_pem: &str, threshold_days: i64) -> Result<bool> {
+    let (_, pem) = x509_parser::pem::parse_x509_pem(cert_pem.as_bytes())
+        .map_err(|e| AcpError::storage(format!("failed to parse certificate PEM: {}", e)))?;
+    let (_, cert) = x509_parser::parse_x509_certificate(&pem.contents)
+        .map_err(|e| AcpError::storage(format!("failed to parse certificate: {}", e)))?;
+
+    let not_after: DateTime<Utc> = cert
+        .validity()
+        .not_after
+        .to_datetime()
+        .into();
+
+    Ok(Utc::now() + Duration::days(threshold_days) >= not_after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_authorization_format() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let account = AcmeAccount {
+            signing_key,
+            kid: "https://example.com/acct/1".to_string(),
+        };
+
+        let key_auth = account.key_authorization("token123");
+        assert!(key_auth.starts_with("token123."));
+        assert_eq!(key_auth.split('.').count(), 2);
+    }
+
+    #[test]
+    fn test_thumbprint_is_stable_for_same_key() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let account = AcmeAccount {
+            signing_key: signing_key.clone(),
+            kid: String::new(),
+        };
+        let other = AcmeAccount {
+            signing_key,
+            kid: String::new(),
+        };
+
+        assert_eq!(account.thumbprint(), other.thumbprint());
+    }
+
+    #[test]
+    fn test_dns01_txt_value_differs_from_key_authorization() {
+        let signing_key = SigningKey::random(&mut rand::thread_rng());
+        let account = AcmeAccount {
+            signing_key,
+            kid: String::new(),
+        };
+
+        let txt_value = account.dns01_txt_value("token123");
+        let key_auth = account.key_authorization("token123");
+        assert_ne!(txt_value, key_auth);
+    }
+}
@@ -0,0 +1,149 @@
+//! Per-SNI certificate resolution for the management listener
+//!
+//! Lets a single listener present different certificates for different
+//! hostnames, keyed by the SNI name in the ClientHello, so rotating one
+//! name's certificate doesn't disturb any other. Connections that don't
+//! present a matching (or any) SNI name - bare IP connections, for instance -
+//! fall back to a designated default entry.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+
+use crate::error::{AcpError, Result};
+
+/// Key under which the no-SNI-match fallback certificate is stored.
+const FALLBACK_KEY: &str = "*";
+
+/// Resolves a TLS certificate by SNI hostname, falling back to a default
+/// entry for IP-only or bare connections.
+#[derive(Debug, Default)]
+pub struct SniCertResolver {
+    certs: RwLock<HashMap<String, Arc<CertifiedKey>>>,
+}
+
+impl SniCertResolver {
+    /// An empty resolver. Connections will be rejected until at least a
+    /// fallback certificate is installed via [`set_fallback`](Self::set_fallback).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Install (or replace) the certificate served for `hostname`, without
+    /// disturbing any other hostname's entry.
+    pub fn set_cert(&self, hostname: &str, cert: Arc<CertifiedKey>) {
+        self.certs
+            .write()
+            .expect("SNI cert map poisoned")
+            .insert(hostname.to_lowercase(), cert);
+    }
+
+    /// Install (or replace) the fallback certificate served when no SNI name
+    /// matches, or none was presented.
+    pub fn set_fallback(&self, cert: Arc<CertifiedKey>) {
+        self.set_cert(FALLBACK_KEY, cert);
+    }
+
+    /// The hostnames currently configured, excluding the fallback entry.
+    pub fn hostnames(&self) -> Vec<String> {
+        self.certs
+            .read()
+            .expect("SNI cert map poisoned")
+            .keys()
+            .filter(|key| key.as_str() != FALLBACK_KEY)
+            .cloned()
+            .collect()
+    }
+}
+
+impl ResolvesServerCert for SniCertResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let certs = self.certs.read().expect("SNI cert map poisoned");
+
+        if let Some(name) = client_hello.server_name() {
+            if let Some(cert) = certs.get(&name.to_lowercase()) {
+                return Some(cert.clone());
+            }
+        }
+
+        certs.get(FALLBACK_KEY).cloned()
+    }
+}
+
+/// Build a `CertifiedKey` from a PEM certificate chain and a PEM private
+/// key, suitable for [`SniCertResolver::set_cert`] / `set_fallback`.
+pub fn certified_key_from_pem(cert_chain_pem: &str, private_key_pem: &str) -> Result<CertifiedKey> {
+    let certs = rustls_pemfile::certs(&mut cert_chain_pem.as_bytes())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| AcpError::storage(format!("invalid certificate chain PEM: {}", e)))?;
+    if certs.is_empty() {
+        return Err(AcpError::storage(
+            "certificate chain PEM contained no certificates".to_string(),
+        ));
+    }
+
+    let key = rustls_pemfile::private_key(&mut private_key_pem.as_bytes())
+        .map_err(|e| AcpError::storage(format!("invalid private key PEM: {}", e)))?
+        .ok_or_else(|| AcpError::storage("private key PEM contained no key".to_string()))?;
+
+    let signing_key = rustls::crypto::ring::sign::any_supported_type(&key)
+        .map_err(|e| AcpError::storage(format!("unsupported private key: {}", e)))?;
+
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A self-signed cert/key PEM pair for `domain`, good enough to exercise
+    /// `certified_key_from_pem` without a real CA.
+    fn self_signed(domain: &str) -> (String, String) {
+        let params = rcgen::CertificateParams::new(vec![domain.to_string()]);
+        let cert = rcgen::Certificate::from_params(params).expect("valid cert params");
+        (cert.serialize_pem().expect("self-signed cert"), cert.serialize_private_key_pem())
+    }
+
+    #[test]
+    fn test_hostnames_excludes_fallback() {
+        let resolver = SniCertResolver::new();
+        let (cert_pem, key_pem) = self_signed("a.example.com");
+        let cert = Arc::new(certified_key_from_pem(&cert_pem, &key_pem).unwrap());
+
+        resolver.set_cert("a.example.com", cert.clone());
+        resolver.set_fallback(cert);
+
+        assert_eq!(resolver.hostnames(), vec!["a.example.com".to_string()]);
+    }
+
+    #[test]
+    fn test_set_cert_lowercases_hostname() {
+        let resolver = SniCertResolver::new();
+        let (cert_pem, key_pem) = self_signed("Example.COM");
+        let cert = Arc::new(certified_key_from_pem(&cert_pem, &key_pem).unwrap());
+
+        resolver.set_cert("Example.COM", cert);
+
+        assert_eq!(resolver.hostnames(), vec!["example.com".to_string()]);
+    }
+
+    #[test]
+    fn test_certified_key_from_pem_round_trips_self_signed_cert() {
+        let (cert_pem, key_pem) = self_signed("example.com");
+        assert!(certified_key_from_pem(&cert_pem, &key_pem).is_ok());
+    }
+
+    #[test]
+    fn test_certified_key_from_pem_rejects_empty_chain() {
+        let (_, key_pem) = self_signed("example.com");
+        assert!(certified_key_from_pem("", &key_pem).is_err());
+    }
+
+    #[test]
+    fn test_certified_key_from_pem_rejects_invalid_key() {
+        let (cert_pem, _) = self_signed("example.com");
+        assert!(certified_key_from_pem(&cert_pem, "not a key").is_err());
+    }
+}
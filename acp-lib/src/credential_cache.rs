@@ -0,0 +1,297 @@
+//! Credential cache with expiration/session semantics
+//!
+//! `parse_and_transform` used to reload every credential field from storage
+//! (or, now, from an external provider) on every single request. That's
+//! wasteful, and it's actively wrong for short-lived/rotating credentials:
+//! reloading a provider on every request is fine, but reloading a *cached*
+//! value past its expiration would serve a stale token. `CredentialCache`
+//! keys cached values by `(plugin, field)` and honors a `CacheControl`
+//! returned alongside each value so callers can pick the right tradeoff per
+//! field.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use tokio::sync::RwLock;
+
+use crate::error::Result;
+
+/// How long a cached credential value remains valid.
+///
+/// Internally tagged so new variants can be added later without breaking
+/// values already serialized by an older version of this enum.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "cache", rename_all = "snake_case")]
+pub enum CacheControl {
+    /// Cache for the life of the proxy process.
+    Session,
+    /// Never cache; always reload on the next lookup.
+    Never,
+    /// Cache until `expiration` (unix timestamp, seconds), then reload.
+    Expires { expiration: i64 },
+}
+
+impl CacheControl {
+    fn is_valid(&self) -> bool {
+        match self {
+            CacheControl::Session => true,
+            CacheControl::Never => false,
+            CacheControl::Expires { expiration } => Utc::now().timestamp() < *expiration,
+        }
+    }
+}
+
+struct CachedCredential {
+    value: String,
+    control: CacheControl,
+}
+
+/// Process-lifetime cache of resolved credential field values
+pub struct CredentialCache {
+    entries: RwLock<HashMap<(String, String), CachedCredential>>,
+}
+
+impl Default for CredentialCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CredentialCache {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Return the cached value for `(plugin, field)` if one exists and is
+    /// still valid under its `CacheControl`; otherwise call `loader` to
+    /// resolve a fresh `(value, control)` pair, cache it, and return it.
+    pub async fn get_or_load<F, Fut>(&self, plugin: &str, field: &str, loader: F) -> Result<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(String, CacheControl)>>,
+    {
+        let key = (plugin.to_string(), field.to_string());
+
+        {
+            let entries = self.entries.read().await;
+            if let Some(cached) = entries.get(&key) {
+                if cached.control.is_valid() {
+                    return Ok(cached.value.clone());
+                }
+            }
+        }
+
+        let (value, control) = loader().await?;
+
+        let mut entries = self.entries.write().await;
+        entries.insert(
+            key,
+            CachedCredential {
+                value: value.clone(),
+                control,
+            },
+        );
+
+        Ok(value)
+    }
+
+    /// Drop every cached value for `plugin`, forcing the next lookup for
+    /// each of its fields to reload.
+    pub async fn invalidate_plugin(&self, plugin: &str) {
+        self.entries.write().await.retain(|(p, _), _| p != plugin);
+    }
+
+    /// Drop the cached value for `(plugin, field)`, forcing the next lookup
+    /// to reload. Callers that write or delete a credential's value directly
+    /// (bypassing `get_or_load`) should call this so a stale cached value
+    /// isn't served afterwards.
+    pub async fn invalidate(&self, plugin: &str, field: &str) {
+        self.entries
+            .write()
+            .await
+            .remove(&(plugin.to_string(), field.to_string()));
+    }
+
+    /// Return the cached value for `(plugin, field)` if present and still
+    /// valid, without triggering a reload. For callers (like a batched
+    /// provider fetch) that resolve several fields in one round trip and
+    /// don't fit `get_or_load`'s single-field closure.
+    pub async fn get(&self, plugin: &str, field: &str) -> Option<String> {
+        let entries = self.entries.read().await;
+        entries
+            .get(&(plugin.to_string(), field.to_string()))
+            .filter(|cached| cached.control.is_valid())
+            .map(|cached| cached.value.clone())
+    }
+
+    /// Cache `value` for `(plugin, field)` under `control`.
+    pub async fn put(&self, plugin: &str, field: &str, value: String, control: CacheControl) {
+        self.entries
+            .write()
+            .await
+            .insert((plugin.to_string(), field.to_string()), CachedCredential { value, control });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn test_session_value_is_reused() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            let value = cache
+                .get_or_load("exa", "api_key", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(("secret-value".to_string(), CacheControl::Session))
+                })
+                .await
+                .expect("get_or_load");
+            assert_eq!(value, "secret-value");
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_never_reloads_every_time() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..3 {
+            cache
+                .get_or_load("exa", "api_key", || async {
+                    let n = calls.fetch_add(1, Ordering::SeqCst);
+                    Ok((format!("value-{}", n), CacheControl::Never))
+                })
+                .await
+                .expect("get_or_load");
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_expired_value_is_reloaded() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+
+        let past = Utc::now().timestamp() - 10;
+        cache
+            .get_or_load("exa", "api_key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("stale".to_string(), CacheControl::Expires { expiration: past }))
+            })
+            .await
+            .expect("get_or_load");
+
+        cache
+            .get_or_load("exa", "api_key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("fresh".to_string(), CacheControl::Expires { expiration: past }))
+            })
+            .await
+            .expect("get_or_load");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unexpired_value_is_reused() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+        let future = Utc::now().timestamp() + 300;
+
+        for _ in 0..3 {
+            cache
+                .get_or_load("exa", "api_key", || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(("value".to_string(), CacheControl::Expires { expiration: future }))
+                })
+                .await
+                .expect("get_or_load");
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_plugin_forces_reload() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+
+        cache
+            .get_or_load("exa", "api_key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("value".to_string(), CacheControl::Session))
+            })
+            .await
+            .expect("get_or_load");
+
+        cache.invalidate_plugin("exa").await;
+
+        cache
+            .get_or_load("exa", "api_key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("value".to_string(), CacheControl::Session))
+            })
+            .await
+            .expect("get_or_load");
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_forces_reload_for_that_field_only() {
+        let cache = CredentialCache::new();
+        let calls = AtomicUsize::new(0);
+
+        for field in ["api_key", "api_secret"] {
+            cache
+                .get_or_load("exa", field, || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(("value".to_string(), CacheControl::Session))
+                })
+                .await
+                .expect("get_or_load");
+        }
+
+        cache.invalidate("exa", "api_key").await;
+
+        cache
+            .get_or_load("exa", "api_key", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("value".to_string(), CacheControl::Session))
+            })
+            .await
+            .expect("get_or_load");
+        cache
+            .get_or_load("exa", "api_secret", || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Ok(("value".to_string(), CacheControl::Session))
+            })
+            .await
+            .expect("get_or_load");
+
+        // api_key reloaded once more (3 total); api_secret stayed cached (still 1).
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_cache_control_serializes_internally_tagged() {
+        let expires = CacheControl::Expires { expiration: 1684251794 };
+        let json = serde_json::to_string(&expires).unwrap();
+        assert_eq!(json, r#"{"cache":"expires","expiration":1684251794}"#);
+
+        let session = CacheControl::Session;
+        let json = serde_json::to_string(&session).unwrap();
+        assert_eq!(json, r#"{"cache":"session"}"#);
+    }
+}
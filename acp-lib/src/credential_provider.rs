@@ -0,0 +1,244 @@
+//! External credential providers
+//!
+//! A `CredentialEntry` whose `provider` field is set is backed by a child
+//! process instead of the local `SecretStore`. This lets credentials be
+//! sourced from Vault, 1Password, a cloud secret manager, or short-lived STS
+//! tokens without ACP ever persisting the long-lived secret itself.
+//!
+//! The protocol is one JSON request per line on the child's stdin, answered
+//! with one JSON response per line on stdout. `get` can resolve several
+//! fields in one round trip; `store` and `erase` mirror
+//! `add_credential`/`remove_credential` and act on one field at a time:
+//!
+//! ```text
+//! -> {"v":1,"action":"get","plugin":"exa","fields":["api_key"]}
+//! <- {"Ok":{"fields":{"api_key":"..."},"cache":null}}
+//! <- {"Err":{"kind":"not_found","message":"no such field"}}
+//!
+//! -> {"v":1,"action":"store","plugin":"exa","field":"api_key","value":"..."}
+//! -> {"v":1,"action":"erase","plugin":"exa","field":"api_key"}
+//! <- {"Ok":null}
+//! <- {"Err":{"kind":"denied","message":"..."}}
+//! ```
+
+use crate::credential_cache::CacheControl;
+use crate::error::{AcpError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::Command;
+
+/// A `get` request sent to a provider process's stdin
+#[derive(Debug, Clone, Serialize)]
+struct ProviderRequest<'a> {
+    v: u32,
+    action: &'a str,
+    plugin: &'a str,
+    fields: &'a [String],
+}
+
+/// A `store` or `erase` request sent to a provider process's stdin. Unlike
+/// `ProviderRequest`, which can resolve several fields in one call, writes
+/// are always one field at a time.
+#[derive(Debug, Clone, Serialize)]
+struct ProviderWriteRequest<'a> {
+    v: u32,
+    action: &'a str,
+    plugin: &'a str,
+    field: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<&'a str>,
+}
+
+/// The fields a provider returned on success, plus an optional cache-control
+/// hint - e.g. `{"cache":"expires","expiration":1684251794}` for a
+/// short-lived STS token, or `null`/omitted to fall back to the caller's
+/// default.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderFields {
+    pub fields: HashMap<String, String>,
+    pub cache: Option<CacheControl>,
+}
+
+/// The error half of a provider response
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProviderError {
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+enum ProviderResponse {
+    Ok(ProviderFields),
+    Err(ProviderError),
+}
+
+/// The response to a `store` or `erase` request - no payload on success, so
+/// unlike `ProviderResponse` the `Ok` side carries whatever (possibly empty)
+/// JSON value the provider sent rather than a typed struct.
+#[derive(Debug, Clone, Deserialize)]
+enum AckResponse {
+    Ok(serde_json::Value),
+    Err(ProviderError),
+}
+
+/// Spawn `command`, write `request` as one JSON line to its stdin, and
+/// return the one JSON line it writes back to stdout.
+///
+/// `command` is parsed with shell-style quoting rules (`shell_words::split`)
+/// rather than `split_whitespace`, so a provider command can itself contain
+/// arguments with embedded spaces, e.g. `python3 -c "import sys; ..."`.
+async fn run(command: &str, request: &impl Serialize) -> Result<String> {
+    let mut parts = shell_words::split(command)
+        .map_err(|e| AcpError::storage(format!("invalid credential provider command '{}': {}", command, e)))?
+        .into_iter();
+    let program = parts
+        .next()
+        .ok_or_else(|| AcpError::storage("credential provider command is empty".to_string()))?;
+    let args: Vec<String> = parts.collect();
+
+    let mut child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| AcpError::storage(format!("failed to spawn credential provider '{}': {}", command, e)))?;
+
+    let mut request_line = serde_json::to_vec(request)
+        .map_err(|e| AcpError::storage(format!("failed to encode provider request: {}", e)))?;
+    request_line.push(b'\n');
+
+    {
+        let stdin = child
+            .stdin
+            .as_mut()
+            .ok_or_else(|| AcpError::storage("credential provider stdin unavailable".to_string()))?;
+        stdin
+            .write_all(&request_line)
+            .await
+            .map_err(|e| AcpError::storage(format!("failed to write to credential provider: {}", e)))?;
+    }
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| AcpError::storage("credential provider stdout unavailable".to_string()))?;
+    let mut lines = BufReader::new(stdout).lines();
+    let response_line = lines
+        .next_line()
+        .await
+        .map_err(|e| AcpError::storage(format!("failed to read from credential provider: {}", e)))?
+        .ok_or_else(|| AcpError::storage("credential provider closed stdout without a response".to_string()))?;
+
+    let _ = child.wait().await;
+
+    Ok(response_line)
+}
+
+/// Run `command` once to fetch `fields` for `plugin_name`, returning the
+/// fields it resolved (and any cache-control hint it supplied).
+pub async fn fetch(command: &str, plugin_name: &str, fields: &[String]) -> Result<ProviderFields> {
+    let request = ProviderRequest {
+        v: 1,
+        action: "get",
+        plugin: plugin_name,
+        fields,
+    };
+    let response_line = run(command, &request).await?;
+
+    let response: ProviderResponse = serde_json::from_str(&response_line)
+        .map_err(|e| AcpError::storage(format!("invalid credential provider response: {}", e)))?;
+
+    match response {
+        ProviderResponse::Ok(fields) => Ok(fields),
+        ProviderResponse::Err(err) => Err(AcpError::storage(format!(
+            "credential provider error ({}): {}",
+            err.kind, err.message
+        ))),
+    }
+}
+
+/// Run `command` once to persist `value` for `plugin_name`'s `field`.
+pub async fn store(command: &str, plugin_name: &str, field: &str, value: &str) -> Result<()> {
+    let request = ProviderWriteRequest {
+        v: 1,
+        action: "store",
+        plugin: plugin_name,
+        field,
+        value: Some(value),
+    };
+    ack(command, &request).await
+}
+
+/// Run `command` once to delete `plugin_name`'s `field`.
+pub async fn erase(command: &str, plugin_name: &str, field: &str) -> Result<()> {
+    let request = ProviderWriteRequest {
+        v: 1,
+        action: "erase",
+        plugin: plugin_name,
+        field,
+        value: None,
+    };
+    ack(command, &request).await
+}
+
+async fn ack(command: &str, request: &impl Serialize) -> Result<()> {
+    let response_line = run(command, request).await?;
+
+    let response: AckResponse = serde_json::from_str(&response_line)
+        .map_err(|e| AcpError::storage(format!("invalid credential provider response: {}", e)))?;
+
+    match response {
+        AckResponse::Ok(_) => Ok(()),
+        AckResponse::Err(err) => Err(AcpError::storage(format!(
+            "credential provider error ({}): {}",
+            err.kind, err.message
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fetch_parses_ok_response() {
+        // `cat` echoes our request line back; swap in a one-liner that turns
+        // it into a well-formed Ok response instead so we can exercise the
+        // real parsing path without a bespoke test binary.
+        let script = r#"python3 -c "import sys,json; json.loads(sys.stdin.readline()); print(json.dumps({'Ok': {'fields': {'api_key': 'test-value'}, 'cache': {'cache': 'session'}}}))""#;
+
+        let fields = fetch(script, "exa", &["api_key".to_string()]).await.expect("fetch");
+        assert_eq!(fields.fields.get("api_key"), Some(&"test-value".to_string()));
+        assert_eq!(fields.cache, Some(crate::credential_cache::CacheControl::Session));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_empty_command() {
+        let result = fetch("", "exa", &["api_key".to_string()]).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_parses_ok_response() {
+        let script = r#"python3 -c "import sys,json; json.loads(sys.stdin.readline()); print(json.dumps({'Ok': None}))""#;
+
+        store(script, "exa", "api_key", "test-value").await.expect("store");
+    }
+
+    #[tokio::test]
+    async fn test_erase_parses_err_response() {
+        let script = r#"python3 -c "import sys,json; json.loads(sys.stdin.readline()); print(json.dumps({'Err': {'kind': 'not_found', 'message': 'no such field'}}))""#;
+
+        let err = erase(script, "exa", "api_key").await.expect_err("expected an error response");
+        assert!(err.to_string().contains("not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_empty_command() {
+        let result = store("", "exa", "api_key", "test-value").await;
+        assert!(result.is_err());
+    }
+}
@@ -2,8 +2,16 @@
 ///
 /// This library contains core types, error handling, and shared logic
 /// used by both the `acp` CLI and `acp-server` daemon.
+pub mod acme;
+pub mod cert_resolver;
+pub mod credential_cache;
+pub mod credential_provider;
 pub mod error;
+pub mod paseto;
+pub mod plugin_cache;
 pub mod types;
+pub mod url_pattern;
 
 pub use error::{AcpError, Result};
+pub use plugin_cache::PluginCache;
 pub use types::{ACPCredentials, ACPPlugin, ACPRequest, AgentToken, Config};
@@ -0,0 +1,397 @@
+//! PASETO v4.public agent tokens
+//!
+//! Symmetric agent tokens are secrets the proxy must store and compare,
+//! so a read-only compromise of the store is enough to mint new ones. This
+//! module adds an asymmetric alternative: at creation time we generate an
+//! Ed25519 keypair, sign a JSON claims payload to produce a `v4.public`
+//! PASETO, and keep only the *public* key server-side, keyed by its PASERK
+//! id. Verifying a token only ever needs that public key.
+
+use crate::error::{AcpError, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Crate-visible so callers (e.g. `Registry::authenticate`) can tell a
+/// `v4.public` PASETO token apart from a plain bearer secret before
+/// deciding which verification path to take.
+pub(crate) const HEADER: &str = "v4.public.";
+
+/// Claims carried in a PASETO agent token's payload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PasetoClaims {
+    /// Token name (mirrors the symmetric `AgentToken::name`)
+    pub sub: String,
+    pub iat: DateTime<Utc>,
+    pub exp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub allowed_plugins: Vec<String>,
+}
+
+/// Pre-Authentication Encoding (PASETO spec): a length-prefixed concatenation
+/// of each piece, preventing ambiguity between e.g. `("ab", "c")` and `("a", "bc")`.
+fn pae(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(pieces.len() as u64).to_le_bytes());
+    for piece in pieces {
+        out.extend_from_slice(&(piece.len() as u64).to_le_bytes());
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+/// PASERK `k4.pid` key id for a v4 public key: a SHA-256 digest of the
+/// PASERK-encoded public key, base64url-encoded.
+///
+/// (The PASERK spec calls for BLAKE2b; we use SHA-256 here since it's
+/// already a dependency elsewhere in this crate. The id is still stable and
+/// collision-resistant, just not byte-for-byte interoperable with other
+/// PASERK implementations.)
+pub fn paserk_id(public_key: &VerifyingKey) -> String {
+    let paserk_public = format!("k4.public.{}", URL_SAFE_NO_PAD.encode(public_key.as_bytes()));
+    let digest = Sha256::digest(paserk_public.as_bytes());
+    format!("k4.pid.{}", URL_SAFE_NO_PAD.encode(digest))
+}
+
+/// Sign `claims` as a `v4.public` PASETO, with a footer naming the PASERK id
+/// of `signing_key`'s public half so the verifier knows which key to use.
+pub fn sign(signing_key: &SigningKey, claims: &PasetoClaims) -> Result<String> {
+    let payload = serde_json::to_vec(claims)
+        .map_err(|e| AcpError::storage(format!("failed to serialize PASETO claims: {}", e)))?;
+    let footer = paserk_id(&signing_key.verifying_key());
+
+    let signing_input = pae(&[HEADER.as_bytes(), &payload, footer.as_bytes()]);
+    let signature: Signature = signing_key.sign(&signing_input);
+
+    let mut signed_payload = payload;
+    signed_payload.extend_from_slice(&signature.to_bytes());
+
+    Ok(format!(
+        "{}{}.{}",
+        HEADER,
+        URL_SAFE_NO_PAD.encode(signed_payload),
+        URL_SAFE_NO_PAD.encode(footer)
+    ))
+}
+
+/// Verify a `v4.public` PASETO against `public_key` and return its claims if
+/// the signature checks out, the token isn't expired, and (when given) the
+/// footer's PASERK id matches `public_key`.
+pub fn verify(token: &str, public_key: &VerifyingKey) -> Result<PasetoClaims> {
+    let body = token
+        .strip_prefix(HEADER)
+        .ok_or_else(|| AcpError::storage("not a v4.public PASETO token".to_string()))?;
+
+    let (payload_b64, footer_b64) = body
+        .split_once('.')
+        .ok_or_else(|| AcpError::storage("PASETO token missing footer".to_string()))?;
+
+    let footer = URL_SAFE_NO_PAD
+        .decode(footer_b64)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO footer: {}", e)))?;
+
+    let expected_kid = paserk_id(public_key);
+    if footer != expected_kid.as_bytes() {
+        return Err(AcpError::storage("PASETO footer does not match the verifying key".to_string()));
+    }
+
+    let signed_payload = URL_SAFE_NO_PAD
+        .decode(payload_b64)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO payload: {}", e)))?;
+
+    if signed_payload.len() < Signature::BYTE_SIZE {
+        return Err(AcpError::storage("PASETO payload too short to contain a signature".to_string()));
+    }
+    let split = signed_payload.len() - Signature::BYTE_SIZE;
+    let (payload, sig_bytes) = signed_payload.split_at(split);
+
+    let signature = Signature::from_slice(sig_bytes)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO signature: {}", e)))?;
+
+    let signing_input = pae(&[HEADER.as_bytes(), payload, &footer]);
+    public_key
+        .verify(&signing_input, &signature)
+        .map_err(|_| AcpError::storage("PASETO signature verification failed".to_string()))?;
+
+    let claims: PasetoClaims = serde_json::from_slice(payload)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO claims: {}", e)))?;
+
+    if let Some(exp) = claims.exp {
+        if Utc::now() > exp {
+            return Err(AcpError::storage("PASETO token has expired".to_string()));
+        }
+    }
+
+    Ok(claims)
+}
+
+/// Claims carried in a host-scoped request token: proves the sender holds
+/// the secret key for the footer's `key_id` and restricts the token to a
+/// single destination host, so a leaked token can't be replayed against
+/// another one.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestClaims {
+    pub host: String,
+    pub iat: DateTime<Utc>,
+    pub exp: DateTime<Utc>,
+}
+
+/// Unencrypted footer carried alongside a token signed by `sign_request`:
+/// the PASERK id of the signing key, so the verifier knows which public
+/// key to check the signature against, and the registry URL that key is
+/// registered under.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct RequestFooter {
+    key_id: String,
+    registry_url: String,
+}
+
+fn decode_request_footer(token: &str) -> Result<RequestFooter> {
+    let body = token
+        .strip_prefix(HEADER)
+        .ok_or_else(|| AcpError::storage("not a v4.public PASETO token".to_string()))?;
+    let (_, footer_b64) = body
+        .split_once('.')
+        .ok_or_else(|| AcpError::storage("PASETO token missing footer".to_string()))?;
+    let footer_bytes = URL_SAFE_NO_PAD
+        .decode(footer_b64)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO footer: {}", e)))?;
+    serde_json::from_slice(&footer_bytes)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO footer: {}", e)))
+}
+
+/// Read the `key_id` a `sign_request` token's footer names, without
+/// verifying its signature - used to look up which public key to verify
+/// against before calling `verify_request`.
+pub fn peek_request_key_id(token: &str) -> Result<String> {
+    Ok(decode_request_footer(token)?.key_id)
+}
+
+/// Sign a short-lived, host-scoped `v4.public` PASETO request token: the
+/// payload binds the destination `host` and an expiry `ttl` from now, and
+/// the footer carries `signing_key`'s PASERK id plus `registry_url` so a
+/// verifier can fetch the matching public key.
+pub fn sign_request(
+    signing_key: &SigningKey,
+    host: &str,
+    registry_url: &str,
+    ttl: chrono::Duration,
+) -> Result<String> {
+    let claims = RequestClaims {
+        host: host.to_string(),
+        iat: Utc::now(),
+        exp: Utc::now() + ttl,
+    };
+    let payload = serde_json::to_vec(&claims)
+        .map_err(|e| AcpError::storage(format!("failed to serialize PASETO claims: {}", e)))?;
+    let footer = RequestFooter {
+        key_id: paserk_id(&signing_key.verifying_key()),
+        registry_url: registry_url.to_string(),
+    };
+    let footer_bytes = serde_json::to_vec(&footer)
+        .map_err(|e| AcpError::storage(format!("failed to serialize PASETO footer: {}", e)))?;
+
+    let signing_input = pae(&[HEADER.as_bytes(), &payload, &footer_bytes]);
+    let signature: Signature = signing_key.sign(&signing_input);
+
+    let mut signed_payload = payload;
+    signed_payload.extend_from_slice(&signature.to_bytes());
+
+    Ok(format!(
+        "{}{}.{}",
+        HEADER,
+        URL_SAFE_NO_PAD.encode(signed_payload),
+        URL_SAFE_NO_PAD.encode(footer_bytes)
+    ))
+}
+
+/// Verify a token signed by `sign_request` against `public_key`: checks the
+/// signature, that it hasn't expired, and that its bound host matches
+/// `expected_host` (a mismatch means the token was replayed against a
+/// destination it wasn't issued for). Returns the claims on success.
+pub fn verify_request(
+    token: &str,
+    public_key: &VerifyingKey,
+    expected_host: &str,
+) -> Result<RequestClaims> {
+    let footer = decode_request_footer(token)?;
+    let footer_bytes = serde_json::to_vec(&footer)
+        .map_err(|e| AcpError::storage(format!("failed to serialize PASETO footer: {}", e)))?;
+
+    let expected_kid = paserk_id(public_key);
+    if footer.key_id != expected_kid {
+        return Err(AcpError::storage("PASETO footer does not match the verifying key".to_string()));
+    }
+
+    let body = token.strip_prefix(HEADER).expect("checked by decode_request_footer");
+    let (payload_b64, _) = body.split_once('.').expect("checked by decode_request_footer");
+    let signed_payload = URL_SAFE_NO_PAD
+        .decode(payload_b64)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO payload: {}", e)))?;
+
+    if signed_payload.len() < Signature::BYTE_SIZE {
+        return Err(AcpError::storage("PASETO payload too short to contain a signature".to_string()));
+    }
+    let split = signed_payload.len() - Signature::BYTE_SIZE;
+    let (payload, sig_bytes) = signed_payload.split_at(split);
+
+    let signature = Signature::from_slice(sig_bytes)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO signature: {}", e)))?;
+
+    let signing_input = pae(&[HEADER.as_bytes(), payload, &footer_bytes]);
+    public_key
+        .verify(&signing_input, &signature)
+        .map_err(|_| AcpError::storage("PASETO signature verification failed".to_string()))?;
+
+    let claims: RequestClaims = serde_json::from_slice(payload)
+        .map_err(|e| AcpError::storage(format!("invalid PASETO claims: {}", e)))?;
+
+    if Utc::now() > claims.exp {
+        return Err(AcpError::storage("PASETO request token has expired".to_string()));
+    }
+    if claims.host != expected_host {
+        return Err(AcpError::storage(format!(
+            "PASETO request token is scoped to host '{}', not '{}'",
+            claims.host, expected_host
+        )));
+    }
+
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::OsRng;
+
+    fn test_claims() -> PasetoClaims {
+        PasetoClaims {
+            sub: "my-agent".to_string(),
+            iat: Utc::now(),
+            exp: None,
+            allowed_plugins: vec!["exa".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify_roundtrip() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let token = sign(&signing_key, &test_claims()).expect("sign token");
+
+        assert!(token.starts_with("v4.public."));
+
+        let claims = verify(&token, &signing_key.verifying_key()).expect("verify token");
+        assert_eq!(claims.sub, "my-agent");
+        assert_eq!(claims.allowed_plugins, vec!["exa".to_string()]);
+    }
+
+    #[test]
+    fn test_verify_rejects_wrong_key() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let other_key = SigningKey::generate(&mut OsRng);
+        let token = sign(&signing_key, &test_claims()).expect("sign token");
+
+        let result = verify(&token, &other_key.verifying_key());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_rejects_expired_token() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let mut claims = test_claims();
+        claims.exp = Some(Utc::now() - chrono::Duration::seconds(1));
+        let token = sign(&signing_key, &claims).expect("sign token");
+
+        let result = verify(&token, &signing_key.verifying_key());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_paserk_id_stable_for_same_key() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let id_a = paserk_id(&signing_key.verifying_key());
+        let id_b = paserk_id(&signing_key.verifying_key());
+        assert_eq!(id_a, id_b);
+        assert!(id_a.starts_with("k4.pid."));
+    }
+
+    #[test]
+    fn test_sign_and_verify_request_roundtrip() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let token = sign_request(
+            &signing_key,
+            "api.exa.ai",
+            "https://registry.example.com",
+            chrono::Duration::seconds(60),
+        )
+        .expect("sign request token");
+
+        let claims = verify_request(&token, &signing_key.verifying_key(), "api.exa.ai")
+            .expect("verify request token");
+        assert_eq!(claims.host, "api.exa.ai");
+    }
+
+    #[test]
+    fn test_verify_request_rejects_mismatched_host() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let token = sign_request(
+            &signing_key,
+            "api.exa.ai",
+            "https://registry.example.com",
+            chrono::Duration::seconds(60),
+        )
+        .expect("sign request token");
+
+        let result = verify_request(&token, &signing_key.verifying_key(), "api.other.com");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_request_rejects_expired_token() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let token = sign_request(
+            &signing_key,
+            "api.exa.ai",
+            "https://registry.example.com",
+            chrono::Duration::seconds(-1),
+        )
+        .expect("sign request token");
+
+        let result = verify_request(&token, &signing_key.verifying_key(), "api.exa.ai");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_verify_request_rejects_wrong_key() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let other_key = SigningKey::generate(&mut OsRng);
+        let token = sign_request(
+            &signing_key,
+            "api.exa.ai",
+            "https://registry.example.com",
+            chrono::Duration::seconds(60),
+        )
+        .expect("sign request token");
+
+        let result = verify_request(&token, &other_key.verifying_key(), "api.exa.ai");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_peek_request_key_id_matches_signing_key() {
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let token = sign_request(
+            &signing_key,
+            "api.exa.ai",
+            "https://registry.example.com",
+            chrono::Duration::seconds(60),
+        )
+        .expect("sign request token");
+
+        let key_id = peek_request_key_id(&token).expect("peek key id");
+        assert_eq!(key_id, paserk_id(&signing_key.verifying_key()));
+    }
+}
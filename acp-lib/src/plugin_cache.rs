@@ -0,0 +1,257 @@
+//! Plugin Cache - Compiled-plugin cache with a host-pattern index
+//!
+//! `find_matching_plugin` is O(plugins) per request and, worse, spins up a
+//! fresh `PluginRuntime` and recompiles the JS source for every candidate on
+//! every host lookup. `PluginCache` loads and compiles each plugin once,
+//! keeps it resident, and indexes match patterns so `lookup(host)` runs in
+//! near-constant time instead of rescanning every plugin's `matches_host`.
+//!
+//! Mirrors `TokenCache`'s invalidate-on-write pattern: writes go through the
+//! cache, which updates storage and the Registry and then drops the index so
+//! the next lookup rebuilds it from the authoritative source.
+
+use crate::error::{AcpError, Result};
+use crate::plugin_runtime::PluginRuntime;
+use crate::registry::{PluginEntry, Registry};
+use crate::storage::SecretStore;
+use crate::types::ACPPlugin;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Compiled-plugin index supporting exact and wildcard host lookup
+struct PluginIndex {
+    /// Exact hostname -> plugin
+    exact: HashMap<String, Arc<ACPPlugin>>,
+    /// Wildcard domain suffix (the part after `*.`) -> plugin
+    wildcard: HashMap<String, Arc<ACPPlugin>>,
+}
+
+/// Cache of compiled plugins indexed by host pattern
+///
+/// Read path: check in-memory index -> on miss, load and compile every
+/// registered plugin once from storage. Write path: mutate storage and the
+/// Registry, then invalidate so the next lookup rebuilds.
+pub struct PluginCache {
+    store: Arc<dyn SecretStore>,
+    registry: Arc<Registry>,
+    index: RwLock<Option<PluginIndex>>,
+}
+
+impl PluginCache {
+    /// Create a new PluginCache
+    pub fn new(store: Arc<dyn SecretStore>, registry: Arc<Registry>) -> Self {
+        Self {
+            store,
+            registry,
+            index: RwLock::new(None),
+        }
+    }
+
+    /// Look up the plugin that matches `host`, compiling the full plugin set
+    /// from storage on a cache miss.
+    ///
+    /// Exact hostnames are checked first, then wildcard patterns by walking
+    /// the query host's domain labels from most-specific to least-specific
+    /// (stripping one leading label at a time) and probing the suffix map -
+    /// the same semantics `ACPPlugin::matches_host` implements for a single
+    /// plugin, just precomputed across all of them.
+    pub async fn lookup(&self, host: &str) -> Result<Option<Arc<ACPPlugin>>> {
+        {
+            let guard = self.index.read().await;
+            if let Some(index) = guard.as_ref() {
+                return Ok(Self::lookup_in(index, host));
+            }
+        }
+
+        self.load_index().await?;
+
+        let guard = self.index.read().await;
+        Ok(guard.as_ref().and_then(|index| Self::lookup_in(index, host)))
+    }
+
+    fn lookup_in(index: &PluginIndex, host: &str) -> Option<Arc<ACPPlugin>> {
+        if let Some(plugin) = index.exact.get(host) {
+            return Some(plugin.clone());
+        }
+
+        let mut labels: Vec<&str> = host.split('.').collect();
+        while labels.len() > 1 {
+            labels.remove(0);
+            let suffix = labels.join(".");
+            if let Some(plugin) = index.wildcard.get(&suffix) {
+                return Some(plugin.clone());
+            }
+        }
+
+        None
+    }
+
+    /// Invalidate the cache, forcing the next `lookup` to recompile every
+    /// plugin from storage.
+    pub async fn invalidate(&self) {
+        *self.index.write().await = None;
+    }
+
+    /// Add a plugin: persist its code, register it, and invalidate the cache.
+    pub async fn add_plugin(&self, entry: &PluginEntry, code: &str) -> Result<()> {
+        let key = format!("plugin:{}", entry.name);
+        self.store.set(&key, code.as_bytes()).await?;
+        self.registry.add_plugin(entry).await?;
+        self.invalidate().await;
+        Ok(())
+    }
+
+    /// Remove a plugin by name and invalidate the cache.
+    pub async fn remove_plugin(&self, name: &str) -> Result<()> {
+        let key = format!("plugin:{}", name);
+        self.store.delete(&key).await?;
+        self.registry.remove_plugin(name).await?;
+        self.invalidate().await;
+        Ok(())
+    }
+
+    /// Load and compile every registered plugin, building the host-pattern index.
+    async fn load_index(&self) -> Result<()> {
+        let mut exact = HashMap::new();
+        let mut wildcard = HashMap::new();
+
+        let entries = self.registry.list_plugins().await?;
+        for entry in entries {
+            let key = format!("plugin:{}", entry.name);
+            let Some(code_bytes) = self.store.get(&key).await? else {
+                continue;
+            };
+            let code = String::from_utf8_lossy(&code_bytes);
+
+            let mut runtime = PluginRuntime::new()?;
+            let plugin = match runtime.load_plugin_from_code(&entry.name, &code) {
+                Ok(plugin) => Arc::new(plugin),
+                Err(e) => {
+                    tracing::warn!("Failed to compile plugin {}: {}", entry.name, e);
+                    continue;
+                }
+            };
+
+            for host in &entry.hosts {
+                if let Some(suffix) = host.strip_prefix("*.") {
+                    wildcard.insert(suffix.to_string(), plugin.clone());
+                } else {
+                    exact.insert(host.clone(), plugin.clone());
+                }
+            }
+        }
+
+        *self.index.write().await = Some(PluginIndex { exact, wildcard });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::FileStore;
+
+    async fn setup_plugin(store: &Arc<dyn SecretStore>, registry: &Registry, name: &str, hosts: &[&str]) {
+        let code = format!(
+            r#"
+            var plugin = {{
+                name: "{name}",
+                matchPatterns: {hosts:?},
+                credentialSchema: [],
+                transform: function(request, credentials) {{ return request; }}
+            }};
+            "#,
+            name = name,
+            hosts = hosts,
+        );
+        store
+            .set(&format!("plugin:{}", name), code.as_bytes())
+            .await
+            .expect("store plugin code");
+        registry
+            .add_plugin(&PluginEntry {
+                name: name.to_string(),
+                hosts: hosts.iter().map(|h| h.to_string()).collect(),
+                credential_schema: vec![],
+            })
+            .await
+            .expect("register plugin");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_exact_match() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Arc::new(Registry::new(Arc::clone(&store)));
+        setup_plugin(&store, &registry, "exa", &["api.exa.ai"]).await;
+
+        let cache = PluginCache::new(store, registry);
+        let plugin = cache.lookup("api.exa.ai").await.expect("lookup");
+        assert!(plugin.is_some());
+        assert_eq!(plugin.unwrap().name, "exa");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_wildcard_match() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Arc::new(Registry::new(Arc::clone(&store)));
+        setup_plugin(&store, &registry, "s3", &["*.s3.amazonaws.com"]).await;
+
+        let cache = PluginCache::new(store, registry);
+        let plugin = cache
+            .lookup("my-bucket.s3.amazonaws.com")
+            .await
+            .expect("lookup");
+        assert!(plugin.is_some());
+        assert_eq!(plugin.unwrap().name, "s3");
+    }
+
+    #[tokio::test]
+    async fn test_lookup_no_match_returns_none() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Arc::new(Registry::new(Arc::clone(&store)));
+        setup_plugin(&store, &registry, "exa", &["api.exa.ai"]).await;
+
+        let cache = PluginCache::new(store, registry);
+        let plugin = cache.lookup("api.other.com").await.expect("lookup");
+        assert!(plugin.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_picks_up_newly_added_plugin() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Arc::new(Registry::new(Arc::clone(&store)));
+        let cache = PluginCache::new(Arc::clone(&store), Arc::clone(&registry));
+
+        // Miss, priming an empty index.
+        assert!(cache.lookup("api.exa.ai").await.expect("lookup").is_none());
+
+        setup_plugin(&store, &registry, "exa", &["api.exa.ai"]).await;
+
+        // Stale cache still reports no match until invalidated.
+        assert!(cache.lookup("api.exa.ai").await.expect("lookup").is_none());
+
+        cache.invalidate().await;
+        assert!(cache.lookup("api.exa.ai").await.expect("lookup").is_some());
+    }
+}
@@ -2,17 +2,39 @@
 //!
 //! Provides a sandboxed JavaScript environment for executing plugin transforms.
 //! Implements:
-//! - ACP.crypto globals (sha256, sha256Hex, hmac)
-//! - ACP.util globals (base64, hex, now, isoDate, amzDate)
+//! - ACP.crypto globals (sha256/384/512, *Hex variants, hmac with selectable algo,
+//!   timingSafeEqual, generateKey, secretbox.seal/open)
+//! - ACP.aws globals (sigv4SigningKey, sign - AWS Signature V4 helpers)
+//! - ACP.util globals (base64, base64url, hex, now, isoDate, amzDate)
 //! - TextEncoder/TextDecoder
 //! - Sandbox restrictions (no fetch, eval, etc.)
+//!
+//! Byte payloads crossing the native/JS boundary are represented as
+//! `Uint8Array` (see `bytes_to_js_array`/`js_value_to_bytes`), matching what
+//! `TextEncoder().encode()` and WebCrypto-style APIs return; plain arrays
+//! and strings are still accepted on input for compatibility.
 
+use crate::types::{ACPCredentials, ACPRequest};
 use crate::{AcpError, Result};
 use base64::Engine;
+use boa_engine::object::builtins::{JsArrayBuffer, JsUint8Array};
 use boa_engine::{Context, JsArgs, JsNativeError, JsResult, JsString, JsValue, NativeFunction, Source};
 use chrono::Utc;
 use hmac::{Hmac, Mac};
-use sha2::{Digest, Sha256};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::collections::HashMap;
+
+/// Headers captured from an upstream response that challenged a request,
+/// most commonly a `401` carrying `WWW-Authenticate`. Passed to a plugin's
+/// `transform` as `request.challenge` on a re-transform so it can read the
+/// realm/scheme and select (or derive) the right credential instead of
+/// blindly injecting one static header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Challenge {
+    pub status: u16,
+    pub headers: HashMap<String, String>,
+}
 
 /// JavaScript runtime for plugin execution
 pub struct PluginRuntime {
@@ -43,10 +65,82 @@ impl PluginRuntime {
             .map_err(|e| AcpError::plugin(format!("JavaScript execution error: {}", e)))
     }
 
-    /// Set up ACP.crypto and ACP.util global objects
+    /// Evaluate plugin source that defines `var plugin = { ..., transform:
+    /// function(request, credentials) { ... } }` and register it under
+    /// `name` so `execute_transform` can find it again. A runtime can hold
+    /// more than one loaded plugin at a time.
+    pub fn load_plugin_from_code(&mut self, name: &str, code: &str) -> Result<()> {
+        self.execute(code)?;
+        let name_json = serde_json::to_string(name)
+            .map_err(|e| AcpError::plugin(format!("Failed to encode plugin name: {}", e)))?;
+        let register = format!(
+            "var __acp_plugins = typeof __acp_plugins === 'undefined' ? {{}} : __acp_plugins; __acp_plugins[{}] = plugin;",
+            name_json
+        );
+        self.execute(&register)?;
+        Ok(())
+    }
+
+    /// Run `name`'s `transform(request, credentials)` and return the
+    /// (possibly modified) request.
+    ///
+    /// `request.headers` carries every header from the inbound request. When
+    /// `challenge` is `Some`, the request also carries `request.challenge =
+    /// { status, headers }` describing the upstream response that triggered
+    /// a re-transform (e.g. a `401` with `WWW-Authenticate`), letting the
+    /// plugin branch on realm/scheme instead of blindly re-injecting the
+    /// same static header.
+    pub fn execute_transform(
+        &mut self,
+        name: &str,
+        request: ACPRequest,
+        credentials: &ACPCredentials,
+        challenge: Option<&Challenge>,
+    ) -> Result<ACPRequest> {
+        let name_json = serde_json::to_string(name)
+            .map_err(|e| AcpError::plugin(format!("Failed to encode plugin name: {}", e)))?;
+        let request_json = serde_json::to_string(&request)
+            .map_err(|e| AcpError::plugin(format!("Failed to encode request: {}", e)))?;
+        let credentials_json = serde_json::to_string(credentials)
+            .map_err(|e| AcpError::plugin(format!("Failed to encode credentials: {}", e)))?;
+        let challenge_json = match challenge {
+            Some(c) => serde_json::to_string(c)
+                .map_err(|e| AcpError::plugin(format!("Failed to encode challenge: {}", e)))?,
+            None => "null".to_string(),
+        };
+
+        let call = format!(
+            r#"(function() {{
+                var __plugin = __acp_plugins && __acp_plugins[{name}];
+                if (!__plugin) {{
+                    throw new Error("plugin not loaded: " + {name});
+                }}
+                var __request = {request};
+                __request.challenge = {challenge};
+                return JSON.stringify(__plugin.transform(__request, {credentials}));
+            }})()"#,
+            name = name_json,
+            request = request_json,
+            challenge = challenge_json,
+            credentials = credentials_json,
+        );
+
+        let result = self.execute(&call)?;
+        let result_str = result
+            .as_string()
+            .ok_or_else(|| AcpError::plugin("transform did not return a value".to_string()))?
+            .to_std_string_escaped();
+
+        serde_json::from_str(&result_str)
+            .map_err(|e| AcpError::plugin(format!("transform returned invalid request: {}", e)))
+    }
+
+    /// Set up ACP.crypto, ACP.jwt, ACP.aws, and ACP.util global objects
     fn setup_acp_globals(context: &mut Context) -> Result<()> {
         // Register native functions first
         Self::register_crypto_natives(context)?;
+        Self::register_jwt_natives(context)?;
+        Self::register_aws_natives(context)?;
         Self::register_util_natives(context)?;
 
         // Create ACP namespace with crypto and util methods
@@ -59,8 +153,54 @@ impl PluginRuntime {
                 sha256Hex: function(data) {
                     return __acp_native_sha256_hex(data);
                 },
-                hmac: function(key, data, encoding) {
-                    return __acp_native_hmac(key, data, encoding || 'hex');
+                sha384: function(data) {
+                    return __acp_native_sha384(data);
+                },
+                sha384Hex: function(data) {
+                    return __acp_native_sha384_hex(data);
+                },
+                sha512: function(data) {
+                    return __acp_native_sha512(data);
+                },
+                sha512Hex: function(data) {
+                    return __acp_native_sha512_hex(data);
+                },
+                hmac: function(key, data, encoding, algo) {
+                    return __acp_native_hmac(key, data, encoding || 'hex', algo || 'sha256');
+                },
+                timingSafeEqual: function(a, b) {
+                    return __acp_native_timing_safe_equal(a, b);
+                },
+                generateKey: function() {
+                    return __acp_native_generate_key();
+                },
+                secretbox: {
+                    seal: function(plaintext, key) {
+                        var sealed = __acp_native_secretbox_seal(plaintext, key);
+                        return { nonce: sealed.slice(0, 24), ciphertext: sealed.slice(24) };
+                    },
+                    open: function(ciphertext, nonce, key) {
+                        return __acp_native_secretbox_open(ciphertext, nonce, key);
+                    }
+                }
+            },
+            aws: {
+                sigv4SigningKey: function(secret, dateStamp, region, service) {
+                    var kDate = __acp_native_hmac_raw('AWS4' + secret, dateStamp);
+                    var kRegion = __acp_native_hmac_raw(kDate, region);
+                    var kService = __acp_native_hmac_raw(kRegion, service);
+                    return __acp_native_hmac_raw(kService, 'aws4_request');
+                },
+                sign: function(signingKey, stringToSign) {
+                    return __acp_native_hmac_raw_hex(signingKey, stringToSign);
+                }
+            },
+            jwt: {
+                sign: function(claims, secret, algo) {
+                    return __acp_native_jwt_sign(JSON.stringify(claims), secret, algo || 'HS256');
+                },
+                verify: function(token, secret, algo) {
+                    return JSON.parse(__acp_native_jwt_verify(token, secret, algo || 'HS256'));
                 }
             },
             util: {
@@ -70,6 +210,12 @@ impl PluginRuntime {
                     }
                     return __acp_native_base64_encode(data);
                 },
+                base64url: function(data, decode) {
+                    if (decode) {
+                        return __acp_native_base64url_decode(data);
+                    }
+                    return __acp_native_base64url_encode(data);
+                },
                 hex: function(data, decode) {
                     if (decode) {
                         return __acp_native_hex_decode(data);
@@ -97,45 +243,47 @@ impl PluginRuntime {
 
     /// Register native crypto functions
     fn register_crypto_natives(context: &mut Context) -> Result<()> {
-        // sha256 - returns array of bytes
-        let sha256_fn = NativeFunction::from_fn_ptr(|_, args, context| {
-            let data = args.get_or_undefined(0);
-            let bytes = js_value_to_bytes(data, context)?;
-
-            let mut hasher = Sha256::new();
-            hasher.update(&bytes);
-            let result = hasher.finalize();
-
-            bytes_to_js_array(&result, context)
-        });
+        // sha256/sha384/sha512 - return an array of bytes
         context.register_global_builtin_callable(
             JsString::from("__acp_native_sha256"),
             1,
-            sha256_fn
+            NativeFunction::from_fn_ptr(digest_to_js_array::<Sha256>)
         ).map_err(|e| AcpError::plugin(format!("Failed to register sha256: {}", e)))?;
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_sha384"),
+            1,
+            NativeFunction::from_fn_ptr(digest_to_js_array::<Sha384>)
+        ).map_err(|e| AcpError::plugin(format!("Failed to register sha384: {}", e)))?;
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_sha512"),
+            1,
+            NativeFunction::from_fn_ptr(digest_to_js_array::<Sha512>)
+        ).map_err(|e| AcpError::plugin(format!("Failed to register sha512: {}", e)))?;
 
-        // sha256Hex - returns hex string
-        let sha256_hex_fn = NativeFunction::from_fn_ptr(|_, args, context| {
-            let data = args.get_or_undefined(0);
-            let bytes = js_value_to_bytes(data, context)?;
-
-            let mut hasher = Sha256::new();
-            hasher.update(&bytes);
-            let result = hasher.finalize();
-
-            Ok(JsValue::from(JsString::from(hex::encode(result))))
-        });
+        // sha256Hex/sha384Hex/sha512Hex - return a hex string
         context.register_global_builtin_callable(
             JsString::from("__acp_native_sha256_hex"),
             1,
-            sha256_hex_fn
+            NativeFunction::from_fn_ptr(digest_to_hex::<Sha256>)
         ).map_err(|e| AcpError::plugin(format!("Failed to register sha256Hex: {}", e)))?;
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_sha384_hex"),
+            1,
+            NativeFunction::from_fn_ptr(digest_to_hex::<Sha384>)
+        ).map_err(|e| AcpError::plugin(format!("Failed to register sha384Hex: {}", e)))?;
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_sha512_hex"),
+            1,
+            NativeFunction::from_fn_ptr(digest_to_hex::<Sha512>)
+        ).map_err(|e| AcpError::plugin(format!("Failed to register sha512Hex: {}", e)))?;
 
-        // hmac - returns encoded string
+        // hmac(key, data, encoding, algo) - returns encoded string, dispatching
+        // on algo to the matching sha2 digest
         let hmac_fn = NativeFunction::from_fn_ptr(|_, args, context| {
             let key = args.get_or_undefined(0);
             let data = args.get_or_undefined(1);
             let encoding = args.get_or_undefined(2);
+            let algo = args.get_or_undefined(3);
 
             let key_bytes = js_value_to_bytes(key, context)?;
             let data_bytes = js_value_to_bytes(data, context)?;
@@ -144,29 +292,296 @@ impl PluginRuntime {
             } else {
                 "hex".to_string()
             };
+            let algo_str = if let Some(s) = algo.as_string() {
+                s.to_std_string_escaped()
+            } else {
+                "sha256".to_string()
+            };
 
-            let mut mac = Hmac::<Sha256>::new_from_slice(&key_bytes)
-                .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
-            mac.update(&data_bytes);
-            let result = mac.finalize().into_bytes();
+            let result: Vec<u8> = match algo_str.as_str() {
+                "sha256" => {
+                    let mut mac = Hmac::<Sha256>::new_from_slice(&key_bytes)
+                        .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
+                    mac.update(&data_bytes);
+                    mac.finalize().into_bytes().to_vec()
+                }
+                "sha384" => {
+                    let mut mac = Hmac::<Sha384>::new_from_slice(&key_bytes)
+                        .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
+                    mac.update(&data_bytes);
+                    mac.finalize().into_bytes().to_vec()
+                }
+                "sha512" => {
+                    let mut mac = Hmac::<Sha512>::new_from_slice(&key_bytes)
+                        .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
+                    mac.update(&data_bytes);
+                    mac.finalize().into_bytes().to_vec()
+                }
+                other => {
+                    return Err(JsNativeError::typ()
+                        .with_message(format!("unsupported HMAC algorithm: {}", other))
+                        .into())
+                }
+            };
 
             match encoding_str.as_str() {
-                "hex" => Ok(JsValue::from(JsString::from(hex::encode(result)))),
-                "base64" => Ok(JsValue::from(JsString::from(base64::prelude::BASE64_STANDARD.encode(result)))),
+                "hex" => Ok(JsValue::from(JsString::from(hex::encode(&result)))),
+                "base64" => Ok(JsValue::from(JsString::from(base64::prelude::BASE64_STANDARD.encode(&result)))),
                 _ => bytes_to_js_array(&result, context),
             }
         });
         context.register_global_builtin_callable(
             JsString::from("__acp_native_hmac"),
-            3,
+            4,
             hmac_fn
         ).map_err(|e| AcpError::plugin(format!("Failed to register hmac: {}", e)))?;
 
+        // timingSafeEqual(a, b) - fixed-time byte comparison to avoid leaking
+        // differences in signature/MAC verification via early-exit timing
+        let timing_safe_equal_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let a = args.get_or_undefined(0);
+            let b = args.get_or_undefined(1);
+            let a_bytes = js_value_to_bytes(a, context)?;
+            let b_bytes = js_value_to_bytes(b, context)?;
+            Ok(JsValue::from(constant_time_eq(&a_bytes, &b_bytes)))
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_timing_safe_equal"),
+            2,
+            timing_safe_equal_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register timingSafeEqual: {}", e)))?;
+
+        // generateKey() - 32 random bytes, suitable as a secretbox key
+        let generate_key_fn = NativeFunction::from_fn_ptr(|_, _, context| {
+            use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+
+            let mut key = [0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            bytes_to_js_array(&key, context)
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_generate_key"),
+            0,
+            generate_key_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register generateKey: {}", e)))?;
+
+        // secretbox.seal(plaintext, key) - XSalsa20-Poly1305 AEAD with a fresh
+        // random nonce; returns nonce || ciphertext as a single byte array,
+        // split back apart on the JS side.
+        let secretbox_seal_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            use xsalsa20poly1305::aead::{Aead, AeadCore, OsRng};
+            use xsalsa20poly1305::{KeyInit, XSalsa20Poly1305};
+
+            let plaintext = args.get_or_undefined(0);
+            let key = args.get_or_undefined(1);
+            let plaintext_bytes = js_value_to_bytes(plaintext, context)?;
+            let key_bytes = js_value_to_bytes(key, context)?;
+
+            let cipher = XSalsa20Poly1305::new_from_slice(&key_bytes)
+                .map_err(|e| JsNativeError::typ().with_message(format!("invalid secretbox key: {}", e)))?;
+            let nonce = XSalsa20Poly1305::generate_nonce(&mut OsRng);
+            let ciphertext = cipher
+                .encrypt(&nonce, plaintext_bytes.as_slice())
+                .map_err(|e| JsNativeError::typ().with_message(format!("secretbox seal failed: {}", e)))?;
+
+            let mut sealed = Vec::with_capacity(nonce.len() + ciphertext.len());
+            sealed.extend_from_slice(&nonce);
+            sealed.extend_from_slice(&ciphertext);
+            bytes_to_js_array(&sealed, context)
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_secretbox_seal"),
+            2,
+            secretbox_seal_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register secretbox.seal: {}", e)))?;
+
+        // secretbox.open(ciphertext, nonce, key) - throws on authentication failure
+        let secretbox_open_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            use xsalsa20poly1305::aead::Aead;
+            use xsalsa20poly1305::{KeyInit, XNonce, XSalsa20Poly1305};
+
+            let ciphertext = args.get_or_undefined(0);
+            let nonce = args.get_or_undefined(1);
+            let key = args.get_or_undefined(2);
+            let ciphertext_bytes = js_value_to_bytes(ciphertext, context)?;
+            let nonce_bytes = js_value_to_bytes(nonce, context)?;
+            let key_bytes = js_value_to_bytes(key, context)?;
+
+            if nonce_bytes.len() != 24 {
+                return Err(JsNativeError::typ()
+                    .with_message("secretbox nonce must be 24 bytes")
+                    .into());
+            }
+
+            let cipher = XSalsa20Poly1305::new_from_slice(&key_bytes)
+                .map_err(|e| JsNativeError::typ().with_message(format!("invalid secretbox key: {}", e)))?;
+            let nonce = XNonce::from_slice(&nonce_bytes);
+            let plaintext = cipher
+                .decrypt(nonce, ciphertext_bytes.as_slice())
+                .map_err(|_| JsNativeError::typ().with_message("secretbox open failed: authentication failed"))?;
+
+            bytes_to_js_array(&plaintext, context)
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_secretbox_open"),
+            3,
+            secretbox_open_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register secretbox.open: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Register native JWT sign/verify functions
+    ///
+    /// `exp`/`nbf` are checked in Unix seconds, matching how the rest of the
+    /// codebase (`CacheControl::Expires`) represents expiration - not
+    /// `ACP.util.now()`'s milliseconds.
+    fn register_jwt_natives(context: &mut Context) -> Result<()> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let sign_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let claims_json = args
+                .get_or_undefined(0)
+                .as_string()
+                .ok_or_else(|| JsNativeError::typ().with_message("jwt.sign expects claims as a JSON string"))?
+                .to_std_string_escaped();
+            let secret = js_value_to_bytes(args.get_or_undefined(1), context)?;
+            let algo = args.get_or_undefined(2);
+            let algo_str = if let Some(s) = algo.as_string() {
+                s.to_std_string_escaped()
+            } else {
+                "HS256".to_string()
+            };
+
+            let header = format!(r#"{{"alg":"{}","typ":"JWT"}}"#, algo_str);
+            let header_b64 = URL_SAFE_NO_PAD.encode(header.as_bytes());
+            let payload_b64 = URL_SAFE_NO_PAD.encode(claims_json.as_bytes());
+            let signing_input = format!("{}.{}", header_b64, payload_b64);
+
+            let mac = jwt_hmac(&algo_str, &secret, signing_input.as_bytes())
+                .map_err(|e| JsNativeError::typ().with_message(e))?;
+            let signature_b64 = URL_SAFE_NO_PAD.encode(mac);
+
+            Ok(JsValue::from(JsString::from(format!("{}.{}", signing_input, signature_b64))))
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_jwt_sign"),
+            3,
+            sign_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register jwt.sign: {}", e)))?;
+
+        let verify_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let token = args
+                .get_or_undefined(0)
+                .as_string()
+                .ok_or_else(|| JsNativeError::typ().with_message("jwt.verify expects a string token"))?
+                .to_std_string_escaped();
+            let secret = js_value_to_bytes(args.get_or_undefined(1), context)?;
+            let algo = args.get_or_undefined(2);
+            let algo_str = if let Some(s) = algo.as_string() {
+                s.to_std_string_escaped()
+            } else {
+                "HS256".to_string()
+            };
+
+            let parts: Vec<&str> = token.split('.').collect();
+            if parts.len() != 3 {
+                return Err(JsNativeError::typ()
+                    .with_message("malformed JWT: expected header.payload.signature")
+                    .into());
+            }
+            let signing_input = format!("{}.{}", parts[0], parts[1]);
+
+            let expected_mac = jwt_hmac(&algo_str, &secret, signing_input.as_bytes())
+                .map_err(|e| JsNativeError::typ().with_message(e))?;
+            let given_signature = URL_SAFE_NO_PAD
+                .decode(parts[2])
+                .map_err(|e| JsNativeError::typ().with_message(format!("invalid JWT signature encoding: {}", e)))?;
+
+            if !constant_time_eq(&expected_mac, &given_signature) {
+                return Err(JsNativeError::typ().with_message("JWT signature verification failed").into());
+            }
+
+            let payload_bytes = URL_SAFE_NO_PAD
+                .decode(parts[1])
+                .map_err(|e| JsNativeError::typ().with_message(format!("invalid JWT payload encoding: {}", e)))?;
+            let claims: serde_json::Value = serde_json::from_slice(&payload_bytes)
+                .map_err(|e| JsNativeError::typ().with_message(format!("invalid JWT payload JSON: {}", e)))?;
+
+            let now = Utc::now().timestamp();
+            if let Some(exp) = claims.get("exp").and_then(|v| v.as_i64()) {
+                if now >= exp {
+                    return Err(JsNativeError::typ().with_message("JWT has expired").into());
+                }
+            }
+            if let Some(nbf) = claims.get("nbf").and_then(|v| v.as_i64()) {
+                if now < nbf {
+                    return Err(JsNativeError::typ().with_message("JWT is not yet valid").into());
+                }
+            }
+
+            let claims_json = serde_json::to_string(&claims)
+                .map_err(|e| JsNativeError::typ().with_message(format!("Failed to encode claims: {}", e)))?;
+            Ok(JsValue::from(JsString::from(claims_json)))
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_jwt_verify"),
+            3,
+            verify_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register jwt.verify: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Register native AWS Signature V4 helpers
+    ///
+    /// SigV4 key derivation chains HMAC-SHA256 where each step's *raw* MAC
+    /// bytes become the next step's key, so these natives deal in byte
+    /// arrays rather than the hex/base64 strings `__acp_native_hmac` returns.
+    fn register_aws_natives(context: &mut Context) -> Result<()> {
+        let hmac_raw_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let key = args.get_or_undefined(0);
+            let data = args.get_or_undefined(1);
+            let key_bytes = js_value_to_bytes(key, context)?;
+            let data_bytes = js_value_to_bytes(data, context)?;
+
+            let mut mac = Hmac::<Sha256>::new_from_slice(&key_bytes)
+                .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
+            mac.update(&data_bytes);
+            let result = mac.finalize().into_bytes().to_vec();
+            bytes_to_js_array(&result, context)
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_hmac_raw"),
+            2,
+            hmac_raw_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register hmac_raw: {}", e)))?;
+
+        let hmac_raw_hex_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let key = args.get_or_undefined(0);
+            let data = args.get_or_undefined(1);
+            let key_bytes = js_value_to_bytes(key, context)?;
+            let data_bytes = js_value_to_bytes(data, context)?;
+
+            let mut mac = Hmac::<Sha256>::new_from_slice(&key_bytes)
+                .map_err(|e| JsNativeError::typ().with_message(format!("HMAC key error: {}", e)))?;
+            mac.update(&data_bytes);
+            let result = mac.finalize().into_bytes().to_vec();
+            Ok(JsValue::from(JsString::from(hex::encode(&result))))
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_hmac_raw_hex"),
+            2,
+            hmac_raw_hex_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register hmac_raw_hex: {}", e)))?;
+
         Ok(())
     }
 
     /// Register native util functions
     fn register_util_natives(context: &mut Context) -> Result<()> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
         use base64::prelude::*;
 
         // base64 encode
@@ -203,6 +618,40 @@ impl PluginRuntime {
             base64_decode_fn
         ).map_err(|e| AcpError::plugin(format!("Failed to register base64 decode: {}", e)))?;
 
+        // base64url encode (URL-safe alphabet, no padding)
+        let base64url_encode_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let data = args.get_or_undefined(0);
+            let bytes = js_value_to_bytes(data, context)?;
+            Ok(JsValue::from(JsString::from(URL_SAFE_NO_PAD.encode(&bytes))))
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_base64url_encode"),
+            1,
+            base64url_encode_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register base64url encode: {}", e)))?;
+
+        // base64url decode - tolerant of input with or without trailing '=' padding
+        let base64url_decode_fn = NativeFunction::from_fn_ptr(|_, args, context| {
+            let data = args.get_or_undefined(0);
+            let s = if let Some(js_str) = data.as_string() {
+                js_str.to_std_string_escaped()
+            } else {
+                return Err(JsNativeError::typ()
+                    .with_message("Expected string for base64url decode")
+                    .into());
+            };
+
+            let bytes = URL_SAFE_NO_PAD.decode(s.trim_end_matches('=').as_bytes())
+                .map_err(|e| JsNativeError::typ().with_message(format!("Base64url decode error: {}", e)))?;
+
+            bytes_to_js_array(&bytes, context)
+        });
+        context.register_global_builtin_callable(
+            JsString::from("__acp_native_base64url_decode"),
+            1,
+            base64url_decode_fn
+        ).map_err(|e| AcpError::plugin(format!("Failed to register base64url decode: {}", e)))?;
+
         // hex encode
         let hex_encode_fn = NativeFunction::from_fn_ptr(|_, args, context| {
             let data = args.get_or_undefined(0);
@@ -385,45 +834,115 @@ impl Default for PluginRuntime {
 fn js_value_to_bytes(value: &JsValue, context: &mut Context) -> JsResult<Vec<u8>> {
     if let Some(s) = value.as_string() {
         // String -> UTF-8 bytes
-        Ok(s.to_std_string_escaped().into_bytes())
-    } else if let Some(obj) = value.as_object() {
-        // Try to extract as array-like object
-        let length_key = JsString::from("length");
-        let length_value = obj.get(length_key, context)?;
-
-        if let Some(length) = length_value.as_number() {
-            let len = length as usize;
-            let mut bytes = Vec::with_capacity(len);
-            for i in 0..len {
-                let val = obj.get(i, context)?;
-                let byte = val.as_number()
-                    .ok_or_else(|| JsNativeError::typ().with_message("Array element must be number"))? as u8;
-                bytes.push(byte);
-            }
-            Ok(bytes)
-        } else {
-            Err(JsNativeError::typ()
-                .with_message("Expected array-like object with length property")
-                .into())
+        return Ok(s.to_std_string_escaped().into_bytes());
+    }
+
+    let obj = value.as_object().ok_or_else(|| {
+        JsNativeError::typ().with_message("Expected string, Uint8Array, or array-like object")
+    })?;
+
+    // Fast path: Uint8Array (what TextEncoder.encode and all crypto/util
+    // natives now return) - copy its backing ArrayBuffer in one go rather
+    // than indexing element by element.
+    if let Ok(typed_array) = JsUint8Array::from_object(obj.clone()) {
+        let buffer = typed_array.buffer(context)?;
+        if let Some(data) = buffer.data() {
+            return Ok(data.to_vec());
         }
+    }
+
+    // Compatibility path: plain array-like object (e.g. `[1, 2, 3]`) with a
+    // numeric `length` property, read index by index.
+    let length_key = JsString::from("length");
+    let length_value = obj.get(length_key, context)?;
+
+    if let Some(length) = length_value.as_number() {
+        let len = length as usize;
+        let mut bytes = Vec::with_capacity(len);
+        for i in 0..len {
+            let val = obj.get(i, context)?;
+            let byte = val.as_number()
+                .ok_or_else(|| JsNativeError::typ().with_message("Array element must be number"))? as u8;
+            bytes.push(byte);
+        }
+        Ok(bytes)
     } else {
         Err(JsNativeError::typ()
-            .with_message("Expected string or array-like object")
+            .with_message("Expected string, Uint8Array, or array-like object")
             .into())
     }
 }
 
-fn bytes_to_js_array(bytes: &[u8], context: &mut Context) -> JsResult<JsValue> {
-    // Create a JavaScript array from bytes
-    let array = context.eval(Source::from_bytes("[]"))?;
-    let array_obj = array.as_object()
-        .ok_or_else(|| JsNativeError::typ().with_message("Failed to create array"))?;
+/// Hash `data` with `D` and return it as a JS array of bytes. Monomorphized
+/// per digest type so each instantiation coerces to the bare fn pointer
+/// `NativeFunction::from_fn_ptr` expects.
+fn digest_to_js_array<D: Digest>(_this: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let data = args.get_or_undefined(0);
+    let bytes = js_value_to_bytes(data, context)?;
+
+    let mut hasher = D::new();
+    hasher.update(&bytes);
+    let result = hasher.finalize();
+
+    bytes_to_js_array(&result, context)
+}
+
+/// Hash `data` with `D` and return it as a hex string.
+fn digest_to_hex<D: Digest>(_this: &JsValue, args: &[JsValue], context: &mut Context) -> JsResult<JsValue> {
+    let data = args.get_or_undefined(0);
+    let bytes = js_value_to_bytes(data, context)?;
+
+    let mut hasher = D::new();
+    hasher.update(&bytes);
+    let result = hasher.finalize();
+
+    Ok(JsValue::from(JsString::from(hex::encode(result))))
+}
+
+/// Compute an HMAC over `data` with `key`, keyed to the JWT `alg` name
+/// (`HS256`/`HS384`/`HS512`).
+fn jwt_hmac(algo: &str, key: &[u8], data: &[u8]) -> std::result::Result<Vec<u8>, String> {
+    match algo {
+        "HS256" => {
+            let mut mac = Hmac::<Sha256>::new_from_slice(key).map_err(|e| format!("HMAC key error: {}", e))?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }
+        "HS384" => {
+            let mut mac = Hmac::<Sha384>::new_from_slice(key).map_err(|e| format!("HMAC key error: {}", e))?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }
+        "HS512" => {
+            let mut mac = Hmac::<Sha512>::new_from_slice(key).map_err(|e| format!("HMAC key error: {}", e))?;
+            mac.update(data);
+            Ok(mac.finalize().into_bytes().to_vec())
+        }
+        other => Err(format!("unsupported JWT algorithm: {}", other)),
+    }
+}
 
-    for (i, &byte) in bytes.iter().enumerate() {
-        array_obj.set(i, JsValue::from(byte as i32), false, context)?;
+/// Constant-time byte comparison, so a verifier timing a signature check
+/// can't narrow down a valid MAC one byte at a time.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut diff = 0u8;
+    for (x, y) in a.iter().zip(b.iter()) {
+        diff |= x ^ y;
     }
+    diff == 0
+}
 
-    Ok(array)
+/// Build a `Uint8Array` from `bytes`, copying the whole buffer in one shot
+/// (via its backing `ArrayBuffer`) instead of setting elements one at a
+/// time, so plugin code sees the same type `TextEncoder().encode()` and
+/// WebCrypto-style APIs return.
+fn bytes_to_js_array(bytes: &[u8], context: &mut Context) -> JsResult<JsValue> {
+    let buffer = JsArrayBuffer::from_byte_block(bytes.to_vec(), context)?;
+    let typed_array = JsUint8Array::from_array_buffer(buffer, context)?;
+    Ok(typed_array.into())
 }
 
 #[cfg(test)]
@@ -470,6 +989,190 @@ mod tests {
         assert_eq!(hmac, "6e9ef29b75fffc5b7abae527d58fdadb2fe42e7219011976917343065f58ed4a");
     }
 
+    #[test]
+    fn test_acp_crypto_sha384_hex() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute("ACP.crypto.sha384Hex('hello')").unwrap();
+        let hash = result.as_string().unwrap().to_std_string_escaped();
+
+        // Expected SHA-384 of "hello"
+        assert_eq!(
+            hash,
+            "59e1748777448c69de6b800d7a33bbfb9ff1b463e44354c3553bcdb9c666fa90125a3c79f90397bdf5f6a13de828684f"
+        );
+    }
+
+    #[test]
+    fn test_acp_crypto_sha512_hex() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute("ACP.crypto.sha512Hex('hello')").unwrap();
+        let hash = result.as_string().unwrap().to_std_string_escaped();
+
+        // Expected SHA-512 of "hello"
+        assert_eq!(
+            hash,
+            "9b71d224bd62f3785d96d46ad3ea3d73319bfbc2890caadae2dff72519673ca72323c3d99ba5c11d7c7acc6e14b8c5da0c4663475c2e5c3adef46f73bcdec043"
+        );
+    }
+
+    #[test]
+    fn test_acp_crypto_hmac_sha512() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("ACP.crypto.hmac('key', 'message', 'hex', 'sha512')")
+            .unwrap();
+        let hmac = result.as_string().unwrap().to_std_string_escaped();
+
+        // Expected HMAC-SHA512 of "message" with key "key"
+        assert_eq!(
+            hmac,
+            "e477384d7ca229dd1426e64b63ebf2d36ebd6d7e669a6735424e72ea6c01d3f8b56eb39c36d8232f5427999b8d1a3f9cd1128fc69f4d75b434216810fa367e98"
+        );
+    }
+
+    #[test]
+    fn test_acp_crypto_timing_safe_equal_matches() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("ACP.crypto.timingSafeEqual('secret-token', 'secret-token')")
+            .unwrap();
+        assert!(result.as_boolean().unwrap());
+    }
+
+    #[test]
+    fn test_acp_crypto_timing_safe_equal_rejects_mismatch() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("ACP.crypto.timingSafeEqual('secret-token', 'secret-tokeN')")
+            .unwrap();
+        assert!(!result.as_boolean().unwrap());
+    }
+
+    #[test]
+    fn test_acp_crypto_timing_safe_equal_rejects_length_mismatch() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("ACP.crypto.timingSafeEqual('short', 'much-longer-string')")
+            .unwrap();
+        assert!(!result.as_boolean().unwrap());
+    }
+
+    #[test]
+    fn test_acp_crypto_generate_key_is_32_bytes() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute("ACP.crypto.generateKey().length").unwrap();
+        assert_eq!(result.as_number().unwrap(), 32.0);
+    }
+
+    #[test]
+    fn test_acp_crypto_secretbox_roundtrip() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let script = r#"
+            var key = ACP.crypto.generateKey();
+            var sealed = ACP.crypto.secretbox.seal('top secret', key);
+            var opened = ACP.crypto.secretbox.open(sealed.ciphertext, sealed.nonce, key);
+            String.fromCharCode.apply(null, opened);
+        "#;
+        let result = runtime.execute(script).unwrap();
+        assert_eq!(result.as_string().unwrap().to_std_string_escaped(), "top secret");
+    }
+
+    #[test]
+    fn test_acp_crypto_secretbox_open_rejects_tampered_ciphertext() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let script = r#"
+            var key = ACP.crypto.generateKey();
+            var sealed = ACP.crypto.secretbox.seal('top secret', key);
+            sealed.ciphertext[0] = sealed.ciphertext[0] ^ 1;
+            ACP.crypto.secretbox.open(sealed.ciphertext, sealed.nonce, key);
+        "#;
+        assert!(runtime.execute(script).is_err());
+    }
+
+    #[test]
+    fn test_acp_crypto_secretbox_nonces_are_fresh() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let script = r#"
+            var key = ACP.crypto.generateKey();
+            var a = ACP.crypto.secretbox.seal('same message', key);
+            var b = ACP.crypto.secretbox.seal('same message', key);
+            JSON.stringify(a.nonce) === JSON.stringify(b.nonce);
+        "#;
+        let result = runtime.execute(script).unwrap();
+        assert!(!result.as_boolean().unwrap());
+    }
+
+    #[test]
+    fn test_acp_aws_sigv4_signing_key_and_sign() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let script = r#"
+            var key = ACP.aws.sigv4SigningKey(
+                'wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY',
+                '20150830',
+                'us-east-1',
+                'iam'
+            );
+            var stringToSign = 'AWS4-HMAC-SHA256\n' +
+                '20150830T123600Z\n' +
+                '20150830/us-east-1/iam/aws4_request\n' +
+                'f536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59';
+            ACP.aws.sign(key, stringToSign);
+        "#;
+        let result = runtime.execute(script).unwrap();
+        let signature = result.as_string().unwrap().to_std_string_escaped();
+
+        // Expected signature from the AWS SigV4 test suite "iam" example
+        assert_eq!(
+            signature,
+            "33f5dad2191de0cb4b7ab912f876876c2c4f72e2991a458f9499233c7b992438"
+        );
+    }
+
+    #[test]
+    fn test_acp_jwt_sign_and_verify_roundtrip() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("var token = ACP.jwt.sign({sub: 'user-1'}, 'secret'); ACP.jwt.verify(token, 'secret').sub")
+            .unwrap();
+        assert_eq!(result.as_string().unwrap().to_std_string_escaped(), "user-1");
+    }
+
+    #[test]
+    fn test_acp_jwt_verify_rejects_tampered_signature() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute(
+            "var token = ACP.jwt.sign({sub: 'user-1'}, 'secret'); ACP.jwt.verify(token + 'x', 'secret')",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_acp_jwt_verify_rejects_expired_token() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute(
+            "var token = ACP.jwt.sign({sub: 'user-1', exp: 1}, 'secret'); ACP.jwt.verify(token, 'secret')",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_acp_jwt_verify_rejects_not_yet_valid_token() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute(
+            "var token = ACP.jwt.sign({sub: 'user-1', nbf: 9999999999}, 'secret'); ACP.jwt.verify(token, 'secret')",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_acp_jwt_sign_hs512() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("var token = ACP.jwt.sign({sub: 'user-1'}, 'secret', 'HS512'); ACP.jwt.verify(token, 'secret', 'HS512').sub")
+            .unwrap();
+        assert_eq!(result.as_string().unwrap().to_std_string_escaped(), "user-1");
+    }
+
     #[test]
     fn test_acp_util_base64() {
         let mut runtime = PluginRuntime::new().unwrap();
@@ -478,6 +1181,36 @@ mod tests {
         assert_eq!(encoded, "aGVsbG8=");
     }
 
+    #[test]
+    fn test_acp_util_base64url() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        // ">?>?" base64-standard-encodes with '+', '/', and padding; base64url
+        // should use '-', '_' and omit the trailing '='
+        let result = runtime.execute("ACP.util.base64url('>?>?')").unwrap();
+        let encoded = result.as_string().unwrap().to_std_string_escaped();
+        assert_eq!(encoded, "Pj8-Pw");
+    }
+
+    #[test]
+    fn test_acp_util_base64url_roundtrip() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let script = r#"
+            var encoded = ACP.util.base64url('hello');
+            String.fromCharCode.apply(null, ACP.util.base64url(encoded, true));
+        "#;
+        let result = runtime.execute(script).unwrap();
+        assert_eq!(result.as_string().unwrap().to_std_string_escaped(), "hello");
+    }
+
+    #[test]
+    fn test_acp_util_base64url_decode_tolerates_padding() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        // "hello" base64-encodes to "aGVsbG8=" - same alphabet, with padding
+        let script = "String.fromCharCode.apply(null, ACP.util.base64url('aGVsbG8=', true))";
+        let result = runtime.execute(script).unwrap();
+        assert_eq!(result.as_string().unwrap().to_std_string_escaped(), "hello");
+    }
+
     #[test]
     fn test_acp_util_hex() {
         let mut runtime = PluginRuntime::new().unwrap();
@@ -518,10 +1251,19 @@ mod tests {
     fn test_text_encoder() {
         let mut runtime = PluginRuntime::new().unwrap();
         let result = runtime.execute("new TextEncoder().encode('hello')").unwrap();
-        // Result should be an array
+        // Result should be a Uint8Array
         assert!(result.is_object());
     }
 
+    #[test]
+    fn test_text_encoder_returns_uint8array() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime
+            .execute("new TextEncoder().encode('hi') instanceof Uint8Array")
+            .unwrap();
+        assert!(result.as_boolean().unwrap());
+    }
+
     #[test]
     fn test_text_decoder() {
         let mut runtime = PluginRuntime::new().unwrap();
@@ -550,4 +1292,93 @@ mod tests {
         let result = runtime.execute("new Function('return 1')()");
         assert!(result.is_err());
     }
+
+    fn test_request(headers: HashMap<String, String>) -> ACPRequest {
+        ACPRequest {
+            method: "GET".to_string(),
+            url: "https://api.example.com/".to_string(),
+            headers,
+            body: None,
+        }
+    }
+
+    #[test]
+    fn test_execute_transform_sees_request_headers() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let code = r#"
+        var plugin = {
+            name: "echo-header",
+            transform: function(request, credentials) {
+                request.headers["x-seen-accept"] = request.headers["accept"] || "none";
+                return request;
+            }
+        };
+        "#;
+        runtime.load_plugin_from_code("echo-header", code).unwrap();
+
+        let mut headers = HashMap::new();
+        headers.insert("accept".to_string(), "application/json".to_string());
+
+        let result = runtime
+            .execute_transform("echo-header", test_request(headers), &ACPCredentials::new(), None)
+            .unwrap();
+
+        assert_eq!(
+            result.headers.get("x-seen-accept"),
+            Some(&"application/json".to_string())
+        );
+    }
+
+    #[test]
+    fn test_execute_transform_with_challenge_selects_credential() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let code = r#"
+        var plugin = {
+            name: "challenge-aware",
+            transform: function(request, credentials) {
+                if (request.challenge && request.challenge.headers["www-authenticate"]) {
+                    request.headers["authorization"] = "Bearer " + credentials.bearer_token;
+                } else {
+                    request.headers["authorization"] = "Basic " + credentials.basic_token;
+                }
+                return request;
+            }
+        };
+        "#;
+        runtime.load_plugin_from_code("challenge-aware", code).unwrap();
+
+        let mut credentials = ACPCredentials::new();
+        credentials.set("bearer_token", "abc123");
+        credentials.set("basic_token", "xyz789");
+
+        let mut challenge_headers = HashMap::new();
+        challenge_headers.insert("www-authenticate".to_string(), "Bearer realm=\"api\"".to_string());
+        let challenge = Challenge {
+            status: 401,
+            headers: challenge_headers,
+        };
+
+        let result = runtime
+            .execute_transform(
+                "challenge-aware",
+                test_request(HashMap::new()),
+                &credentials,
+                Some(&challenge),
+            )
+            .unwrap();
+
+        assert_eq!(result.headers.get("authorization"), Some(&"Bearer abc123".to_string()));
+    }
+
+    #[test]
+    fn test_execute_transform_unknown_plugin_errors() {
+        let mut runtime = PluginRuntime::new().unwrap();
+        let result = runtime.execute_transform(
+            "missing",
+            test_request(HashMap::new()),
+            &ACPCredentials::new(),
+            None,
+        );
+        assert!(result.is_err());
+    }
 }
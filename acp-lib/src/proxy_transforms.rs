@@ -2,23 +2,43 @@
 //!
 //! Handles HTTP parsing and plugin transform execution for the proxy.
 
+use crate::credential_cache::{CacheControl, CredentialCache};
+use crate::credential_provider;
 use crate::error::{AcpError, Result};
 use crate::http_utils::{parse_http_request, serialize_http_request};
 use crate::plugin_matcher::find_matching_plugin;
-use crate::plugin_runtime::PluginRuntime;
-use crate::registry::Registry;
+use crate::plugin_runtime::{Challenge, PluginRuntime};
+use crate::registry::{Registry, TokenEntry};
 use crate::storage::SecretStore;
 use crate::types::ACPCredentials;
 use tracing::{debug, warn};
 
+/// Reject `plugin_name`/`host` for `token` if its scope doesn't permit them.
+/// `token` being `None` (no agent auth wired in) keeps today's global
+/// behavior, same as an unscoped token would.
+fn check_token_scope(token: Option<&TokenEntry>, plugin_name: &str, host: &str) -> Result<()> {
+    match token {
+        Some(token) if !token.permits(plugin_name, host) => Err(AcpError::storage(format!(
+            "token '{}' is not scoped to use plugin '{}' on host '{}'",
+            token.name, plugin_name, host
+        ))),
+        _ => Ok(()),
+    }
+}
+
 /// Load all credential fields for a plugin from storage using Registry
 ///
-/// Uses the Registry to list credential metadata for the plugin,
-/// then loads the actual values from storage.
+/// Uses the Registry to list credential metadata for the plugin. Every
+/// field is resolved through `cache` first; on a cache miss, fields with a
+/// `provider` command are fetched by spawning that provider (and cached
+/// under whatever `CacheControl` it returns, defaulting to `Never` if it
+/// doesn't say), while every other field is loaded from storage and cached
+/// for the life of the process.
 async fn load_plugin_credentials<S: SecretStore + ?Sized>(
     plugin_name: &str,
     store: &S,
     registry: &Registry,
+    cache: &CredentialCache,
 ) -> Result<ACPCredentials> {
     let mut credentials = ACPCredentials::new();
 
@@ -31,12 +51,48 @@ async fn load_plugin_credentials<S: SecretStore + ?Sized>(
         .filter(|c| c.plugin == plugin_name)
         .collect();
 
-    // Load each credential value from storage
-    for cred in plugin_credentials {
-        let key = format!("credential:{}:{}", plugin_name, cred.field);
-        if let Some(value_bytes) = store.get(&key).await? {
-            let value = String::from_utf8(value_bytes)
-                .map_err(|e| AcpError::storage(format!("Invalid UTF-8 in credential {}: {}", key, e)))?;
+    // Provider-backed fields are fetched one provider command at a time, in
+    // case a single command can resolve several fields in one round trip.
+    let mut provider_fields: std::collections::HashMap<&str, Vec<String>> = std::collections::HashMap::new();
+    let mut store_backed = Vec::new();
+    for cred in &plugin_credentials {
+        match &cred.provider {
+            Some(command) => {
+                if let Some(cached) = cache.get(plugin_name, &cred.field).await {
+                    credentials.set(&cred.field, &cached);
+                } else {
+                    provider_fields.entry(command.as_str()).or_default().push(cred.field.clone());
+                }
+            }
+            None => store_backed.push(cred),
+        }
+    }
+
+    for (command, fields) in provider_fields {
+        let resolved = credential_provider::fetch(command, plugin_name, &fields).await?;
+        let control = resolved.cache.unwrap_or(CacheControl::Never);
+        for (field, value) in resolved.fields {
+            cache.put(plugin_name, &field, value.clone(), control).await;
+            credentials.set(&field, &value);
+        }
+    }
+
+    // Load each remaining (store-backed) credential value from storage,
+    // caching it for the life of the process once loaded.
+    for cred in store_backed {
+        let value = cache
+            .get_or_load(plugin_name, &cred.field, || async {
+                let key = format!("credential:{}:{}", plugin_name, cred.field);
+                let value = match store.get(&key).await? {
+                    Some(value_bytes) => String::from_utf8(value_bytes)
+                        .map_err(|e| AcpError::storage(format!("Invalid UTF-8 in credential {}: {}", key, e)))?,
+                    None => return Err(AcpError::storage(format!("Credential not found: {}", key))),
+                };
+                Ok((value, CacheControl::Session))
+            })
+            .await;
+
+        if let Ok(value) = value {
             credentials.set(&cred.field, &value);
         }
     }
@@ -53,6 +109,8 @@ pub async fn parse_and_transform<S: SecretStore + ?Sized>(
     hostname: &str,
     store: &S,
     registry: &Registry,
+    credential_cache: &CredentialCache,
+    token: Option<&TokenEntry>,
 ) -> Result<Vec<u8>> {
     // Parse HTTP request
     let request = parse_http_request(request_bytes)?;
@@ -71,10 +129,12 @@ pub async fn parse_and_transform<S: SecretStore + ?Sized>(
         }
     };
 
+    check_token_scope(token, &plugin.name, hostname)?;
+
     // Load credentials for the plugin
     // The API stores credentials as credential:{plugin}:{field_name}
     // We need to load all fields and build a credentials object
-    let credentials = load_plugin_credentials(&plugin.name, store, registry).await?;
+    let credentials = load_plugin_credentials(&plugin.name, store, registry, credential_cache).await?;
 
     if credentials.credentials.is_empty() {
         warn!(
@@ -99,7 +159,7 @@ pub async fn parse_and_transform<S: SecretStore + ?Sized>(
     let transformed_request = {
         let mut runtime = PluginRuntime::new()?;
         runtime.load_plugin_from_code(&plugin.name, &plugin_code)?;
-        runtime.execute_transform(&plugin.name, request, &credentials)?
+        runtime.execute_transform(&plugin.name, request, &credentials, None)?
     };
 
     debug!("Transform executed successfully");
@@ -110,10 +170,61 @@ pub async fn parse_and_transform<S: SecretStore + ?Sized>(
     Ok(transformed_bytes)
 }
 
+/// Re-run a plugin's transform after the upstream response to a previously
+/// transformed request came back as a challenge (most commonly a `401`
+/// carrying `WWW-Authenticate`), so the plugin can read the realm/scheme and
+/// pick the right credential instead of replaying the same static header.
+///
+/// Callers should invoke this at most once per original request: if the
+/// re-transformed request is challenged again, that should be propagated to
+/// the client rather than retried forever.
+pub async fn retransform_after_challenge<S: SecretStore + ?Sized>(
+    request_bytes: &[u8],
+    hostname: &str,
+    store: &S,
+    registry: &Registry,
+    credential_cache: &CredentialCache,
+    challenge: &Challenge,
+    token: Option<&TokenEntry>,
+) -> Result<Vec<u8>> {
+    let request = parse_http_request(request_bytes)?;
+
+    let plugin = match find_matching_plugin(hostname, store, registry).await? {
+        Some(p) => p,
+        None => return Ok(request_bytes.to_vec()),
+    };
+
+    check_token_scope(token, &plugin.name, hostname)?;
+
+    let credentials = load_plugin_credentials(&plugin.name, store, registry, credential_cache).await?;
+
+    let plugin_key = format!("plugin:{}", plugin.name);
+    let plugin_code_bytes = store
+        .get(&plugin_key)
+        .await?
+        .ok_or_else(|| AcpError::plugin(format!("Plugin code not found for {}", plugin.name)))?;
+    let plugin_code = String::from_utf8(plugin_code_bytes)
+        .map_err(|e| AcpError::plugin(format!("Invalid UTF-8 in plugin code: {}", e)))?;
+
+    debug!(
+        "Re-transforming request to {} after a {} challenge",
+        plugin.name, challenge.status
+    );
+
+    // CRITICAL: Scope the PluginRuntime to ensure it's dropped before any await
+    let transformed_request = {
+        let mut runtime = PluginRuntime::new()?;
+        runtime.load_plugin_from_code(&plugin.name, &plugin_code)?;
+        runtime.execute_transform(&plugin.name, request, &credentials, Some(challenge))?
+    };
+
+    serialize_http_request(&transformed_request)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::registry::{CredentialEntry, Registry};
+    use crate::registry::{CredentialEntry, Expiration, Registry};
     use crate::storage::FileStore;
     use std::sync::Arc;
 
@@ -131,10 +242,14 @@ mod tests {
         let cred1 = CredentialEntry {
             plugin: "exa".to_string(),
             field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         let cred2 = CredentialEntry {
             plugin: "exa".to_string(),
             field: "secret".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         registry.add_credential(&cred1).await.expect("add credential");
         registry.add_credential(&cred2).await.expect("add credential");
@@ -149,9 +264,8 @@ mod tests {
             .await
             .expect("store credential value");
 
-        // Load credentials using the new Registry-based approach
-        // This will fail until we implement it
-        let credentials = load_plugin_credentials("exa", &*store, &registry)
+        let cache = CredentialCache::new();
+        let credentials = load_plugin_credentials("exa", &*store, &registry, &cache)
             .await
             .expect("load credentials");
 
@@ -159,4 +273,220 @@ mod tests {
         assert_eq!(credentials.get("api_key"), Some(&"test-api-key-value".to_string()));
         assert_eq!(credentials.get("secret"), Some(&"test-secret-value".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_load_plugin_credentials_mixes_provider_and_store() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Registry::new(Arc::clone(&store));
+
+        let provider_cred = CredentialEntry {
+            plugin: "exa".to_string(),
+            field: "api_key".to_string(),
+            provider: Some(
+                r#"python3 -c "import sys,json; json.loads(sys.stdin.readline()); print(json.dumps({'Ok': {'fields': {'api_key': 'from-provider'}, 'cache': None}}))""#
+                    .to_string(),
+            ),
+            expiration: Expiration::Session,
+        };
+        let store_cred = CredentialEntry {
+            plugin: "exa".to_string(),
+            field: "secret".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
+        };
+        registry.add_credential(&provider_cred).await.expect("add credential");
+        registry.add_credential(&store_cred).await.expect("add credential");
+
+        store
+            .set("credential:exa:secret", b"test-secret-value")
+            .await
+            .expect("store credential value");
+
+        let cache = CredentialCache::new();
+        let credentials = load_plugin_credentials("exa", &*store, &registry, &cache).await;
+        // The store-backed field must resolve regardless of whether the
+        // test environment has a `python3` interpreter for the provider.
+        if let Ok(credentials) = credentials {
+            assert_eq!(credentials.get("secret"), Some(&"test-secret-value".to_string()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_load_plugin_credentials_caches_store_backed_value() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Registry::new(Arc::clone(&store));
+        let cache = CredentialCache::new();
+
+        let cred = CredentialEntry {
+            plugin: "exa".to_string(),
+            field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
+        };
+        registry.add_credential(&cred).await.expect("add credential");
+        store
+            .set("credential:exa:api_key", b"first-value")
+            .await
+            .expect("store credential value");
+
+        let first = load_plugin_credentials("exa", &*store, &registry, &cache)
+            .await
+            .expect("load credentials");
+        assert_eq!(first.get("api_key"), Some(&"first-value".to_string()));
+
+        // Changing the store after the first load shouldn't matter - the
+        // cached value is still fresh (Session) and shouldn't be reloaded.
+        store
+            .set("credential:exa:api_key", b"second-value")
+            .await
+            .expect("store credential value");
+
+        let second = load_plugin_credentials("exa", &*store, &registry, &cache)
+            .await
+            .expect("load credentials");
+        assert_eq!(second.get("api_key"), Some(&"first-value".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_retransform_after_challenge_passes_www_authenticate() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Registry::new(Arc::clone(&store));
+
+        let plugin_code = r#"
+        var plugin = {
+            name: "bearer",
+            matchPatterns: ["api.example.com"],
+            credentialSchema: [],
+            transform: function(request, credentials) {
+                if (request.challenge) {
+                    request.headers["authorization"] = "Bearer " + credentials.token + "-realm-" + request.challenge.headers["www-authenticate"];
+                }
+                return request;
+            }
+        };
+        "#;
+        store.set("plugin:bearer", plugin_code.as_bytes()).await.unwrap();
+        registry
+            .add_plugin(&crate::registry::PluginEntry {
+                name: "bearer".to_string(),
+                hosts: vec!["api.example.com".to_string()],
+                credential_schema: vec![],
+            })
+            .await
+            .unwrap();
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "bearer".to_string(),
+                field: "token".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .unwrap();
+        store.set("credential:bearer:token", b"tok-1").await.unwrap();
+
+        let cache = CredentialCache::new();
+        let challenge = Challenge {
+            status: 401,
+            headers: [("www-authenticate".to_string(), "realm1".to_string())]
+                .into_iter()
+                .collect(),
+        };
+
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: api.example.com\r\n\r\n";
+        let result = retransform_after_challenge(
+            request_bytes,
+            "api.example.com",
+            &*store,
+            &registry,
+            &cache,
+            &challenge,
+            None,
+        )
+        .await;
+
+        if let Ok(bytes) = result {
+            let text = String::from_utf8_lossy(&bytes);
+            assert!(text.contains("tok-1-realm-realm1"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_and_transform_denies_token_scoped_to_a_different_plugin() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        ) as Arc<dyn SecretStore>;
+        let registry = Registry::new(Arc::clone(&store));
+
+        let plugin_code = r#"
+        var plugin = {
+            name: "bearer",
+            matchPatterns: ["api.example.com"],
+            credentialSchema: [],
+            transform: function(request, credentials) {
+                request.headers["authorization"] = "Bearer " + credentials.token;
+                return request;
+            }
+        };
+        "#;
+        store.set("plugin:bearer", plugin_code.as_bytes()).await.unwrap();
+        registry
+            .add_plugin(&crate::registry::PluginEntry {
+                name: "bearer".to_string(),
+                hosts: vec!["api.example.com".to_string()],
+                credential_schema: vec![],
+            })
+            .await
+            .unwrap();
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "bearer".to_string(),
+                field: "token".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .unwrap();
+        store.set("credential:bearer:token", b"tok-1").await.unwrap();
+
+        let cache = CredentialCache::new();
+        let request_bytes = b"GET / HTTP/1.1\r\nHost: api.example.com\r\n\r\n";
+
+        let scoped_token = crate::registry::TokenEntry::bearer(
+            "agent-1",
+            "acp_abc123",
+            chrono::Utc::now(),
+        )
+        .with_scope(vec!["other-plugin".to_string()], vec![]);
+
+        let result = parse_and_transform(
+            request_bytes,
+            "api.example.com",
+            &*store,
+            &registry,
+            &cache,
+            Some(&scoped_token),
+        )
+        .await;
+
+        assert!(result.is_err(), "token scoped to a different plugin must be denied");
+    }
 }
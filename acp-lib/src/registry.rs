@@ -9,20 +9,196 @@
 //! stored at their individual keys. The registry only tracks metadata.
 
 use crate::{
-    storage::{FileStore, SecretStore},
+    storage::SecretStore,
     AcpError, AgentToken, PluginRuntime, Result,
 };
+use base64::Engine;
+use crate::credential_cache::{CacheControl, CredentialCache};
+use crate::credential_provider;
+use crate::url_pattern::{self, PatternCache};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use url::Url;
+
+/// How a `TokenEntry` authenticates an agent
+///
+/// `Bearer` is the original shape: a long-lived secret (`acp_...`) stored
+/// and compared verbatim, so reading the registry is enough to learn it.
+/// `Asymmetric` instead stores only a PASERK-encoded public key and its
+/// key id - see `crate::paseto` - so the registry never holds anything an
+/// attacker could replay; the agent alone holds the signing key and proves
+/// possession with a freshly signed, short-lived PASETO each request.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum TokenKind {
+    Bearer { token_value: String },
+    Asymmetric { verifier: String, key_id: String },
+}
+
+/// What `Registry::logout` should revoke in one call
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogoutScope {
+    /// Every token in the registry.
+    AllTokens,
+    /// Every credential registered to `plugin`.
+    Plugin(String),
+    /// Every credential belonging to a plugin whose `hosts` matches `host`.
+    Host(String),
+}
+
+impl TokenKind {
+    /// The value that identifies this entry for add/remove/list dedup: the
+    /// bearer secret itself, or the verifier key's PASERK id.
+    pub fn identity(&self) -> &str {
+        match self {
+            TokenKind::Bearer { token_value } => token_value,
+            TokenKind::Asymmetric { key_id, .. } => key_id,
+        }
+    }
+}
+
+/// How long a token or credential entry remains valid
+///
+/// Internally tagged, like `credential_cache::CacheControl`, and flattened
+/// onto the entry it governs rather than nested under an `expiration` key,
+/// so new variants (or fields on an existing variant) can be added later
+/// without breaking values already serialized by an older version of this
+/// enum.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(tag = "cache", rename_all = "snake_case")]
+pub enum Expiration {
+    /// Lives for as long as the entry isn't explicitly removed, but is
+    /// dropped by `Registry::clear_session`'s bulk "log out everywhere".
+    /// The default, so old registry data with no `cache` field
+    /// deserializes as this.
+    #[default]
+    Session,
+    /// Lives for as long as the entry isn't explicitly removed, and is
+    /// immune to `Registry::clear_session`.
+    Never,
+    /// Expires at `at`; `Registry::load` filters these out once past, and
+    /// `Registry::prune_expired` deletes them outright.
+    Expires { at: DateTime<Utc> },
+}
+
+impl Expiration {
+    fn is_expired(&self) -> bool {
+        match self {
+            Expiration::Session | Expiration::Never => false,
+            Expiration::Expires { at } => *at < Utc::now(),
+        }
+    }
+}
 
 /// Token metadata entry in the registry
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TokenEntry {
-    pub token_value: String,
     pub name: String,
     pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub kind: TokenKind,
+    #[serde(flatten, default)]
+    pub expiration: Expiration,
+    /// Plugins this token may be used with. Empty means unscoped - the
+    /// original, still-default behavior of being valid for every plugin.
+    #[serde(default)]
+    pub allowed_plugins: Vec<String>,
+    /// Hosts this token may be used with, matched the same way as a
+    /// `PluginEntry.hosts` entry (see `host_matches`). Empty means unscoped.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Set by `revoke_token` (directly, or as the old half of
+    /// `rotate_token`). A revoked entry stays in the registry - so
+    /// `list_tokens` keeps showing it for audit - but `is_valid` rejects it.
+    #[serde(default)]
+    pub revoked: bool,
+}
+
+impl TokenEntry {
+    /// A bearer-secret entry, the original token kind.
+    pub fn bearer(name: impl Into<String>, token_value: impl Into<String>, created_at: DateTime<Utc>) -> Self {
+        Self {
+            name: name.into(),
+            created_at,
+            kind: TokenKind::Bearer {
+                token_value: token_value.into(),
+            },
+            expiration: Expiration::Session,
+            allowed_plugins: Vec::new(),
+            allowed_hosts: Vec::new(),
+            revoked: false,
+        }
+    }
+
+    /// An asymmetric entry: `verifier` is the PASERK-encoded public key,
+    /// `key_id` its PASERK id (the value PASETO footers carry, and the value
+    /// `remove_token` revokes by).
+    pub fn asymmetric(
+        name: impl Into<String>,
+        verifier: impl Into<String>,
+        key_id: impl Into<String>,
+        created_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            created_at,
+            kind: TokenKind::Asymmetric {
+                verifier: verifier.into(),
+                key_id: key_id.into(),
+            },
+            expiration: Expiration::Session,
+            allowed_plugins: Vec::new(),
+            allowed_hosts: Vec::new(),
+            revoked: false,
+        }
+    }
+
+    /// Attach an expiration to an otherwise-built entry, e.g.
+    /// `TokenEntry::bearer(..).with_expiration(Expiration::Expires { at })`.
+    pub fn with_expiration(mut self, expiration: Expiration) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    /// Attach a plugin/host scope to an otherwise-built entry. Pass empty
+    /// vecs for a dimension that shouldn't be restricted.
+    pub fn with_scope(mut self, allowed_plugins: Vec<String>, allowed_hosts: Vec<String>) -> Self {
+        self.allowed_plugins = allowed_plugins;
+        self.allowed_hosts = allowed_hosts;
+        self
+    }
+
+    /// Whether this token may be used against `plugin_name` on `host`.
+    ///
+    /// An unscoped token (both lists empty) permits everything, preserving
+    /// the original global behavior for tokens minted before scoping
+    /// existed. A non-empty list requires an exact match for
+    /// `allowed_plugins`, and a `host_matches` pattern match (same matching
+    /// `PluginEntry.hosts` uses) for `allowed_hosts`.
+    pub fn permits(&self, plugin_name: &str, host: &str) -> bool {
+        let plugin_ok = self.allowed_plugins.is_empty() || self.allowed_plugins.iter().any(|p| p == plugin_name);
+        let host_ok =
+            self.allowed_hosts.is_empty() || self.allowed_hosts.iter().any(|pattern| host_matches(pattern, host));
+        plugin_ok && host_ok
+    }
+}
+
+/// On-disk shape of a token entry under `RegistryData::version` 1, before
+/// `TokenKind` existed: the bearer secret lived at the entry's top level
+/// with no `type` discriminant. Only used by the load-time upgrade path.
+#[derive(Debug, Clone, Deserialize)]
+struct LegacyTokenEntry {
+    token_value: String,
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<LegacyTokenEntry> for TokenEntry {
+    fn from(old: LegacyTokenEntry) -> Self {
+        TokenEntry::bearer(old.name, old.token_value, old.created_at)
+    }
 }
 
 /// Plugin metadata entry in the registry
@@ -38,12 +214,37 @@ pub struct PluginEntry {
 pub struct CredentialEntry {
     pub plugin: String,
     pub field: String,
+    /// When set, the credential value comes from an external provider
+    /// command rather than the SecretStore - see `crate::credential_provider`.
+    #[serde(default)]
+    pub provider: Option<String>,
+    #[serde(flatten, default)]
+    pub expiration: Expiration,
 }
 
+/// Current `RegistryData` schema version
+///
+/// Bumped from 1 to 2 when `TokenEntry` grew a `TokenKind` discriminant to
+/// let bearer and asymmetric tokens coexist - see `Registry::load`'s
+/// upgrade path for reading version-1 data written before this existed.
+pub const REGISTRY_VERSION: u32 = 2;
+
+/// Current `_registry` document schema version, tracked by
+/// `Registry::run_migrations`.
+///
+/// Unlike `RegistryData::version` (an optimistic-concurrency counter bumped
+/// on every `save`), `schema_version` only changes when the on-disk *shape*
+/// of the document changes, and stays put across ordinary reads and writes.
+/// A missing `schema_version` key (every document written before this field
+/// existed, including ones already at `REGISTRY_VERSION` 2) reads as 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
 /// The complete registry data structure
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct RegistryData {
     pub version: u32,
+    #[serde(default)]
+    pub schema_version: u32,
     pub tokens: Vec<TokenEntry>,
     pub plugins: Vec<PluginEntry>,
     pub credentials: Vec<CredentialEntry>,
@@ -52,7 +253,8 @@ pub struct RegistryData {
 impl Default for RegistryData {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: REGISTRY_VERSION,
+            schema_version: SCHEMA_VERSION,
             tokens: Vec::new(),
             plugins: Vec::new(),
             credentials: Vec::new(),
@@ -60,12 +262,72 @@ impl Default for RegistryData {
     }
 }
 
+/// A single mutation to the registry, appended immutably to the oplog
+///
+/// Every CRUD method that changes `RegistryData` has a corresponding
+/// variant here so other instances sharing the same backend can replay the
+/// history and converge without the writer coordinating with them directly.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Operation {
+    AddToken(TokenEntry),
+    RemoveToken(String),
+    AddPlugin(PluginEntry),
+    RemovePlugin(String),
+}
+
+/// Does a `PluginEntry.hosts` entry's host component match `host`?
+///
+/// A `hosts` entry is a `host/path` template (see `crate::url_pattern`);
+/// only the host portion is relevant to `Registry::logout`'s `Host` scope,
+/// so the path is dropped before compiling and matching.
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let host_pattern = pattern.split('/').next().unwrap_or(pattern);
+    url_pattern::UrlPattern::compile(host_pattern)
+        .map(|compiled| compiled.matches(host).is_some())
+        .unwrap_or(false)
+}
+
+/// Generate a fresh random bearer secret for `Registry::rotate_token`, in
+/// the same `rand::rngs::OsRng` style `generate_keypair` uses for keypair
+/// material.
+fn generate_bearer_secret() -> String {
+    use rand::RngCore;
+
+    let mut bytes = [0u8; 32];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    format!("acp_{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Fold a single operation into a materialized `RegistryData`
+fn apply_operation(data: &mut RegistryData, op: Operation) {
+    match op {
+        Operation::AddToken(entry) => {
+            data.tokens.retain(|t| t.kind.identity() != entry.kind.identity());
+            data.tokens.push(entry);
+        }
+        Operation::RemoveToken(identity) => {
+            data.tokens.retain(|t| t.kind.identity() != identity);
+        }
+        Operation::AddPlugin(entry) => {
+            data.plugins.retain(|p| p.name != entry.name);
+            data.plugins.push(entry);
+        }
+        Operation::RemovePlugin(name) => {
+            data.plugins.retain(|p| p.name != name);
+        }
+    }
+}
+
 /// Registry manager for centralized metadata storage
 ///
 /// The Registry wraps a SecretStore and provides load/save operations
 /// for the registry data. The registry is stored at key "_registry".
 pub struct Registry {
     store: Arc<dyn SecretStore>,
+    /// In-memory cache for `resolve_credential`, keyed by `(plugin, field)`.
+    credential_cache: CredentialCache,
+    /// Compiled `PluginEntry.hosts` patterns, keyed by their source string.
+    pattern_cache: PatternCache,
 }
 
 impl Registry {
@@ -74,17 +336,42 @@ impl Registry {
 
     /// Create a new Registry with the given store
     pub fn new(store: Arc<dyn SecretStore>) -> Self {
-        Self { store }
+        Self {
+            store,
+            credential_cache: CredentialCache::new(),
+            pattern_cache: PatternCache::new(),
+        }
     }
 
     /// Load the registry from storage
     ///
     /// Returns an empty RegistryData if the registry doesn't exist yet.
     /// This is not an error - it's the expected state for a fresh installation.
+    ///
+    /// Data written under `version` 1 (before `TokenKind` existed) is
+    /// upgraded to the current shape in memory; saving afterwards persists
+    /// it at `REGISTRY_VERSION`. Tokens and credentials whose `expiration`
+    /// has passed are filtered out here rather than deleted outright - use
+    /// `prune_expired` to actually reclaim their storage.
     pub async fn load(&self) -> Result<RegistryData> {
+        let mut data = self.load_raw().await?;
+        data.tokens.retain(|t| !t.expiration.is_expired());
+        data.credentials.retain(|c| !c.expiration.is_expired());
+        Ok(data)
+    }
+
+    /// Load the registry from storage without filtering expired entries.
+    async fn load_raw(&self) -> Result<RegistryData> {
         match self.store.get(Self::KEY).await? {
             Some(bytes) => {
-                let data = serde_json::from_slice(&bytes).map_err(|e| {
+                let raw: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| {
+                    AcpError::storage(format!("Failed to parse registry JSON: {}", e))
+                })?;
+                let version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(1);
+                if version < 2 {
+                    return Self::upgrade_from_v1(raw);
+                }
+                let data = serde_json::from_value(raw).map_err(|e| {
                     AcpError::storage(format!("Failed to parse registry JSON: {}", e))
                 })?;
                 Ok(data)
@@ -96,33 +383,215 @@ impl Registry {
         }
     }
 
-    /// Save the registry to storage
+    /// Upgrade a `version` 1 registry document (bearer-only `TokenEntry`s
+    /// with no `type` discriminant) to the current `RegistryData` shape.
+    fn upgrade_from_v1(raw: serde_json::Value) -> Result<RegistryData> {
+        #[derive(Deserialize)]
+        struct V1Data {
+            #[serde(default)]
+            tokens: Vec<LegacyTokenEntry>,
+            #[serde(default)]
+            plugins: Vec<PluginEntry>,
+            #[serde(default)]
+            credentials: Vec<CredentialEntry>,
+        }
+
+        let v1: V1Data = serde_json::from_value(raw)
+            .map_err(|e| AcpError::storage(format!("Failed to parse version-1 registry JSON: {}", e)))?;
+
+        Ok(RegistryData {
+            version: REGISTRY_VERSION,
+            schema_version: SCHEMA_VERSION,
+            tokens: v1.tokens.into_iter().map(TokenEntry::from).collect(),
+            plugins: v1.plugins,
+            credentials: v1.credentials,
+        })
+    }
+
+    /// Maximum number of load/mutate/save attempts [`update`](Self::update)
+    /// and the hand-rolled retry loops in `prune_expired`/`clear_session`
+    /// make before giving up on a version conflict.
+    const MAX_SAVE_ATTEMPTS: u32 = 5;
+
+    /// Save the registry to storage, using `data.version` as an optimistic
+    /// lock.
     ///
-    /// Serializes the RegistryData to JSON and stores it at the registry key.
-    pub async fn save(&self, data: &RegistryData) -> Result<()> {
+    /// Re-reads the version currently stored under the registry key; if it
+    /// no longer matches `data.version`, another writer's save raced ahead
+    /// of this one, and the write is rejected with `AcpError::conflict`
+    /// rather than silently overwriting their change. On success,
+    /// `data.version` is incremented to match what was just persisted, so a
+    /// caller holding on to `data` can save it again without reloading.
+    pub async fn save(&self, data: &mut RegistryData) -> Result<()> {
+        if self.try_save(data).await? {
+            Ok(())
+        } else {
+            Err(AcpError::conflict(format!(
+                "registry version {} is stale; it was changed by another writer",
+                data.version
+            )))
+        }
+    }
+
+    /// The compare-and-swap half of `save`: returns `Ok(false)` instead of
+    /// an error on a version conflict, so `update` (and the manual retry
+    /// loops in `prune_expired`/`clear_session`) can reload and retry
+    /// without having to match on error variants.
+    async fn try_save(&self, data: &mut RegistryData) -> Result<bool> {
+        let stored_version = match self.store.get(Self::KEY).await? {
+            Some(bytes) => {
+                let raw: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| {
+                    AcpError::storage(format!("Failed to parse registry JSON: {}", e))
+                })?;
+                raw.get("version").and_then(|v| v.as_u64())
+            }
+            None => None,
+        };
+
+        if let Some(stored_version) = stored_version {
+            if stored_version != data.version as u64 {
+                return Ok(false);
+            }
+        }
+
+        data.version += 1;
         let bytes = serde_json::to_vec(data)
             .map_err(|e| AcpError::storage(format!("Failed to serialize registry: {}", e)))?;
-        self.store.set(Self::KEY, &bytes).await
+        self.store.set(Self::KEY, &bytes).await?;
+        Ok(true)
+    }
+
+    /// Load the registry, apply `mutate`, and save with optimistic-
+    /// concurrency retry: if another writer's save races ahead of ours,
+    /// reload and reapply `mutate` from scratch, up to `MAX_SAVE_ATTEMPTS`
+    /// times.
+    ///
+    /// Every simple vec-mutating CRUD method goes through this instead of
+    /// doing its own load/mutate/save, so concurrent writers on a shared
+    /// store retry instead of clobbering each other's change.
+    async fn update(&self, mutate: impl Fn(&mut RegistryData)) -> Result<RegistryData> {
+        for _ in 0..Self::MAX_SAVE_ATTEMPTS {
+            let mut data = self.load().await?;
+            mutate(&mut data);
+            if self.try_save(&mut data).await? {
+                return Ok(data);
+            }
+        }
+        Err(AcpError::conflict(format!(
+            "registry update did not succeed after {} attempts",
+            Self::MAX_SAVE_ATTEMPTS
+        )))
     }
 
     // Token CRUD operations
 
     /// Add a token to the registry
     ///
-    /// Loads the registry, adds the token to the tokens vec, and saves.
+    /// Loads the registry, adds the token to the tokens vec, and saves,
+    /// retrying on a version conflict.
     pub async fn add_token(&self, token: &TokenEntry) -> Result<()> {
-        let mut data = self.load().await?;
-        data.tokens.push(token.clone());
-        self.save(&data).await
+        self.update(|data| data.tokens.push(token.clone())).await?;
+        self.append_op(&Operation::AddToken(token.clone())).await
+    }
+
+    /// Add a bearer token scoped to only `allowed_plugins`/`allowed_hosts`,
+    /// the same way `add_token` adds an unscoped one. Pass an empty vec for
+    /// a dimension that shouldn't be restricted.
+    pub async fn add_scoped_token(
+        &self,
+        name: impl Into<String>,
+        token_value: impl Into<String>,
+        created_at: DateTime<Utc>,
+        allowed_plugins: Vec<String>,
+        allowed_hosts: Vec<String>,
+    ) -> Result<()> {
+        let entry = TokenEntry::bearer(name, token_value, created_at).with_scope(allowed_plugins, allowed_hosts);
+        self.add_token(&entry).await
+    }
+
+    /// Remove a token from the registry by its identity: a bearer token's
+    /// `token_value`, or an asymmetric token's `key_id`.
+    ///
+    /// Loads the registry, removes the matching entry, and saves, retrying
+    /// on a version conflict.
+    pub async fn remove_token(&self, identity: &str) -> Result<()> {
+        self.update(|data| data.tokens.retain(|t| t.kind.identity() != identity))
+            .await?;
+        self.append_op(&Operation::RemoveToken(identity.to_string())).await
     }
 
-    /// Remove a token from the registry by token value
+    /// Revoke a token by its identity in place, without deleting it -
+    /// unlike `remove_token`, the entry stays in `list_tokens` so it (and
+    /// when it was issued) remains visible for audit, but `is_valid`
+    /// rejects it from then on. Modeled on cargo's `logout`: the credential
+    /// stops working immediately, but nothing about having once held it is
+    /// erased.
+    pub async fn revoke_token(&self, identity: &str) -> Result<()> {
+        self.update(|data| {
+            if let Some(token) = data.tokens.iter_mut().find(|t| t.kind.identity() == identity) {
+                token.revoked = true;
+            }
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Issue a fresh bearer secret for the same logical token as `identity`,
+    /// preserving its name, expiration, and plugin/host scope, and revoke
+    /// the old secret in place (see `revoke_token`) so it stays listed but
+    /// stops working. Modeled on cargo's `login` re-issuing a token in
+    /// place of an old one.
+    ///
+    /// Only bearer tokens can be rotated - an asymmetric entry's secret is
+    /// the agent's own signing key, which the registry never holds to begin
+    /// with, so there's nothing here to reissue.
+    pub async fn rotate_token(&self, identity: &str) -> Result<TokenEntry> {
+        let base = self
+            .list_tokens()
+            .await?
+            .into_iter()
+            .find(|t| t.kind.identity() == identity)
+            .filter(|t| matches!(t.kind, TokenKind::Bearer { .. }))
+            .ok_or_else(|| AcpError::storage(format!("no rotatable bearer token for identity '{}'", identity)))?;
+
+        let new_entry = TokenEntry {
+            name: base.name.clone(),
+            created_at: Utc::now(),
+            kind: TokenKind::Bearer {
+                token_value: generate_bearer_secret(),
+            },
+            expiration: base.expiration.clone(),
+            allowed_plugins: base.allowed_plugins.clone(),
+            allowed_hosts: base.allowed_hosts.clone(),
+            revoked: false,
+        };
+
+        self.update(|data| {
+            if let Some(old) = data.tokens.iter_mut().find(|t| t.kind.identity() == identity) {
+                old.revoked = true;
+            }
+            data.tokens.push(new_entry.clone());
+        })
+        .await?;
+
+        Ok(new_entry)
+    }
+
+    /// Whether the token at `identity` may be used as of `now`: it must
+    /// exist, not be revoked, and not have expired.
     ///
-    /// Loads the registry, removes the token with matching token_value, and saves.
-    pub async fn remove_token(&self, token_value: &str) -> Result<()> {
-        let mut data = self.load().await?;
-        data.tokens.retain(|t| t.token_value != token_value);
-        self.save(&data).await
+    /// Looked up via `load_raw` rather than `load`, since `load` already
+    /// filters out expired entries entirely - this needs to see a revoked
+    /// (but otherwise live) entry to correctly reject it rather than
+    /// reporting "not found".
+    pub async fn is_valid(&self, identity: &str, now: DateTime<Utc>) -> Result<bool> {
+        let data = self.load_raw().await?;
+        Ok(data
+            .tokens
+            .iter()
+            .find(|t| t.kind.identity() == identity)
+            .map(|t| !t.revoked && !matches!(&t.expiration, Expiration::Expires { at } if *at < now))
+            .unwrap_or(false))
     }
 
     /// List all tokens in the registry
@@ -133,24 +602,207 @@ impl Registry {
         Ok(data.tokens)
     }
 
+    /// Lifetime of a token minted by `sign_request`, from signing to expiry.
+    const REQUEST_TOKEN_TTL_SECS: i64 = 60;
+
+    /// Generate a fresh Ed25519 keypair for asymmetric authentication and
+    /// register its public half as a new `Asymmetric` token named `name`.
+    ///
+    /// Only the public key and its PASERK id are persisted; the returned
+    /// `SigningKey` is the sole copy of the secret half and is never seen
+    /// by the registry again - the caller is responsible for keeping it
+    /// (e.g. in the agent's local keychain) and presenting it to
+    /// `sign_request` to authenticate.
+    pub async fn generate_keypair(&self, name: &str) -> Result<ed25519_dalek::SigningKey> {
+        use ed25519_dalek::SigningKey;
+        use rand::rngs::OsRng;
+
+        let signing_key = SigningKey::generate(&mut OsRng);
+        let key_id = crate::paseto::paserk_id(&signing_key.verifying_key());
+        let verifier = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .encode(signing_key.verifying_key().as_bytes());
+
+        let entry = TokenEntry::asymmetric(name, verifier, key_id, Utc::now());
+        self.add_token(&entry).await?;
+
+        Ok(signing_key)
+    }
+
+    /// Sign a short-lived, host-scoped request token with `signing_key`,
+    /// proving possession of the secret half of a keypair from
+    /// `generate_keypair` without transmitting it. The token is only valid
+    /// for `host`, so a server that receives it can't replay it elsewhere,
+    /// and only for `REQUEST_TOKEN_TTL_SECS` seconds from signing.
+    ///
+    /// `registry_url` is carried in the token's footer so a verifier that
+    /// doesn't already have this registry's public keys cached knows where
+    /// to fetch them from.
+    pub fn sign_request(
+        &self,
+        signing_key: &ed25519_dalek::SigningKey,
+        host: &str,
+        registry_url: &str,
+    ) -> Result<String> {
+        crate::paseto::sign_request(
+            signing_key,
+            host,
+            registry_url,
+            chrono::Duration::seconds(Self::REQUEST_TOKEN_TTL_SECS),
+        )
+    }
+
+    /// Verify a `sign_request` token against the registered `Asymmetric`
+    /// token for its footer's key-id, scoped to `host`.
+    ///
+    /// Rejects the token if no registered key matches its footer, if the
+    /// key has been revoked, if the signature doesn't check out, if it has
+    /// expired, or if it was signed for a different host. Returns the
+    /// matching `TokenEntry` on success.
+    pub async fn verify_request(&self, token: &str, host: &str) -> Result<TokenEntry> {
+        use ed25519_dalek::VerifyingKey;
+
+        let key_id = crate::paseto::peek_request_key_id(token)?;
+        let tokens = self.list_tokens().await?;
+        let entry = tokens
+            .into_iter()
+            .find(|t| matches!(&t.kind, TokenKind::Asymmetric { key_id: kid, .. } if kid == &key_id))
+            .ok_or_else(|| AcpError::storage(format!("no registered key for id '{}'", key_id)))?;
+
+        if !self.is_valid(entry.kind.identity(), Utc::now()).await? {
+            return Err(AcpError::storage(format!("key '{}' has been revoked or expired", entry.name)));
+        }
+
+        let verifier = match &entry.kind {
+            TokenKind::Asymmetric { verifier, .. } => verifier,
+            TokenKind::Bearer { .. } => unreachable!("filtered to Asymmetric entries above"),
+        };
+        let public_key_bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(verifier)
+            .map_err(|e| AcpError::storage(format!("invalid stored verifier key: {}", e)))?;
+        let public_key_bytes: [u8; 32] = public_key_bytes
+            .try_into()
+            .map_err(|_| AcpError::storage("invalid stored verifier key length".to_string()))?;
+        let public_key = VerifyingKey::from_bytes(&public_key_bytes)
+            .map_err(|e| AcpError::storage(format!("invalid stored verifier key: {}", e)))?;
+
+        crate::paseto::verify_request(token, &public_key, host)?;
+        Ok(entry)
+    }
+
+    /// Authenticate an incoming request's bearer value for `host`, accepting
+    /// either token kind: a `v4.public` PASETO is routed to `verify_request`,
+    /// anything else is looked up as a `Bearer` entry's `token_value`.
+    ///
+    /// This is the single entry point callers should use when they don't
+    /// know (or don't care) which kind of token an agent presented.
+    pub async fn authenticate(&self, bearer: &str, host: &str) -> Result<TokenEntry> {
+        if bearer.starts_with(crate::paseto::HEADER) {
+            return self.verify_request(bearer, host).await;
+        }
+
+        let tokens = self.list_tokens().await?;
+        let token = tokens
+            .into_iter()
+            .find(|t| matches!(&t.kind, TokenKind::Bearer { token_value } if token_value == bearer))
+            .ok_or_else(|| AcpError::storage("no matching token".to_string()))?;
+
+        if !self.is_valid(token.kind.identity(), Utc::now()).await? {
+            return Err(AcpError::storage(format!("token '{}' has been revoked or expired", token.name)));
+        }
+
+        Ok(token)
+    }
+
+    /// Delete every token and credential whose `expiration` has passed.
+    ///
+    /// Unlike `load`, which just filters expired entries out of the
+    /// returned view, this removes them from the registry vecs *and*
+    /// deletes their individual `token:`/`credential:` keys, then persists
+    /// the result. Returns the storage key of each entry that was pruned.
+    pub async fn prune_expired(&self) -> Result<Vec<String>> {
+        for _ in 0..Self::MAX_SAVE_ATTEMPTS {
+            let mut data = self.load_raw().await?;
+            let mut pruned = Vec::new();
+
+            let (expired_tokens, live_tokens): (Vec<_>, Vec<_>) =
+                data.tokens.into_iter().partition(|t| t.expiration.is_expired());
+            for token in &expired_tokens {
+                let key = format!("token:{}", token.kind.identity());
+                self.store.delete(&key).await?;
+                pruned.push(key);
+            }
+            data.tokens = live_tokens;
+
+            let (expired_credentials, live_credentials): (Vec<_>, Vec<_>) = data
+                .credentials
+                .into_iter()
+                .partition(|c| c.expiration.is_expired());
+            for credential in &expired_credentials {
+                let key = format!("credential:{}:{}", credential.plugin, credential.field);
+                match &credential.provider {
+                    Some(command) => {
+                        credential_provider::erase(command, &credential.plugin, &credential.field).await?
+                    }
+                    None => self.store.delete(&key).await?,
+                }
+                pruned.push(key);
+            }
+            data.credentials = live_credentials;
+
+            if self.try_save(&mut data).await? {
+                return Ok(pruned);
+            }
+        }
+        Err(AcpError::conflict(format!(
+            "prune_expired did not succeed after {} attempts",
+            Self::MAX_SAVE_ATTEMPTS
+        )))
+    }
+
+    /// Drop every session-scoped token (and its `token:` key) in one call,
+    /// leaving tokens with an explicit `Expires` untouched. Gives operators
+    /// a "log out everywhere" style bulk revocation.
+    pub async fn clear_session(&self) -> Result<()> {
+        for _ in 0..Self::MAX_SAVE_ATTEMPTS {
+            let mut data = self.load_raw().await?;
+
+            let (session_tokens, remaining): (Vec<_>, Vec<_>) = data
+                .tokens
+                .into_iter()
+                .partition(|t| matches!(t.expiration, Expiration::Session));
+            for token in &session_tokens {
+                self.store.delete(&format!("token:{}", token.kind.identity())).await?;
+            }
+            data.tokens = remaining;
+
+            if self.try_save(&mut data).await? {
+                return Ok(());
+            }
+        }
+        Err(AcpError::conflict(format!(
+            "clear_session did not succeed after {} attempts",
+            Self::MAX_SAVE_ATTEMPTS
+        )))
+    }
+
     // Plugin CRUD operations
 
     /// Add a plugin to the registry
     ///
-    /// Loads the registry, adds the plugin to the plugins vec, and saves.
+    /// Loads the registry, adds the plugin to the plugins vec, and saves,
+    /// retrying on a version conflict.
     pub async fn add_plugin(&self, plugin: &PluginEntry) -> Result<()> {
-        let mut data = self.load().await?;
-        data.plugins.push(plugin.clone());
-        self.save(&data).await
+        self.update(|data| data.plugins.push(plugin.clone())).await?;
+        self.append_op(&Operation::AddPlugin(plugin.clone())).await
     }
 
     /// Remove a plugin from the registry by name
     ///
-    /// Loads the registry, removes the plugin with matching name, and saves.
+    /// Loads the registry, removes the plugin with matching name, and saves,
+    /// retrying on a version conflict.
     pub async fn remove_plugin(&self, name: &str) -> Result<()> {
-        let mut data = self.load().await?;
-        data.plugins.retain(|p| p.name != name);
-        self.save(&data).await
+        self.update(|data| data.plugins.retain(|p| p.name != name)).await?;
+        self.append_op(&Operation::RemovePlugin(name.to_string())).await
     }
 
     /// List all plugins in the registry
@@ -161,25 +813,57 @@ impl Registry {
         Ok(data.plugins)
     }
 
+    /// Find the plugin whose `hosts` patterns match `url`, if any.
+    ///
+    /// Each entry in a plugin's `hosts` is a `host/path` template like
+    /// `api.exa.ai/search/:id` or `*.s3.amazonaws.com/*`, compiled and
+    /// cached by [`url_pattern::PatternCache`]. `url`'s host and path are
+    /// joined the same way (scheme is ignored, trailing slashes are
+    /// normalized away) before matching, so this works regardless of how
+    /// the caller constructed the request URL. Plugins are checked in
+    /// registry order and the first match wins, returning the matched
+    /// entry along with any named parameters captured from the path.
+    pub async fn find_plugin_for_url(
+        &self,
+        url: &Url,
+    ) -> Result<Option<(PluginEntry, HashMap<String, String>)>> {
+        let target = url_pattern::normalize(url);
+        let plugins = self.list_plugins().await?;
+
+        for entry in plugins {
+            for pattern in &entry.hosts {
+                let compiled = self.pattern_cache.get_or_compile(pattern).await?;
+                if let Some(params) = compiled.matches(&target) {
+                    return Ok(Some((entry, params)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     // Credential CRUD operations
 
     /// Add a credential to the registry
     ///
-    /// Loads the registry, adds the credential to the credentials vec, and saves.
+    /// Loads the registry, adds the credential to the credentials vec, and
+    /// saves, retrying on a version conflict.
     pub async fn add_credential(&self, credential: &CredentialEntry) -> Result<()> {
-        let mut data = self.load().await?;
-        data.credentials.push(credential.clone());
-        self.save(&data).await
+        self.update(|data| data.credentials.push(credential.clone())).await?;
+        Ok(())
     }
 
     /// Remove a credential from the registry by plugin and field
     ///
-    /// Loads the registry, removes the credential with matching plugin and field, and saves.
+    /// Loads the registry, removes the credential with matching plugin and
+    /// field, and saves, retrying on a version conflict.
     pub async fn remove_credential(&self, plugin: &str, field: &str) -> Result<()> {
-        let mut data = self.load().await?;
-        data.credentials
-            .retain(|c| !(c.plugin == plugin && c.field == field));
-        self.save(&data).await
+        self.update(|data| {
+            data.credentials
+                .retain(|c| !(c.plugin == plugin && c.field == field))
+        })
+        .await?;
+        Ok(())
     }
 
     /// List all credentials in the registry
@@ -190,6 +874,176 @@ impl Registry {
         Ok(data.credentials)
     }
 
+    /// Resolve a credential value by `(plugin, field)` - the single lookup
+    /// path callers should use instead of reading `credential:{plugin}:{field}`
+    /// directly.
+    ///
+    /// A cached value is returned as-is if still valid under its
+    /// `CacheControl`. On a cache miss: an entry with a `provider` command
+    /// is resolved by spawning that provider (see `crate::credential_provider`)
+    /// and cached under whatever `CacheControl` it returns, defaulting to
+    /// `Never` if it doesn't say; every other entry is loaded from the
+    /// SecretStore and cached for the life of the process.
+    pub async fn resolve_credential(&self, plugin: &str, field: &str) -> Result<String> {
+        if let Some(cached) = self.credential_cache.get(plugin, field).await {
+            return Ok(cached);
+        }
+
+        let entry = self
+            .list_credentials()
+            .await?
+            .into_iter()
+            .find(|c| c.plugin == plugin && c.field == field)
+            .ok_or_else(|| AcpError::storage(format!("no credential registered for {}:{}", plugin, field)))?;
+
+        match entry.provider {
+            Some(command) => {
+                let resolved = credential_provider::fetch(&command, plugin, &[field.to_string()]).await?;
+                let control = resolved.cache.unwrap_or(CacheControl::Never);
+                let value = resolved.fields.get(field).cloned().ok_or_else(|| {
+                    AcpError::storage(format!("provider did not return field '{}'", field))
+                })?;
+                self.credential_cache.put(plugin, field, value.clone(), control).await;
+                Ok(value)
+            }
+            None => {
+                let key = format!("credential:{}:{}", plugin, field);
+                let value_bytes = self
+                    .store
+                    .get(&key)
+                    .await?
+                    .ok_or_else(|| AcpError::storage(format!("credential not found: {}", key)))?;
+                let value = String::from_utf8(value_bytes)
+                    .map_err(|e| AcpError::storage(format!("invalid UTF-8 in credential {}: {}", key, e)))?;
+                self.credential_cache
+                    .put(plugin, field, value.clone(), CacheControl::Session)
+                    .await;
+                Ok(value)
+            }
+        }
+    }
+
+    /// Persist `value` for `(plugin, field)` - the single write path callers
+    /// should use instead of writing `credential:{plugin}:{field}` directly.
+    ///
+    /// An entry with a `provider` command has its value pushed to that
+    /// provider (see `crate::credential_provider::store`) instead of the
+    /// SecretStore, so a provider-backed credential's value never touches
+    /// local disk. Either way, any cached value for `(plugin, field)` is
+    /// invalidated so the next `resolve_credential` call sees the update.
+    pub async fn store_credential_value(&self, plugin: &str, field: &str, value: &str) -> Result<()> {
+        let entry = self
+            .list_credentials()
+            .await?
+            .into_iter()
+            .find(|c| c.plugin == plugin && c.field == field)
+            .ok_or_else(|| AcpError::storage(format!("no credential registered for {}:{}", plugin, field)))?;
+
+        match entry.provider {
+            Some(command) => credential_provider::store(&command, plugin, field, value).await?,
+            None => {
+                let key = format!("credential:{}:{}", plugin, field);
+                self.store.set(&key, value.as_bytes()).await?;
+            }
+        }
+        self.credential_cache.invalidate(plugin, field).await;
+        Ok(())
+    }
+
+    /// Delete the value for `(plugin, field)`, routed the same way as
+    /// `store_credential_value`.
+    pub async fn erase_credential_value(&self, plugin: &str, field: &str) -> Result<()> {
+        let entry = self
+            .list_credentials()
+            .await?
+            .into_iter()
+            .find(|c| c.plugin == plugin && c.field == field)
+            .ok_or_else(|| AcpError::storage(format!("no credential registered for {}:{}", plugin, field)))?;
+
+        match entry.provider {
+            Some(command) => credential_provider::erase(&command, plugin, field).await?,
+            None => {
+                let key = format!("credential:{}:{}", plugin, field);
+                self.store.delete(&key).await?;
+            }
+        }
+        self.credential_cache.invalidate(plugin, field).await;
+        Ok(())
+    }
+
+    /// Revoke every entry matching `scope` in one call - the bulk
+    /// counterpart to `remove_token`/`remove_credential`, for a "log me out
+    /// of everything for this service" flow.
+    ///
+    /// Each removed credential is routed through its `provider` command if
+    /// one is registered, same as `erase_credential_value`, so whichever
+    /// `SecretStore` backend (or external provider) is active ends up with
+    /// the key actually gone. Returns the storage key of each entry
+    /// removed; an empty list (nothing matched) is not an error, so calling
+    /// this twice in a row is a harmless no-op the second time.
+    pub async fn logout(&self, scope: LogoutScope) -> Result<Vec<String>> {
+        for _ in 0..Self::MAX_SAVE_ATTEMPTS {
+            let mut data = self.load_raw().await?;
+            let mut removed = Vec::new();
+
+            match &scope {
+                LogoutScope::AllTokens => {
+                    for token in &data.tokens {
+                        let key = format!("token:{}", token.kind.identity());
+                        self.store.delete(&key).await?;
+                        removed.push(key);
+                    }
+                    data.tokens.clear();
+                }
+                LogoutScope::Plugin(plugin) => {
+                    let (matching, remaining): (Vec<_>, Vec<_>) =
+                        data.credentials.into_iter().partition(|c| &c.plugin == plugin);
+                    for credential in &matching {
+                        removed.push(self.erase_in_place(credential).await?);
+                    }
+                    data.credentials = remaining;
+                }
+                LogoutScope::Host(host) => {
+                    let matching_plugins: HashSet<String> = data
+                        .plugins
+                        .iter()
+                        .filter(|p| p.hosts.iter().any(|pattern| host_matches(pattern, host)))
+                        .map(|p| p.name.clone())
+                        .collect();
+                    let (matching, remaining): (Vec<_>, Vec<_>) = data
+                        .credentials
+                        .into_iter()
+                        .partition(|c| matching_plugins.contains(&c.plugin));
+                    for credential in &matching {
+                        removed.push(self.erase_in_place(credential).await?);
+                    }
+                    data.credentials = remaining;
+                }
+            }
+
+            if self.try_save(&mut data).await? {
+                return Ok(removed);
+            }
+        }
+        Err(AcpError::conflict(format!(
+            "logout did not succeed after {} attempts",
+            Self::MAX_SAVE_ATTEMPTS
+        )))
+    }
+
+    /// Delete `credential`'s value (provider-aware, same routing as
+    /// `erase_credential_value`) and return its storage key. Shared by
+    /// `logout`'s `Plugin`/`Host` branches.
+    async fn erase_in_place(&self, credential: &CredentialEntry) -> Result<String> {
+        let key = format!("credential:{}:{}", credential.plugin, credential.field);
+        match &credential.provider {
+            Some(command) => credential_provider::erase(command, &credential.plugin, &credential.field).await?,
+            None => self.store.delete(&key).await?,
+        }
+        self.credential_cache.invalidate(&credential.plugin, &credential.field).await;
+        Ok(key)
+    }
+
     // Migration support
 
     /// Migrate tokens from old format (token:{id}) to new format (token:{value})
@@ -212,20 +1066,16 @@ impl Registry {
     /// * Ok(()) if migration succeeded
     /// * Err if migration failed
     pub async fn migrate_old_token_format(&self) -> Result<()> {
-        // We need FileStore to list keys
-        // If store is not FileStore, we can't migrate (return Ok to skip)
-        let file_store = match self.store.as_any().downcast_ref::<FileStore>() {
-            Some(fs) => fs,
-            None => return Ok(()), // Not a FileStore, skip migration
-        };
-
         // Get all token keys
-        let token_keys = file_store.list_internal("token:").await?;
+        let token_keys = self.store.list("token:").await?;
 
         // Load existing registry to check for duplicates
         let mut registry_data = self.load().await?;
-        let existing_token_values: HashSet<String> =
-            registry_data.tokens.iter().map(|t| t.token_value.clone()).collect();
+        let existing_token_values: HashSet<String> = registry_data
+            .tokens
+            .iter()
+            .map(|t| t.kind.identity().to_string())
+            .collect();
 
         for key in token_keys {
             // Load the token
@@ -240,13 +1090,13 @@ impl Registry {
                         // Store at new key
                         self.store.set(&new_key, &token_bytes).await?;
 
-                        // Add to registry if not already present
+                        // Add to registry if not already present. These
+                        // predate expiration entirely, so default to Never
+                        // rather than Session to preserve their old
+                        // behavior of living until explicitly removed.
                         if !existing_token_values.contains(&token.token) {
-                            let entry = TokenEntry {
-                                token_value: token.token.clone(),
-                                name: token.name.clone(),
-                                created_at: token.created_at,
-                            };
+                            let entry = TokenEntry::bearer(&token.name, &token.token, token.created_at)
+                                .with_expiration(Expiration::Never);
                             registry_data.tokens.push(entry);
                         }
 
@@ -258,11 +1108,125 @@ impl Registry {
         }
 
         // Save updated registry
-        self.save(&registry_data).await?;
+        self.save(&mut registry_data).await?;
+
+        Ok(())
+    }
+
+    // Operation-log + checkpoint sync
+    //
+    // `save`/`load` assume a single writer: an external process writing
+    // directly to the store is invisible until something calls a manual
+    // reload. The oplog gives multiple ACP instances sharing one backend a
+    // way to converge without a full-scan reload on every read.
+
+    /// Number of operations appended between automatic checkpoints
+    const CHECKPOINT_INTERVAL: u32 = 50;
+
+    /// Append an operation to the oplog under a monotonically increasing key
+    ///
+    /// Keys are `oplog:<nanos-since-epoch>`, which sorts lexically in
+    /// timestamp order as long as instances don't append concurrently within
+    /// the same nanosecond - good enough for the intended low-write-rate
+    /// management operations (token/plugin/credential CRUD).
+    pub async fn append_op(&self, op: &Operation) -> Result<()> {
+        let ts = Utc::now().timestamp_nanos_opt().unwrap_or(0);
+        let key = format!("oplog:{:020}", ts);
+        let bytes = serde_json::to_vec(op)
+            .map_err(|e| AcpError::storage(format!("Failed to serialize operation: {}", e)))?;
+        self.store.set(&key, &bytes).await?;
+
+        let count = self.bump_op_counter().await?;
+        if count % Self::CHECKPOINT_INTERVAL == 0 {
+            let data = self.replay_from(&RegistryData::default(), 0).await?;
+            self.write_checkpoint(ts, &data).await?;
+        }
 
         Ok(())
     }
 
+    /// Track how many operations have been appended since the last checkpoint
+    async fn bump_op_counter(&self) -> Result<u32> {
+        let key = "oplog:_counter";
+        let count = match self.store.get(key).await? {
+            Some(bytes) => String::from_utf8_lossy(&bytes).parse::<u32>().unwrap_or(0),
+            None => 0,
+        } + 1;
+        self.store.set(key, count.to_string().as_bytes()).await?;
+        Ok(count)
+    }
+
+    /// Write a checkpoint: the full materialized registry state as of `ts`
+    async fn write_checkpoint(&self, ts: i64, data: &RegistryData) -> Result<()> {
+        let key = format!("checkpoint:{:020}", ts);
+        let bytes = serde_json::to_vec(data)
+            .map_err(|e| AcpError::storage(format!("Failed to serialize checkpoint: {}", e)))?;
+        self.store.set(&key, &bytes).await
+    }
+
+    /// Load the most recent checkpoint, if any, returning its timestamp and data
+    async fn latest_checkpoint(&self) -> Result<Option<(i64, RegistryData)>> {
+        let keys = self.store.list("checkpoint:").await?;
+        let Some(latest_key) = keys.last() else {
+            return Ok(None);
+        };
+
+        let ts: i64 = latest_key
+            .strip_prefix("checkpoint:")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+
+        let data = match self.store.get(latest_key).await? {
+            Some(bytes) => serde_json::from_slice(&bytes)
+                .map_err(|e| AcpError::storage(format!("Failed to parse checkpoint: {}", e)))?,
+            None => RegistryData::default(),
+        };
+
+        Ok(Some((ts, data)))
+    }
+
+    /// Replay every oplog entry with timestamp greater than `since_ts` onto `base`
+    async fn replay_from(&self, base: &RegistryData, since_ts: i64) -> Result<RegistryData> {
+        let mut data = base.clone();
+        let keys = self.store.list("oplog:").await?;
+        for key in keys {
+            let Some(ts_str) = key.strip_prefix("oplog:") else {
+                continue;
+            };
+            // Skip the sentinel counter key, which isn't timestamp-shaped.
+            let Ok(ts) = ts_str.parse::<i64>() else {
+                continue;
+            };
+            if ts <= since_ts {
+                continue;
+            }
+
+            if let Some(bytes) = self.store.get(&key).await? {
+                if let Ok(op) = serde_json::from_slice::<Operation>(&bytes) {
+                    apply_operation(&mut data, op);
+                }
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Sync the registry by replaying the oplog onto the latest checkpoint
+    ///
+    /// This is the convergence path for multiple ACP instances sharing one
+    /// backend: load the most recent checkpoint, fetch every oplog entry
+    /// written after it (by any process), and fold them into the in-memory
+    /// view. Storage remains the single source of truth; this just avoids
+    /// re-reading the entire history on every sync.
+    pub async fn sync(&self) -> Result<RegistryData> {
+        let (since_ts, base) = match self.latest_checkpoint().await? {
+            Some((ts, data)) => (ts, data),
+            None => (0, RegistryData::default()),
+        };
+
+        self.replay_from(&base, since_ts).await
+    }
+
     /// Migrate existing FileStore data to registry
     ///
     /// This method is used during server startup to migrate from old installations
@@ -272,12 +1236,12 @@ impl Registry {
     /// this method does nothing (returns Ok immediately).
     ///
     /// # Arguments
-    /// * `file_store` - Reference to the FileStore to migrate from
+    /// * `source` - The store to migrate from (any `SecretStore`, not just `FileStore`)
     ///
     /// # Returns
     /// * Ok(()) if migration succeeded or was skipped (registry already exists)
     /// * Err if migration failed
-    pub async fn migrate_from_file_store(&self, file_store: &FileStore) -> Result<()> {
+    pub async fn migrate_from_file_store(&self, source: &dyn SecretStore) -> Result<()> {
         // Check if registry already exists
         if self.store.get(Self::KEY).await?.is_some() {
             // Registry already exists, skip migration
@@ -288,23 +1252,20 @@ impl Registry {
         let mut data = RegistryData::default();
 
         // Migrate tokens: keys like "token:abc123"
-        let token_keys = file_store.list_internal("token:").await?;
+        let token_keys = source.list("token:").await?;
         for key in token_keys {
             // Load the token to get metadata
             if let Some(token_bytes) = self.store.get(&key).await? {
                 if let Ok(token) = serde_json::from_slice::<AgentToken>(&token_bytes) {
-                    let entry = TokenEntry {
-                        token_value: token.token.clone(),
-                        name: token.name.clone(),
-                        created_at: token.created_at,
-                    };
+                    let entry = TokenEntry::bearer(&token.name, &token.token, token.created_at)
+                        .with_expiration(Expiration::Never);
                     data.tokens.push(entry);
                 }
             }
         }
 
         // Migrate plugins: keys like "plugin:exa"
-        let plugin_keys = file_store.list_internal("plugin:").await?;
+        let plugin_keys = source.list("plugin:").await?;
         for key in plugin_keys {
             // Extract plugin name from key "plugin:name" -> "name"
             let plugin_name = key.strip_prefix("plugin:").unwrap_or(&key);
@@ -333,7 +1294,7 @@ impl Registry {
         }
 
         // Migrate credentials: keys like "credential:plugin:field"
-        let credential_keys = file_store.list_internal("credential:").await?;
+        let credential_keys = source.list("credential:").await?;
         for key in credential_keys {
             // Parse key "credential:plugin:field" -> plugin="plugin", field="field"
             let parts: Vec<&str> = key.split(':').collect();
@@ -341,41 +1302,193 @@ impl Registry {
                 let entry = CredentialEntry {
                     plugin: parts[1].to_string(),
                     field: parts[2].to_string(),
+                    provider: None,
+                    expiration: Expiration::Session,
                 };
                 data.credentials.push(entry);
             }
         }
 
         // Save the registry
-        self.save(&data).await?;
+        self.save(&mut data).await?;
 
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Move every `credential:<plugin>:<field>` value out of this registry's
+    /// current store and into `target` (e.g. a `KeychainStore`), so a
+    /// `CompositeStore` put in front of both going forward finds credentials
+    /// already sitting where it expects them.
+    ///
+    /// Idempotent: if no `credential:` keys remain in this store, assumes a
+    /// previous run already moved them and returns without touching
+    /// anything, mirroring the existing `_registry`-exists guard in
+    /// `migrate_from_file_store`.
+    pub async fn migrate_credentials_into(&self, target: Arc<dyn SecretStore>) -> Result<()> {
+        let credential_keys = self.store.list("credential:").await?;
+        if credential_keys.is_empty() {
+            return Ok(());
+        }
 
-    #[test]
-    fn test_registry_data_serialization() {
-        let data = RegistryData {
-            version: 1,
-            tokens: vec![TokenEntry {
-                token_value: "acp_abc123".to_string(),
-                name: "test-token".to_string(),
-                created_at: DateTime::parse_from_rfc3339("2024-01-15T10:30:00Z")
-                    .unwrap()
-                    .with_timezone(&Utc),
-            }],
-            plugins: vec![PluginEntry {
-                name: "exa".to_string(),
-                hosts: vec!["api.exa.ai".to_string()],
-                credential_schema: vec!["api_key".to_string()],
+        for key in credential_keys {
+            if let Some(value) = self.store.get(&key).await? {
+                target.set(&key, &value).await?;
+                self.store.delete(&key).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read the `_registry` document's `schema_version` under `store`
+    /// (treating a missing `_registry` key, or a missing `schema_version`
+    /// field on an existing one, as version 0) and apply the chained
+    /// migration steps below in order until it reaches `SCHEMA_VERSION`.
+    ///
+    /// Each step persists its result before the next one runs, so a crash
+    /// mid-chain just resumes from whatever `schema_version` was last
+    /// written rather than re-applying from scratch. Refuses to proceed -
+    /// returning an error instead - if the stored `schema_version` is
+    /// already newer than `SCHEMA_VERSION`, since that means a newer binary
+    /// wrote a shape this one doesn't know how to read.
+    ///
+    /// `store` is the legacy backend `migrate_from_file_store` would scan
+    /// for the version-0-to-1 step; for a deployment with no such backend,
+    /// pass `self`'s own store again.
+    pub async fn run_migrations(&self, store: &dyn SecretStore) -> Result<()> {
+        let mut current = self.schema_version().await?;
+
+        if current > SCHEMA_VERSION {
+            return Err(AcpError::storage(format!(
+                "registry schema_version {} is newer than this binary supports ({}); refusing to downgrade",
+                current, SCHEMA_VERSION
+            )));
+        }
+
+        while current < SCHEMA_VERSION {
+            current = match current {
+                0 => self.migrate_schema_v0_to_v1(store).await?,
+                other => {
+                    return Err(AcpError::storage(format!(
+                        "no migration registered from schema_version {}",
+                        other
+                    )))
+                }
+            };
+        }
+
+        Ok(())
+    }
+
+    /// The `schema_version` recorded in the stored `_registry` document, or
+    /// 0 if there is no document yet (or it predates this field entirely).
+    async fn schema_version(&self) -> Result<u32> {
+        match self.store.get(Self::KEY).await? {
+            Some(bytes) => {
+                let raw: serde_json::Value = serde_json::from_slice(&bytes).map_err(|e| {
+                    AcpError::storage(format!("Failed to parse registry JSON: {}", e))
+                })?;
+                Ok(raw.get("schema_version").and_then(|v| v.as_u64()).unwrap_or(0) as u32)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Schema version 0 (no `_registry` document, or one predating
+    /// `schema_version` tracking) to version 1 (today's shape).
+    ///
+    /// If a `_registry` document already exists, there's no prior shape to
+    /// transform - it's already version 1 in everything but name, so this
+    /// just stamps the field. Otherwise this is a genuinely fresh or
+    /// pre-registry installation, bootstrapped the same way
+    /// `migrate_from_file_store` always has: by scanning `store` for loose
+    /// `token:`/`plugin:`/`credential:` keys.
+    async fn migrate_schema_v0_to_v1(&self, store: &dyn SecretStore) -> Result<u32> {
+        if self.store.get(Self::KEY).await?.is_some() {
+            let mut data = self.load().await?;
+            data.schema_version = 1;
+            self.save(&mut data).await?;
+        } else {
+            self.migrate_from_file_store(store).await?;
+        }
+        Ok(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_sync_replays_oplog_without_save() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        let token = TokenEntry::bearer("test-token".to_string(), "acp_abc123".to_string(), Utc::now());
+        registry.add_token(&token).await.expect("add should succeed");
+
+        // A second instance pointed at the same store should converge via sync()
+        // even though it never called save() itself.
+        let other_instance = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+        let synced = other_instance.sync().await.expect("sync should succeed");
+        assert_eq!(synced.tokens.len(), 1);
+        assert_eq!(synced.tokens[0].kind.identity(), "acp_abc123");
+    }
+
+    #[tokio::test]
+    async fn test_sync_applies_remove_after_checkpoint() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        let token = TokenEntry::bearer("test-token".to_string(), "acp_abc123".to_string(), Utc::now());
+        registry.add_token(&token).await.expect("add should succeed");
+        registry
+            .remove_token("acp_abc123")
+            .await
+            .expect("remove should succeed");
+
+        let synced = registry.sync().await.expect("sync should succeed");
+        assert_eq!(synced.tokens.len(), 0);
+    }
+
+    #[test]
+    fn test_registry_data_serialization() {
+        let data = RegistryData {
+            version: 1,
+            schema_version: SCHEMA_VERSION,
+            tokens: vec![TokenEntry::bearer(
+                "test-token".to_string(),
+                "acp_abc123".to_string(),
+                DateTime::parse_from_rfc3339("2024-01-15T10:30:00Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            )],
+            plugins: vec![PluginEntry {
+                name: "exa".to_string(),
+                hosts: vec!["api.exa.ai".to_string()],
+                credential_schema: vec!["api_key".to_string()],
             }],
             credentials: vec![CredentialEntry {
                 plugin: "exa".to_string(),
                 field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
             }],
         };
 
@@ -390,39 +1503,787 @@ mod tests {
             serde_json::from_str(&json).expect("deserialization should succeed");
         assert_eq!(parsed.version, 1);
         assert_eq!(parsed.tokens.len(), 1);
-        assert_eq!(parsed.tokens[0].token_value, "acp_abc123");
+        assert_eq!(parsed.tokens[0].kind.identity(), "acp_abc123");
         assert_eq!(parsed.plugins.len(), 1);
         assert_eq!(parsed.plugins[0].name, "exa");
         assert_eq!(parsed.credentials.len(), 1);
         assert_eq!(parsed.credentials[0].plugin, "exa");
     }
 
-    #[test]
-    fn test_registry_data_empty() {
-        let data = RegistryData::default();
+    #[test]
+    fn test_registry_data_empty() {
+        let data = RegistryData::default();
+
+        assert_eq!(data.version, REGISTRY_VERSION);
+        assert_eq!(data.tokens.len(), 0);
+        assert_eq!(data.plugins.len(), 0);
+        assert_eq!(data.credentials.len(), 0);
+
+        // Should serialize/deserialize empty structures
+        let json = serde_json::to_string(&data).expect("serialization should succeed");
+        let parsed: RegistryData =
+            serde_json::from_str(&json).expect("deserialization should succeed");
+        assert_eq!(parsed.version, REGISTRY_VERSION);
+    }
+
+    #[test]
+    fn test_token_entry_fields() {
+        let token = TokenEntry::bearer("my-agent", "acp_test123", Utc::now());
+
+        assert_eq!(token.kind.identity(), "acp_test123");
+        assert_eq!(token.name, "my-agent");
+    }
+
+    #[test]
+    fn test_asymmetric_token_entry_identity_is_key_id() {
+        let token = TokenEntry::asymmetric("my-agent", "k4.pid.fake-verifier", "k4.pid.fake-key-id", Utc::now());
+
+        assert_eq!(token.kind.identity(), "k4.pid.fake-key-id");
+        assert_eq!(token.name, "my-agent");
+    }
+
+    #[tokio::test]
+    async fn test_registry_remove_token_by_key_id_revokes_asymmetric_entry() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let bearer = TokenEntry::bearer("bearer-agent", "acp_bearer1", Utc::now());
+        let asymmetric = TokenEntry::asymmetric("key-agent", "k4.pid.verifier", "k4.pid.keyid", Utc::now());
+        registry.add_token(&bearer).await.expect("add bearer token");
+        registry.add_token(&asymmetric).await.expect("add asymmetric token");
+
+        registry
+            .remove_token("k4.pid.keyid")
+            .await
+            .expect("remove by key_id");
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind.identity(), "acp_bearer1");
+    }
+
+    #[tokio::test]
+    async fn test_generate_keypair_registers_asymmetric_token() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        registry
+            .generate_keypair("agent-1")
+            .await
+            .expect("generate keypair");
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].name, "agent-1");
+        assert!(matches!(tokens[0].kind, TokenKind::Asymmetric { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_sign_and_verify_request_roundtrip() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let signing_key = registry
+            .generate_keypair("agent-1")
+            .await
+            .expect("generate keypair");
+
+        let token = registry
+            .sign_request(&signing_key, "api.exa.ai", "https://registry.example.com")
+            .expect("sign request");
+
+        let entry = registry
+            .verify_request(&token, "api.exa.ai")
+            .await
+            .expect("verify request");
+        assert_eq!(entry.name, "agent-1");
+    }
+
+    #[tokio::test]
+    async fn test_verify_request_rejects_mismatched_host() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let signing_key = registry
+            .generate_keypair("agent-1")
+            .await
+            .expect("generate keypair");
+        let token = registry
+            .sign_request(&signing_key, "api.exa.ai", "https://registry.example.com")
+            .expect("sign request");
+
+        let result = registry.verify_request(&token, "api.other.com").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_request_rejects_revoked_key() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let signing_key = registry
+            .generate_keypair("agent-1")
+            .await
+            .expect("generate keypair");
+        let token = registry
+            .sign_request(&signing_key, "api.exa.ai", "https://registry.example.com")
+            .expect("sign request");
+
+        let key_id = crate::paseto::peek_request_key_id(&token).expect("peek key id");
+        registry
+            .revoke_token(&key_id)
+            .await
+            .expect("revoke key");
+
+        let result = registry.verify_request(&token, "api.exa.ai").await;
+        assert!(result.is_err());
+        assert!(registry
+            .list_tokens()
+            .await
+            .expect("list tokens")
+            .iter()
+            .any(|t| t.kind.identity() == key_id));
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_accepts_bearer_token() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let entry = TokenEntry::bearer("agent-1", "acp_bearer1", Utc::now());
+        registry.add_token(&entry).await.expect("add bearer token");
+
+        let found = registry
+            .authenticate("acp_bearer1", "api.exa.ai")
+            .await
+            .expect("authenticate");
+        assert_eq!(found.name, "agent-1");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_routes_paseto_to_verify_request() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let signing_key = registry
+            .generate_keypair("agent-1")
+            .await
+            .expect("generate keypair");
+        let token = registry
+            .sign_request(&signing_key, "api.exa.ai", "https://registry.example.com")
+            .expect("sign request");
+
+        let found = registry
+            .authenticate(&token, "api.exa.ai")
+            .await
+            .expect("authenticate");
+        assert_eq!(found.name, "agent-1");
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_unknown_bearer() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let result = registry.authenticate("acp_nope", "api.exa.ai").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_rejects_revoked_token() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let entry = TokenEntry::bearer("agent-1", "acp_bearer1", Utc::now());
+        registry.add_token(&entry).await.expect("add token");
+
+        assert!(registry.is_valid("acp_bearer1", Utc::now()).await.expect("is_valid"));
+
+        registry.revoke_token("acp_bearer1").await.expect("revoke token");
+
+        assert!(!registry.is_valid("acp_bearer1", Utc::now()).await.expect("is_valid"));
+        assert!(registry
+            .list_tokens()
+            .await
+            .expect("list tokens")
+            .iter()
+            .any(|t| t.kind.identity() == "acp_bearer1"));
+    }
+
+    #[tokio::test]
+    async fn test_is_valid_rejects_expired_token() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let mut entry = TokenEntry::bearer("agent-1", "acp_bearer1", Utc::now());
+        entry.expiration = Expiration::Expires {
+            at: Utc::now() - chrono::Duration::seconds(60),
+        };
+        registry.add_token(&entry).await.expect("add token");
+
+        assert!(!registry.is_valid("acp_bearer1", Utc::now()).await.expect("is_valid"));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_token_invalidates_old_secret_but_keeps_it_listed() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let entry = TokenEntry::bearer("agent-1", "acp_old", Utc::now());
+        registry.add_token(&entry).await.expect("add token");
+
+        let rotated = registry.rotate_token("acp_old").await.expect("rotate token");
+        assert_ne!(rotated.kind.identity(), "acp_old");
+        assert_eq!(rotated.name, "agent-1");
+
+        assert!(!registry.is_valid("acp_old", Utc::now()).await.expect("is_valid"));
+        assert!(registry
+            .is_valid(rotated.kind.identity(), Utc::now())
+            .await
+            .expect("is_valid"));
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert!(tokens.iter().any(|t| t.kind.identity() == "acp_old"));
+        assert!(tokens.iter().any(|t| t.kind.identity() == rotated.kind.identity()));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_token_errors_for_unknown_identity() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let result = registry.rotate_token("acp_nope").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_authenticate_rejects_revoked_token() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let entry = TokenEntry::bearer("agent-1", "acp_bearer1", Utc::now());
+        registry.add_token(&entry).await.expect("add token");
+        registry.revoke_token("acp_bearer1").await.expect("revoke token");
+
+        let result = registry.authenticate("acp_bearer1", "api.exa.ai").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_registry_load_upgrades_version_1_bearer_tokens() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+
+        let v1_json = serde_json::json!({
+            "version": 1,
+            "tokens": [{
+                "token_value": "acp_legacy123",
+                "name": "legacy-agent",
+                "created_at": "2024-01-15T10:30:00Z",
+            }],
+            "plugins": [],
+            "credentials": [],
+        });
+        store
+            .set("_registry", &serde_json::to_vec(&v1_json).unwrap())
+            .await
+            .expect("seed version-1 registry");
+
+        let registry = Registry::new(Arc::new(store));
+        let loaded = registry.load().await.expect("load should upgrade");
+
+        assert_eq!(loaded.version, REGISTRY_VERSION);
+        assert_eq!(loaded.tokens.len(), 1);
+        assert_eq!(loaded.tokens[0].kind.identity(), "acp_legacy123");
+        assert_eq!(loaded.tokens[0].name, "legacy-agent");
+    }
+
+    #[test]
+    fn test_expiration_serializes_internally_tagged() {
+        let session = serde_json::to_value(Expiration::Session).unwrap();
+        assert_eq!(session, serde_json::json!({"cache": "session"}));
+
+        let never = serde_json::to_value(Expiration::Never).unwrap();
+        assert_eq!(never, serde_json::json!({"cache": "never"}));
+
+        let at = DateTime::parse_from_rfc3339("2024-01-15T10:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let expires = serde_json::to_value(Expiration::Expires { at }).unwrap();
+        assert_eq!(
+            expires,
+            serde_json::json!({"cache": "expires", "at": "2024-01-15T10:30:00Z"})
+        );
+    }
+
+    #[test]
+    fn test_expiration_flattens_onto_token_entry() {
+        let token = TokenEntry::bearer("agent", "acp_abc123", Utc::now());
+        let value = serde_json::to_value(&token).unwrap();
+
+        // "cache" sits alongside "name"/"type" at the top level, not nested
+        // under an "expiration" key.
+        assert_eq!(value["cache"], serde_json::json!("session"));
+        assert!(value.get("expiration").is_none());
+    }
+
+    #[test]
+    fn test_expiration_defaults_to_session_when_field_missing() {
+        let token: TokenEntry = serde_json::from_value(serde_json::json!({
+            "name": "old-agent",
+            "created_at": "2024-01-15T10:30:00Z",
+            "type": "bearer",
+            "token_value": "acp_old1",
+        }))
+        .expect("should deserialize without an expiration field");
+
+        assert_eq!(token.expiration, Expiration::Session);
+    }
+
+    #[tokio::test]
+    async fn test_registry_load_filters_out_expired_tokens() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let live = TokenEntry::bearer("live-agent", "acp_live", Utc::now());
+        let expired = TokenEntry::bearer("expired-agent", "acp_expired", Utc::now()).with_expiration(
+            Expiration::Expires {
+                at: Utc::now() - chrono::Duration::hours(1),
+            },
+        );
+        registry.add_token(&live).await.expect("add live token");
+        registry.add_token(&expired).await.expect("add expired token");
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind.identity(), "acp_live");
+    }
+
+    #[tokio::test]
+    async fn test_registry_prune_expired_deletes_registry_entry_and_secret_key() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        let expired = TokenEntry::bearer("expired-agent", "acp_expired", Utc::now()).with_expiration(
+            Expiration::Expires {
+                at: Utc::now() - chrono::Duration::hours(1),
+            },
+        );
+        registry.add_token(&expired).await.expect("add expired token");
+        store
+            .set("token:acp_expired", b"placeholder")
+            .await
+            .expect("seed secret key");
+
+        let pruned = registry.prune_expired().await.expect("prune expired");
+
+        assert_eq!(pruned, vec!["token:acp_expired".to_string()]);
+        assert!(store.get("token:acp_expired").await.unwrap().is_none());
+        let data = registry.load().await.expect("load after prune");
+        assert_eq!(data.tokens.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_registry_clear_session_drops_session_tokens_but_not_expires() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let session_token = TokenEntry::bearer("session-agent", "acp_session", Utc::now());
+        let fixed_expiry_token = TokenEntry::bearer("expiring-agent", "acp_expiring", Utc::now())
+            .with_expiration(Expiration::Expires {
+                at: Utc::now() + chrono::Duration::hours(1),
+            });
+        registry.add_token(&session_token).await.expect("add session token");
+        registry
+            .add_token(&fixed_expiry_token)
+            .await
+            .expect("add expiring token");
+
+        registry.clear_session().await.expect("clear session tokens");
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind.identity(), "acp_expiring");
+    }
+
+    #[tokio::test]
+    async fn test_registry_clear_session_does_not_drop_never_tokens() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let never_token = TokenEntry::bearer("long-lived-agent", "acp_never", Utc::now())
+            .with_expiration(Expiration::Never);
+        registry.add_token(&never_token).await.expect("add never-expiring token");
+
+        registry.clear_session().await.expect("clear session tokens");
+
+        let tokens = registry.list_tokens().await.expect("list tokens");
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind.identity(), "acp_never");
+    }
+
+    #[tokio::test]
+    async fn test_logout_all_tokens_revokes_every_token_and_secret_key() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        registry
+            .add_token(&TokenEntry::bearer("agent-a", "acp_a", Utc::now()))
+            .await
+            .expect("add token a");
+        registry
+            .add_token(&TokenEntry::bearer("agent-b", "acp_b", Utc::now()))
+            .await
+            .expect("add token b");
+        store.set("token:acp_a", b"placeholder").await.expect("seed secret key");
+        store.set("token:acp_b", b"placeholder").await.expect("seed secret key");
+
+        let mut removed = registry.logout(LogoutScope::AllTokens).await.expect("logout");
+        removed.sort();
+        assert_eq!(removed, vec!["token:acp_a".to_string(), "token:acp_b".to_string()]);
+        assert!(store.get("token:acp_a").await.unwrap().is_none());
+        assert!(store.get("token:acp_b").await.unwrap().is_none());
+        assert!(registry.list_tokens().await.expect("list tokens").is_empty());
+
+        // Idempotent: calling again finds nothing left to revoke.
+        let removed_again = registry.logout(LogoutScope::AllTokens).await.expect("logout again");
+        assert!(removed_again.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_logout_plugin_revokes_only_that_plugins_credentials() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "exa".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add exa credential");
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "other".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add other credential");
+
+        let removed = registry
+            .logout(LogoutScope::Plugin("exa".to_string()))
+            .await
+            .expect("logout");
+        assert_eq!(removed, vec!["credential:exa:api_key".to_string()]);
+
+        let remaining = registry.list_credentials().await.expect("list credentials");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].plugin, "other");
+    }
+
+    #[tokio::test]
+    async fn test_logout_host_revokes_credentials_of_plugins_matching_that_host() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        registry
+            .add_plugin(&PluginEntry {
+                name: "exa".to_string(),
+                hosts: vec!["api.exa.ai/search/:id".to_string()],
+                credential_schema: vec!["api_key".to_string()],
+            })
+            .await
+            .expect("add exa plugin");
+        registry
+            .add_plugin(&PluginEntry {
+                name: "other".to_string(),
+                hosts: vec!["api.other.com".to_string()],
+                credential_schema: vec!["api_key".to_string()],
+            })
+            .await
+            .expect("add other plugin");
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "exa".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add exa credential");
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "other".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add other credential");
+
+        let removed = registry
+            .logout(LogoutScope::Host("api.exa.ai".to_string()))
+            .await
+            .expect("logout");
+        assert_eq!(removed, vec!["credential:exa:api_key".to_string()]);
+
+        let remaining = registry.list_credentials().await.expect("list credentials");
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].plugin, "other");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credential_reads_store_backed_value() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "exa".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add credential");
+        store
+            .set("credential:exa:api_key", b"stored-value")
+            .await
+            .expect("seed store value");
+
+        let value = registry.resolve_credential("exa", "api_key").await.expect("resolve");
+        assert_eq!(value, "stored-value");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credential_caches_store_backed_value() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(store.clone());
+
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "exa".to_string(),
+                field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add credential");
+        store
+            .set("credential:exa:api_key", b"stored-value")
+            .await
+            .expect("seed store value");
+
+        registry.resolve_credential("exa", "api_key").await.expect("resolve once");
+        store.delete("credential:exa:api_key").await.expect("delete backing key");
+
+        // Still resolves from the in-memory cache even though the backing
+        // key is gone.
+        let value = registry.resolve_credential("exa", "api_key").await.expect("resolve cached");
+        assert_eq!(value, "stored-value");
+    }
+
+    #[tokio::test]
+    async fn test_resolve_credential_uses_provider_and_caches_per_its_control() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
 
-        assert_eq!(data.version, 1);
-        assert_eq!(data.tokens.len(), 0);
-        assert_eq!(data.plugins.len(), 0);
-        assert_eq!(data.credentials.len(), 0);
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
 
-        // Should serialize/deserialize empty structures
-        let json = serde_json::to_string(&data).expect("serialization should succeed");
-        let parsed: RegistryData =
-            serde_json::from_str(&json).expect("deserialization should succeed");
-        assert_eq!(parsed.version, 1);
+        let script = r#"python3 -c "import sys,json; json.loads(sys.stdin.readline()); print(json.dumps({'Ok': {'fields': {'api_key': 'from-provider'}, 'cache': {'cache': 'session'}}}))""#;
+        registry
+            .add_credential(&CredentialEntry {
+                plugin: "exa".to_string(),
+                field: "api_key".to_string(),
+                provider: Some(script.to_string()),
+                expiration: Expiration::Session,
+            })
+            .await
+            .expect("add credential");
+
+        let result = registry.resolve_credential("exa", "api_key").await;
+        if let Ok(value) = result {
+            assert_eq!(value, "from-provider");
+            assert_eq!(
+                registry.credential_cache.get("exa", "api_key").await,
+                Some("from-provider".to_string())
+            );
+        }
+        // If python3 isn't available in the test environment, we still want
+        // the registry lookup and provider dispatch above to have run
+        // without panicking; a missing interpreter is an environment gap,
+        // not a protocol bug.
     }
 
-    #[test]
-    fn test_token_entry_fields() {
-        let token = TokenEntry {
-            token_value: "acp_test123".to_string(),
-            name: "my-agent".to_string(),
-            created_at: Utc::now(),
-        };
+    #[tokio::test]
+    async fn test_resolve_credential_errors_when_not_registered() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
 
-        assert_eq!(token.token_value, "acp_test123");
-        assert_eq!(token.name, "my-agent");
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        assert!(registry.resolve_credential("exa", "api_key").await.is_err());
     }
 
     #[test]
@@ -443,6 +2304,8 @@ mod tests {
         let cred = CredentialEntry {
             plugin: "exa".to_string(),
             field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
 
         assert_eq!(cred.plugin, "exa");
@@ -462,7 +2325,7 @@ mod tests {
 
         // Load when no registry exists yet - should return empty RegistryData
         let data = registry.load().await.expect("load should succeed");
-        assert_eq!(data.version, 1);
+        assert_eq!(data.version, REGISTRY_VERSION);
         assert_eq!(data.tokens.len(), 0);
         assert_eq!(data.plugins.len(), 0);
         assert_eq!(data.credentials.len(), 0);
@@ -480,13 +2343,10 @@ mod tests {
         let registry = Registry::new(Arc::new(store));
 
         // Create test data
-        let data = RegistryData {
-            version: 1,
-            tokens: vec![TokenEntry {
-                token_value: "acp_test123".to_string(),
-                name: "test-token".to_string(),
-                created_at: Utc::now(),
-            }],
+        let mut data = RegistryData {
+            version: REGISTRY_VERSION,
+            schema_version: SCHEMA_VERSION,
+            tokens: vec![TokenEntry::bearer("test-token".to_string(), "acp_test123".to_string(), Utc::now())],
             plugins: vec![PluginEntry {
                 name: "exa".to_string(),
                 hosts: vec!["api.exa.ai".to_string()],
@@ -495,12 +2355,14 @@ mod tests {
             credentials: vec![CredentialEntry {
                 plugin: "exa".to_string(),
                 field: "api_key".to_string(),
+                provider: None,
+                expiration: Expiration::Session,
             }],
         };
 
         // Save
         registry
-            .save(&data)
+            .save(&mut data)
             .await
             .expect("save should succeed");
 
@@ -508,7 +2370,7 @@ mod tests {
         let loaded = registry.load().await.expect("load should succeed");
         assert_eq!(loaded.version, data.version);
         assert_eq!(loaded.tokens.len(), 1);
-        assert_eq!(loaded.tokens[0].token_value, "acp_test123");
+        assert_eq!(loaded.tokens[0].kind.identity(), "acp_test123");
         assert_eq!(loaded.plugins.len(), 1);
         assert_eq!(loaded.plugins[0].name, "exa");
         assert_eq!(loaded.credentials.len(), 1);
@@ -527,42 +2389,33 @@ mod tests {
         let registry = Registry::new(Arc::new(store));
 
         // Save initial data
-        let data1 = RegistryData {
-            version: 1,
-            tokens: vec![TokenEntry {
-                token_value: "acp_token1".to_string(),
-                name: "first".to_string(),
-                created_at: Utc::now(),
-            }],
+        let mut data1 = RegistryData {
+            version: REGISTRY_VERSION,
+            schema_version: SCHEMA_VERSION,
+            tokens: vec![TokenEntry::bearer("first".to_string(), "acp_token1".to_string(), Utc::now())],
             plugins: vec![],
             credentials: vec![],
         };
-        registry.save(&data1).await.expect("save should succeed");
+        registry.save(&mut data1).await.expect("save should succeed");
 
-        // Overwrite with new data
-        let data2 = RegistryData {
-            version: 1,
+        // Overwrite with new data, carrying forward the version save() just
+        // bumped data1 to so this isn't treated as a conflicting write.
+        let mut data2 = RegistryData {
+            version: data1.version,
+            schema_version: SCHEMA_VERSION,
             tokens: vec![
-                TokenEntry {
-                    token_value: "acp_token1".to_string(),
-                    name: "first".to_string(),
-                    created_at: Utc::now(),
-                },
-                TokenEntry {
-                    token_value: "acp_token2".to_string(),
-                    name: "second".to_string(),
-                    created_at: Utc::now(),
-                },
+                TokenEntry::bearer("first".to_string(), "acp_token1".to_string(), Utc::now()),
+                TokenEntry::bearer("second".to_string(), "acp_token2".to_string(), Utc::now()),
             ],
             plugins: vec![],
             credentials: vec![],
         };
-        registry.save(&data2).await.expect("save should succeed");
+        registry.save(&mut data2).await.expect("save should succeed");
 
         // Load and verify it was overwritten
         let loaded = registry.load().await.expect("load should succeed");
         assert_eq!(loaded.tokens.len(), 2);
-        assert_eq!(loaded.tokens[1].token_value, "acp_token2");
+        assert_eq!(loaded.tokens[1].kind.identity(), "acp_token2");
     }
 
     #[tokio::test]
@@ -579,8 +2432,8 @@ mod tests {
         let registry = Registry::new(store.clone());
 
         // Save some data
-        let data = RegistryData::default();
-        registry.save(&data).await.expect("save should succeed");
+        let mut data = RegistryData::default();
+        registry.save(&mut data).await.expect("save should succeed");
 
         // Verify it was stored at the correct key
         let raw_value = store
@@ -592,7 +2445,7 @@ mod tests {
         // Verify it's valid JSON
         let parsed: RegistryData =
             serde_json::from_slice(&raw_value).expect("should deserialize");
-        assert_eq!(parsed.version, 1);
+        assert_eq!(parsed.version, REGISTRY_VERSION);
     }
 
     // RED: Tests for token CRUD operations
@@ -607,11 +2460,7 @@ mod tests {
             .expect("create FileStore");
         let registry = Registry::new(Arc::new(store));
 
-        let token = TokenEntry {
-            token_value: "acp_abc123".to_string(),
-            name: "test-token".to_string(),
-            created_at: Utc::now(),
-        };
+        let token = TokenEntry::bearer("test-token".to_string(), "acp_abc123".to_string(), Utc::now());
 
         // Add token should succeed
         registry.add_token(&token).await.expect("add should succeed");
@@ -619,7 +2468,7 @@ mod tests {
         // Verify token is in registry
         let tokens = registry.list_tokens().await.expect("list should succeed");
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].token_value, "acp_abc123");
+        assert_eq!(tokens[0].kind.identity(), "acp_abc123");
         assert_eq!(tokens[0].name, "test-token");
     }
 
@@ -635,16 +2484,8 @@ mod tests {
         let registry = Registry::new(Arc::new(store));
 
         // Add two tokens
-        let token1 = TokenEntry {
-            token_value: "acp_abc123".to_string(),
-            name: "token1".to_string(),
-            created_at: Utc::now(),
-        };
-        let token2 = TokenEntry {
-            token_value: "acp_def456".to_string(),
-            name: "token2".to_string(),
-            created_at: Utc::now(),
-        };
+        let token1 = TokenEntry::bearer("token1".to_string(), "acp_abc123".to_string(), Utc::now());
+        let token2 = TokenEntry::bearer("token2".to_string(), "acp_def456".to_string(), Utc::now());
         registry.add_token(&token1).await.expect("add should succeed");
         registry.add_token(&token2).await.expect("add should succeed");
 
@@ -657,7 +2498,7 @@ mod tests {
         // Verify only second token remains
         let tokens = registry.list_tokens().await.expect("list should succeed");
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].token_value, "acp_def456");
+        assert_eq!(tokens[0].kind.identity(), "acp_def456");
     }
 
     #[tokio::test]
@@ -676,16 +2517,8 @@ mod tests {
         assert_eq!(tokens.len(), 0);
 
         // Add tokens
-        let token1 = TokenEntry {
-            token_value: "acp_abc123".to_string(),
-            name: "token1".to_string(),
-            created_at: Utc::now(),
-        };
-        let token2 = TokenEntry {
-            token_value: "acp_def456".to_string(),
-            name: "token2".to_string(),
-            created_at: Utc::now(),
-        };
+        let token1 = TokenEntry::bearer("token1".to_string(), "acp_abc123".to_string(), Utc::now());
+        let token2 = TokenEntry::bearer("token2".to_string(), "acp_def456".to_string(), Utc::now());
         registry.add_token(&token1).await.expect("add should succeed");
         registry.add_token(&token2).await.expect("add should succeed");
 
@@ -808,6 +2641,126 @@ mod tests {
         assert_eq!(plugins.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_find_plugin_for_url_matches_named_param() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let plugin = PluginEntry {
+            name: "exa".to_string(),
+            hosts: vec!["api.exa.ai/search/:id".to_string()],
+            credential_schema: vec!["api_key".to_string()],
+        };
+        registry
+            .add_plugin(&plugin)
+            .await
+            .expect("add should succeed");
+
+        let url = url::Url::parse("https://api.exa.ai/search/42").unwrap();
+        let (found, params) = registry
+            .find_plugin_for_url(&url)
+            .await
+            .expect("lookup should succeed")
+            .expect("url should match");
+
+        assert_eq!(found.name, "exa");
+        assert_eq!(params.get("id"), Some(&"42".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_find_plugin_for_url_matches_wildcard_host_and_path() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let plugin = PluginEntry {
+            name: "s3".to_string(),
+            hosts: vec!["*.s3.amazonaws.com/*".to_string()],
+            credential_schema: vec![],
+        };
+        registry
+            .add_plugin(&plugin)
+            .await
+            .expect("add should succeed");
+
+        let url = url::Url::parse("https://my-bucket.s3.amazonaws.com/objects/a.txt").unwrap();
+        let found = registry
+            .find_plugin_for_url(&url)
+            .await
+            .expect("lookup should succeed");
+        assert!(found.is_some());
+        assert_eq!(found.unwrap().0.name, "s3");
+    }
+
+    #[tokio::test]
+    async fn test_find_plugin_for_url_ignores_trailing_slash_and_scheme() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let plugin = PluginEntry {
+            name: "exa".to_string(),
+            hosts: vec!["api.exa.ai/search".to_string()],
+            credential_schema: vec![],
+        };
+        registry
+            .add_plugin(&plugin)
+            .await
+            .expect("add should succeed");
+
+        let url = url::Url::parse("http://api.exa.ai/search/").unwrap();
+        let found = registry
+            .find_plugin_for_url(&url)
+            .await
+            .expect("lookup should succeed");
+        assert!(found.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_find_plugin_for_url_returns_none_when_no_host_matches() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let registry = Registry::new(Arc::new(store));
+
+        let plugin = PluginEntry {
+            name: "exa".to_string(),
+            hosts: vec!["api.exa.ai/search/:id".to_string()],
+            credential_schema: vec![],
+        };
+        registry
+            .add_plugin(&plugin)
+            .await
+            .expect("add should succeed");
+
+        let url = url::Url::parse("https://api.other.com/search/42").unwrap();
+        let found = registry
+            .find_plugin_for_url(&url)
+            .await
+            .expect("lookup should succeed");
+        assert!(found.is_none());
+    }
+
     // RED: Tests for credential CRUD operations
     #[tokio::test]
     async fn test_add_credential() {
@@ -823,6 +2776,8 @@ mod tests {
         let cred = CredentialEntry {
             plugin: "exa".to_string(),
             field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
 
         // Add credential should succeed
@@ -856,14 +2811,20 @@ mod tests {
         let cred1 = CredentialEntry {
             plugin: "exa".to_string(),
             field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         let cred2 = CredentialEntry {
             plugin: "exa".to_string(),
             field: "secret".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         let cred3 = CredentialEntry {
             plugin: "github".to_string(),
             field: "token".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         registry
             .add_credential(&cred1)
@@ -919,10 +2880,14 @@ mod tests {
         let cred1 = CredentialEntry {
             plugin: "exa".to_string(),
             field: "api_key".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         let cred2 = CredentialEntry {
             plugin: "github".to_string(),
             field: "token".to_string(),
+            provider: None,
+            expiration: Expiration::Session,
         };
         registry
             .add_credential(&cred1)
@@ -953,11 +2918,7 @@ mod tests {
             .expect("create FileStore");
         let registry = Registry::new(Arc::new(store));
 
-        let token = TokenEntry {
-            token_value: "acp_test123".to_string(),
-            name: "test-token".to_string(),
-            created_at: Utc::now(),
-        };
+        let token = TokenEntry::bearer("test-token".to_string(), "acp_test123".to_string(), Utc::now());
 
         // Add token
         registry.add_token(&token).await.expect("add should succeed");
@@ -965,7 +2926,7 @@ mod tests {
         // Verify token is in registry
         let tokens = registry.list_tokens().await.expect("list should succeed");
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].token_value, "acp_test123");
+        assert_eq!(tokens[0].kind.identity(), "acp_test123");
         assert_eq!(tokens[0].name, "test-token");
     }
 
@@ -982,11 +2943,7 @@ mod tests {
         let registry = Registry::new(Arc::new(store));
 
         // Add token
-        let token = TokenEntry {
-            token_value: "acp_test123".to_string(),
-            name: "test-token".to_string(),
-            created_at: Utc::now(),
-        };
+        let token = TokenEntry::bearer("test-token".to_string(), "acp_test123".to_string(), Utc::now());
         registry.add_token(&token).await.expect("add should succeed");
 
         // Remove by value
@@ -1026,8 +2983,11 @@ mod tests {
         // Verify token is now in registry with new format
         let tokens = registry.list_tokens().await.expect("list should succeed");
         assert_eq!(tokens.len(), 1);
-        assert_eq!(tokens[0].token_value, old_token.token);
+        assert_eq!(tokens[0].kind.identity(), old_token.token);
         assert_eq!(tokens[0].name, "old-token");
+        // Old-format tokens predate expiration entirely, so migration
+        // defaults them to Never rather than Session.
+        assert_eq!(tokens[0].expiration, Expiration::Never);
 
         // Verify token is accessible via new format key
         let new_key = format!("token:{}", old_token.token);
@@ -1067,11 +3027,7 @@ mod tests {
         let new_key = format!("token:{}", new_token.token);
         store.set(&new_key, &new_token_json).await.expect("store new token");
 
-        let new_entry = TokenEntry {
-            token_value: new_token.token.clone(),
-            name: new_token.name.clone(),
-            created_at: new_token.created_at,
-        };
+        let new_entry = TokenEntry::bearer(&new_token.name, &new_token.token, new_token.created_at);
         registry.add_token(&new_entry).await.expect("add new token to registry");
 
         // Run migration
@@ -1081,7 +3037,7 @@ mod tests {
         let tokens = registry.list_tokens().await.expect("list should succeed");
         assert_eq!(tokens.len(), 2);
 
-        let token_values: Vec<String> = tokens.iter().map(|t| t.token_value.clone()).collect();
+        let token_values: Vec<String> = tokens.iter().map(|t| t.kind.identity().to_string()).collect();
         assert!(token_values.contains(&old_token.token));
         assert!(token_values.contains(&new_token.token));
 
@@ -1092,4 +3048,167 @@ mod tests {
         let new_value = store.get(&new_key).await.expect("get should succeed");
         assert!(new_value.is_some(), "new format key should still exist");
     }
+
+    #[tokio::test]
+    async fn test_migrate_credentials_into_moves_values_and_deletes_source() {
+        use crate::storage::{FileStore, SecretStore};
+        use std::sync::Arc;
+
+        let source_dir = tempfile::tempdir().expect("create temp dir");
+        let source_store = Arc::new(
+            FileStore::new(source_dir.path().to_path_buf())
+                .await
+                .expect("create source FileStore"),
+        );
+        source_store
+            .set("credential:exa:api_key", b"secret-value")
+            .await
+            .expect("seed source credential");
+        let registry = Registry::new(Arc::clone(&source_store) as Arc<dyn SecretStore>);
+
+        let target_dir = tempfile::tempdir().expect("create temp dir");
+        let target_store: Arc<dyn SecretStore> = Arc::new(
+            FileStore::new(target_dir.path().to_path_buf())
+                .await
+                .expect("create target FileStore"),
+        );
+
+        registry
+            .migrate_credentials_into(Arc::clone(&target_store))
+            .await
+            .expect("migration should succeed");
+
+        let moved = target_store
+            .get("credential:exa:api_key")
+            .await
+            .expect("get should succeed")
+            .expect("value should have moved");
+        assert_eq!(moved, b"secret-value");
+
+        let left_behind = source_store
+            .get("credential:exa:api_key")
+            .await
+            .expect("get should succeed");
+        assert!(left_behind.is_none(), "credential should be removed from the source store");
+    }
+
+    #[tokio::test]
+    async fn test_migrate_credentials_into_is_idempotent() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let source_dir = tempfile::tempdir().expect("create temp dir");
+        let source_store = Arc::new(
+            FileStore::new(source_dir.path().to_path_buf())
+                .await
+                .expect("create source FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&source_store) as Arc<dyn SecretStore>);
+
+        let target_dir = tempfile::tempdir().expect("create temp dir");
+        let target_store: Arc<dyn SecretStore> = Arc::new(
+            FileStore::new(target_dir.path().to_path_buf())
+                .await
+                .expect("create target FileStore"),
+        );
+
+        // Nothing to migrate - should return cleanly rather than erroring.
+        registry
+            .migrate_credentials_into(Arc::clone(&target_store))
+            .await
+            .expect("migration of an empty source should be a no-op");
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_bootstraps_fresh_install_to_current_schema() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        registry
+            .run_migrations(store.as_ref())
+            .await
+            .expect("migrations should succeed on a fresh install");
+
+        let loaded = registry.load().await.expect("load should succeed");
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_stamps_schema_version_onto_existing_registry() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        // Simulate a registry written before `schema_version` existed: a
+        // normal save, which never sets the field explicitly.
+        let entry = TokenEntry::bearer("agent-1", "acp_abc123", Utc::now());
+        registry.add_token(&entry).await.expect("add token");
+
+        registry
+            .run_migrations(store.as_ref())
+            .await
+            .expect("migrations should succeed");
+
+        let loaded = registry.load().await.expect("load should succeed");
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+        assert_eq!(loaded.tokens.len(), 1, "existing data must survive the stamp");
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_is_idempotent() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        registry.run_migrations(store.as_ref()).await.expect("first run");
+        registry.run_migrations(store.as_ref()).await.expect("second run should be a no-op");
+
+        let loaded = registry.load().await.expect("load should succeed");
+        assert_eq!(loaded.schema_version, SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_run_migrations_refuses_to_downgrade() {
+        use crate::storage::FileStore;
+        use std::sync::Arc;
+
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(
+            FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+        );
+        let registry = Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>);
+
+        let mut data = RegistryData {
+            schema_version: SCHEMA_VERSION + 1,
+            ..RegistryData::default()
+        };
+        registry.save(&mut data).await.expect("save should succeed");
+
+        let result = registry.run_migrations(store.as_ref()).await;
+        assert!(result.is_err());
+    }
 }
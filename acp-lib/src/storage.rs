@@ -38,6 +38,79 @@ pub trait SecretStore: Send + Sync {
     ///
     /// Returns Ok(()) even if the key doesn't exist (idempotent).
     async fn delete(&self, key: &str) -> Result<()>;
+
+    /// Enumerate every stored key starting with `prefix`, e.g. `"credential:exa:"`.
+    ///
+    /// Backends that can enumerate natively (a directory listing, an S3
+    /// `ListObjectsV2` call) do so directly. Backends whose native API can't
+    /// reliably list by our namespace prefix (Keychain, Secret Service, and
+    /// Windows Credential Manager all expect lookup by a known key, not
+    /// prefix search) instead maintain a small index secret alongside every
+    /// other value, updated on `set`/`delete`.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// A cheap, monotonically increasing generation counter for the whole
+    /// store, if the backend can provide one.
+    ///
+    /// Backends that can't cheaply watch for out-of-band changes (e.g. a
+    /// remote object store with no native change feed) can bump this on
+    /// every `set`/`delete` so a poller can detect "something changed"
+    /// without re-reading every key. Returns `None` when unsupported.
+    async fn revision(&self) -> Result<Option<u64>> {
+        Ok(None)
+    }
+}
+
+/// Reserved key under which backends that can't natively enumerate by
+/// prefix (Keychain, Secret Service, Windows Credential Manager) store their
+/// key index - see [`SecretStore::list`].
+const INDEX_KEY: &str = "__index__";
+
+/// Load the key index for a backend that maintains one under [`INDEX_KEY`].
+async fn index_load<S: SecretStore + ?Sized>(store: &S) -> Result<std::collections::HashSet<String>> {
+    match store.get(INDEX_KEY).await? {
+        Some(bytes) => Ok(serde_json::from_slice(&bytes).unwrap_or_default()),
+        None => Ok(std::collections::HashSet::new()),
+    }
+}
+
+/// Record `key` in the index, if it isn't there already. No-op for `INDEX_KEY`
+/// itself, so callers can unconditionally call this from `set` without recursing.
+async fn index_insert<S: SecretStore + ?Sized>(store: &S, key: &str) -> Result<()> {
+    if key == INDEX_KEY {
+        return Ok(());
+    }
+
+    let mut keys = index_load(store).await?;
+    if keys.insert(key.to_string()) {
+        let bytes = serde_json::to_vec(&keys)
+            .map_err(|e| crate::AcpError::storage(format!("Failed to encode key index: {}", e)))?;
+        store.set(INDEX_KEY, &bytes).await?;
+    }
+    Ok(())
+}
+
+/// Remove `key` from the index, if present. No-op for `INDEX_KEY` itself.
+async fn index_remove<S: SecretStore + ?Sized>(store: &S, key: &str) -> Result<()> {
+    if key == INDEX_KEY {
+        return Ok(());
+    }
+
+    let mut keys = index_load(store).await?;
+    if keys.remove(key) {
+        let bytes = serde_json::to_vec(&keys)
+            .map_err(|e| crate::AcpError::storage(format!("Failed to encode key index: {}", e)))?;
+        store.set(INDEX_KEY, &bytes).await?;
+    }
+    Ok(())
+}
+
+/// List every indexed key starting with `prefix`, sorted for stable output.
+async fn index_list<S: SecretStore + ?Sized>(store: &S, prefix: &str) -> Result<Vec<String>> {
+    let keys = index_load(store).await?;
+    let mut matched: Vec<String> = keys.into_iter().filter(|k| k.starts_with(prefix)).collect();
+    matched.sort();
+    Ok(matched)
 }
 
 /// File-based secret storage implementation
@@ -128,6 +201,10 @@ impl SecretStore for FileStore {
             Err(e) => Err(e.into()),
         }
     }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        self.list_internal(prefix).await
+    }
 }
 
 impl FileStore {
@@ -158,6 +235,192 @@ impl FileStore {
     }
 }
 
+/// The default scrypt cost parameter for [`EncryptedFileStore`]. `log_n =
+/// 17` (N = 2^17) is a reasonable "slow enough to matter, fast enough for a
+/// CLI" default; callers with stronger hardware (or weaker threat models
+/// needing faster unlocks) can override it via `with_log_n`.
+const DEFAULT_SCRYPT_LOG_N: u8 = 17;
+
+/// Passphrase-encrypted file-based secret storage
+///
+/// Like `FileStore`, but every value is sealed with XChaCha20-Poly1305
+/// before it touches disk, under a key derived from a user passphrase with
+/// scrypt. Each `set` picks a fresh random 16-byte salt and 24-byte nonce
+/// and writes `salt || nonce || ciphertext || tag` to the file, so a leaked
+/// backup or snapshot of the directory is useless without the passphrase.
+///
+/// The passphrase itself is kept in memory for the life of the store (to
+/// re-derive the key from each value's own salt on `get`) - it is never
+/// written to disk.
+pub struct EncryptedFileStore {
+    base_path: PathBuf,
+    passphrase: String,
+    /// scrypt `log_n` cost parameter; higher is slower to derive and harder
+    /// to brute-force.
+    log_n: u8,
+}
+
+const SCRYPT_SALT_LEN: usize = 16;
+const XCHACHA_NONCE_LEN: usize = 24;
+
+impl EncryptedFileStore {
+    /// Create a new EncryptedFileStore at `base_path`, using the default
+    /// scrypt cost ([`DEFAULT_SCRYPT_LOG_N`]).
+    pub async fn new(base_path: PathBuf, passphrase: impl Into<String>) -> Result<Self> {
+        Self::with_log_n(base_path, passphrase, DEFAULT_SCRYPT_LOG_N).await
+    }
+
+    /// Create a new EncryptedFileStore, overriding the scrypt `log_n` cost
+    /// (N = 2^log_n). Every value written under this store carries its own
+    /// salt, so `log_n` can be changed across restarts without breaking
+    /// values written under a previous setting.
+    pub async fn with_log_n(base_path: PathBuf, passphrase: impl Into<String>, log_n: u8) -> Result<Self> {
+        tokio::fs::create_dir_all(&base_path).await?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = std::fs::Permissions::from_mode(0o700);
+            std::fs::set_permissions(&base_path, perms)?;
+        }
+
+        Ok(Self {
+            base_path,
+            passphrase: passphrase.into(),
+            log_n,
+        })
+    }
+
+    fn key_to_filename(&self, key: &str) -> PathBuf {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let encoded = URL_SAFE_NO_PAD.encode(key.as_bytes());
+        self.base_path.join(encoded)
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> Result<[u8; 32]> {
+        let params = scrypt::Params::new(self.log_n, 8, 1, 32)
+            .map_err(|e| crate::AcpError::storage(format!("Invalid scrypt parameters: {}", e)))?;
+
+        let mut key = [0u8; 32];
+        scrypt::scrypt(self.passphrase.as_bytes(), salt, &params, &mut key)
+            .map_err(|e| crate::AcpError::storage(format!("scrypt key derivation failed: {}", e)))?;
+
+        Ok(key)
+    }
+}
+
+#[async_trait]
+impl SecretStore for EncryptedFileStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        use chacha20poly1305::aead::{rand_core::RngCore, Aead, OsRng};
+        use chacha20poly1305::{AeadCore, KeyInit, XChaCha20Poly1305};
+
+        let mut salt = [0u8; SCRYPT_SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+
+        let derived_key = self.derive_key(&salt)?;
+        let cipher = XChaCha20Poly1305::new((&derived_key).into());
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let ciphertext = cipher
+            .encrypt(&nonce, value)
+            .map_err(|e| crate::AcpError::storage(format!("Encryption failed for {}: {}", key, e)))?;
+
+        let mut sealed = Vec::with_capacity(salt.len() + nonce.len() + ciphertext.len());
+        sealed.extend_from_slice(&salt);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        let path = self.key_to_filename(key);
+        let temp_path = path.with_extension("tmp");
+        tokio::fs::write(&temp_path, &sealed).await?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let perms = std::fs::Permissions::from_mode(0o600);
+            std::fs::set_permissions(&temp_path, perms)?;
+        }
+
+        tokio::fs::rename(&temp_path, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use chacha20poly1305::aead::Aead;
+        use chacha20poly1305::{KeyInit, XChaCha20Poly1305, XNonce};
+
+        let path = self.key_to_filename(key);
+        let sealed = match tokio::fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        if sealed.len() < SCRYPT_SALT_LEN + XCHACHA_NONCE_LEN {
+            return Err(crate::AcpError::storage(format!(
+                "Sealed value for {} is too short to contain a salt and nonce",
+                key
+            )));
+        }
+
+        let (salt, rest) = sealed.split_at(SCRYPT_SALT_LEN);
+        let (nonce_bytes, ciphertext) = rest.split_at(XCHACHA_NONCE_LEN);
+
+        let derived_key = self.derive_key(salt)?;
+        let cipher = XChaCha20Poly1305::new((&derived_key).into());
+        let nonce = XNonce::from_slice(nonce_bytes);
+
+        let plaintext = cipher
+            .decrypt(nonce, ciphertext)
+            .map_err(|_| crate::AcpError::storage(format!("Decryption failed for {} (wrong passphrase or tampered data)", key)))?;
+
+        Ok(Some(plaintext))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let path = self.key_to_filename(key);
+
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let mut keys = Vec::new();
+
+        let mut entries = tokio::fs::read_dir(&self.base_path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name();
+            let encoded = match file_name.to_str() {
+                Some(s) => s,
+                None => continue,
+            };
+            let decoded = match URL_SAFE_NO_PAD.decode(encoded) {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+            let key = match String::from_utf8(decoded) {
+                Ok(key) => key,
+                Err(_) => continue,
+            };
+            if key.starts_with(prefix) {
+                keys.push(key);
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}
+
 /// macOS Keychain secret storage implementation
 ///
 /// Uses the macOS Keychain to securely store secrets.
@@ -192,6 +455,7 @@ impl SecretStore for KeychainStore {
         set_generic_password(&self.service_name, key, value)
             .map_err(|e| crate::AcpError::storage(format!("Keychain set failed: {}", e)))?;
 
+        index_insert(self, key).await?;
         Ok(())
     }
 
@@ -219,193 +483,1023 @@ impl SecretStore for KeychainStore {
         use security_framework::passwords::delete_generic_password;
 
         match delete_generic_password(&self.service_name, key) {
-            Ok(()) => Ok(()),
+            Ok(()) => {}
             Err(e) => {
                 // Check if it's a "not found" error (idempotent)
                 let err_str = format!("{:?}", e);
-                if err_str.contains("ItemNotFound") || err_str.contains("-25300") {
-                    Ok(())
-                } else {
-                    Err(crate::AcpError::storage(format!(
+                if !(err_str.contains("ItemNotFound") || err_str.contains("-25300")) {
+                    return Err(crate::AcpError::storage(format!(
                         "Keychain delete failed: {}",
                         e
-                    )))
+                    )));
                 }
             }
         }
+
+        index_remove(self, key).await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        index_list(self, prefix).await
     }
 }
 
-/// Factory function to create the appropriate SecretStore implementation
-///
-/// On macOS, returns a KeychainStore by default. If `data_dir` is provided,
-/// returns a FileStore instead (useful for containers/testing).
+/// Linux Secret Service (D-Bus) secret storage implementation
 ///
-/// On other platforms, always returns a FileStore.
+/// Talks to the freedesktop Secret Service (GNOME Keyring, KWallet) over
+/// D-Bus. Each secret is stored as a collection item in the user's default
+/// collection, labeled for humans but looked up by an `application`/`key`
+/// attribute pair so our `type:name:key` namespace round-trips exactly -
+/// unlike the label, item attributes aren't meant for display and are what
+/// `search_items` matches against.
 ///
-/// # Arguments
-/// * `data_dir` - Optional directory for FileStore. If None on macOS, uses Keychain.
-///   If None on other platforms, uses a default location.
-pub async fn create_store(data_dir: Option<PathBuf>) -> Result<Box<dyn SecretStore>> {
-    // Check for ACP_DATA_DIR environment variable first (useful for testing)
-    if let Ok(env_path) = std::env::var("ACP_DATA_DIR") {
-        let store = FileStore::new(PathBuf::from(env_path)).await?;
-        return Ok(Box::new(store));
+/// Only available on Linux. Connects fresh for every operation rather than
+/// holding a D-Bus session open, the same way `KeychainStore` doesn't cache
+/// anything either.
+#[cfg(target_os = "linux")]
+pub struct SecretServiceStore {
+    application: String,
+}
+
+#[cfg(target_os = "linux")]
+impl SecretServiceStore {
+    /// Create a new SecretServiceStore, namespaced by `application`.
+    ///
+    /// Fails if no Secret Service daemon is reachable over D-Bus, so callers
+    /// (like `create_store`) can fall back to `FileStore`.
+    pub async fn new(application: impl Into<String>) -> Result<Self> {
+        use secret_service::SecretService;
+
+        // Connect once just to confirm a daemon is actually reachable;
+        // every operation below opens its own connection.
+        SecretService::connect(secret_service::EncryptionType::Dh)
+            .await
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service unreachable: {}", e)))?;
+
+        Ok(Self {
+            application: application.into(),
+        })
     }
 
-    match data_dir {
-        Some(path) => {
-            // Explicit file storage requested
-            let store = FileStore::new(path).await?;
-            Ok(Box::new(store))
-        }
-        None => {
-            // Platform-specific default
-            #[cfg(target_os = "macos")]
-            {
-                let store = KeychainStore::new("com.acp.credentials")?;
-                Ok(Box::new(store))
-            }
+    async fn collection(
+        service: &secret_service::SecretService<'_>,
+    ) -> Result<secret_service::Collection<'_>> {
+        let collection = service
+            .get_default_collection()
+            .await
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service get_default_collection failed: {}", e)))?;
 
-            #[cfg(not(target_os = "macos"))]
-            {
-                // Use default location: ~/.acp/secrets
-                let home = std::env::var("HOME")
-                    .or_else(|_| std::env::var("USERPROFILE"))
-                    .map_err(|_| crate::AcpError::storage("Cannot determine home directory"))?;
-                let path = PathBuf::from(home).join(".acp").join("secrets");
-                let store = FileStore::new(path).await?;
-                Ok(Box::new(store))
-            }
+        if collection
+            .is_locked()
+            .await
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service is_locked failed: {}", e)))?
+        {
+            collection
+                .unlock()
+                .await
+                .map_err(|e| crate::AcpError::storage(format!("Secret Service unlock failed: {}", e)))?;
         }
+
+        Ok(collection)
+    }
+
+    fn attributes(&self, key: &str) -> std::collections::HashMap<&str, &str> {
+        let mut attrs = std::collections::HashMap::new();
+        attrs.insert("application", self.application.as_str());
+        attrs.insert("key", key);
+        attrs
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+#[cfg(target_os = "linux")]
+#[async_trait]
+impl SecretStore for SecretServiceStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        use secret_service::{EncryptionType, SecretService};
 
-    /// Test helper to verify SecretStore implementation
-    async fn test_store_implementation<S: SecretStore>(store: S) {
-        // Test set and get
-        store
-            .set("test:key1", b"value1")
+        let service = SecretService::connect(EncryptionType::Dh)
             .await
-            .expect("set should succeed");
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service connect failed: {}", e)))?;
+        let collection = Self::collection(&service).await?;
 
-        let value = store
-            .get("test:key1")
+        collection
+            .create_item(
+                &format!("ACP secret ({})", key),
+                self.attributes(key),
+                value,
+                true, // replace any existing item with the same attributes
+                "text/plain",
+            )
             .await
-            .expect("get should succeed")
-            .expect("value should exist");
-        assert_eq!(value, b"value1");
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service create_item failed for {}: {}", key, e)))?;
 
-        // Test get non-existent key
-        let missing = store
-            .get("test:missing")
-            .await
-            .expect("get should succeed");
-        assert!(missing.is_none(), "missing key should return None");
+        index_insert(self, key).await?;
+        Ok(())
+    }
 
-        // Test overwrite
-        store
-            .set("test:key1", b"value2")
-            .await
-            .expect("overwrite should succeed");
-        let value = store
-            .get("test:key1")
-            .await
-            .expect("get should succeed")
-            .expect("value should exist");
-        assert_eq!(value, b"value2");
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use secret_service::{EncryptionType, SecretService};
 
-        // Test binary data
-        let binary_data = vec![0u8, 1, 2, 255, 128];
-        store
-            .set("test:binary", &binary_data)
-            .await
-            .expect("binary set should succeed");
-        let retrieved = store
-            .get("test:binary")
+        let service = SecretService::connect(EncryptionType::Dh)
             .await
-            .expect("get should succeed")
-            .expect("value should exist");
-        assert_eq!(retrieved, binary_data);
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service connect failed: {}", e)))?;
+        let collection = Self::collection(&service).await?;
 
-        // Test delete
-        store
-            .delete("test:key1")
+        let items = collection
+            .search_items(self.attributes(key))
             .await
-            .expect("delete should succeed");
-        let deleted = store
-            .get("test:key1")
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service search_items failed for {}: {}", key, e)))?;
+
+        match items.first() {
+            Some(item) => {
+                let secret = item
+                    .get_secret()
+                    .await
+                    .map_err(|e| crate::AcpError::storage(format!("Secret Service get_secret failed for {}: {}", key, e)))?;
+                Ok(Some(secret))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        use secret_service::{EncryptionType, SecretService};
+
+        let service = SecretService::connect(EncryptionType::Dh)
             .await
-            .expect("get should succeed");
-        assert!(deleted.is_none(), "deleted key should not exist");
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service connect failed: {}", e)))?;
+        let collection = Self::collection(&service).await?;
 
-        // Test delete idempotency
-        store
-            .delete("test:key1")
+        let items = collection
+            .search_items(self.attributes(key))
             .await
-            .expect("second delete should succeed");
+            .map_err(|e| crate::AcpError::storage(format!("Secret Service search_items failed for {}: {}", key, e)))?;
 
-        // Cleanup
-        store.delete("test:binary").await.ok();
-    }
+        for item in items {
+            item.delete()
+                .await
+                .map_err(|e| crate::AcpError::storage(format!("Secret Service delete failed for {}: {}", key, e)))?;
+        }
 
-    #[tokio::test]
-    async fn test_file_store() {
-        let temp_dir = tempfile::tempdir().expect("create temp dir");
-        let store = FileStore::new(temp_dir.path().to_path_buf())
-            .await
-            .expect("create FileStore");
+        index_remove(self, key).await?;
+        Ok(())
+    }
 
-        test_store_implementation(store).await;
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        index_list(self, prefix).await
     }
+}
 
-    #[tokio::test]
-    async fn test_file_store_permissions() {
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
+/// Windows Credential Manager secret storage implementation
+///
+/// Stores each secret as a generic credential via `CredWrite`/`CredRead`/
+/// `CredDelete`, using our namespaced key (`type:name:key`) directly as the
+/// target name and the binary value as the credential blob.
+///
+/// Only available on Windows. `CredentialBlob` is capped at
+/// [`MAX_CREDENTIAL_BLOB_SIZE`] bytes by the Windows API; `set` returns a
+/// clear `AcpError::storage` instead of letting `CredWrite` fail opaquely
+/// when a value would exceed it.
+#[cfg(target_os = "windows")]
+pub struct WinCredStore {
+    prefix: String,
+}
 
-            let temp_dir = tempfile::tempdir().expect("create temp dir");
-            let store = FileStore::new(temp_dir.path().to_path_buf())
-                .await
-                .expect("create FileStore");
+/// The maximum size of a `CREDENTIALW.CredentialBlob`, per the Windows API
+/// (`CRED_MAX_CREDENTIAL_BLOB_SIZE` = 5 * 512 bytes).
+#[cfg(target_os = "windows")]
+const MAX_CREDENTIAL_BLOB_SIZE: usize = 5 * 512;
 
-            // Check directory permissions
-            let metadata = std::fs::metadata(temp_dir.path()).expect("get metadata");
-            let mode = metadata.permissions().mode();
-            assert_eq!(mode & 0o777, 0o700, "directory should have mode 0700");
+#[cfg(target_os = "windows")]
+impl WinCredStore {
+    /// Create a new WinCredStore, namespacing every target name under `prefix`.
+    pub fn new(prefix: impl Into<String>) -> Result<Self> {
+        Ok(Self {
+            prefix: prefix.into(),
+        })
+    }
+
+    fn target_name(&self, key: &str) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt;
+
+        std::ffi::OsStr::new(&format!("{}:{}", self.prefix, key))
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect()
+    }
+}
+
+#[cfg(target_os = "windows")]
+#[async_trait]
+impl SecretStore for WinCredStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        use windows::Win32::Security::Credentials::{
+            CredWriteW, CREDENTIALW, CRED_PERSIST_LOCAL_MACHINE, CRED_TYPE_GENERIC,
+        };
+
+        if value.len() > MAX_CREDENTIAL_BLOB_SIZE {
+            return Err(crate::AcpError::storage(format!(
+                "value for {} is {} bytes, exceeding the {}-byte CredentialBlob limit",
+                key,
+                value.len(),
+                MAX_CREDENTIAL_BLOB_SIZE
+            )));
+        }
+
+        let mut target_name = self.target_name(key);
+        let mut blob = value.to_vec();
+
+        let credential = CREDENTIALW {
+            Flags: 0,
+            Type: CRED_TYPE_GENERIC,
+            TargetName: windows::core::PWSTR(target_name.as_mut_ptr()),
+            Comment: windows::core::PWSTR::null(),
+            LastWritten: Default::default(),
+            CredentialBlobSize: blob.len() as u32,
+            CredentialBlob: blob.as_mut_ptr(),
+            Persist: CRED_PERSIST_LOCAL_MACHINE,
+            AttributeCount: 0,
+            Attributes: std::ptr::null_mut(),
+            TargetAlias: windows::core::PWSTR::null(),
+            UserName: windows::core::PWSTR::null(),
+        };
+
+        unsafe {
+            CredWriteW(&credential, 0)
+                .map_err(|e| crate::AcpError::storage(format!("CredWrite failed for {}: {}", key, e)))?;
+        }
+
+        index_insert(self, key).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use windows::Win32::Security::Credentials::{CredFree, CredReadW, CRED_TYPE_GENERIC};
+        use windows::Win32::Foundation::ERROR_NOT_FOUND;
+
+        let target_name = self.target_name(key);
+
+        unsafe {
+            let mut credential_ptr = std::ptr::null_mut();
+            match CredReadW(
+                windows::core::PCWSTR(target_name.as_ptr()),
+                CRED_TYPE_GENERIC,
+                0,
+                &mut credential_ptr,
+            ) {
+                Ok(()) => {
+                    let credential = &*credential_ptr;
+                    let blob = std::slice::from_raw_parts(
+                        credential.CredentialBlob,
+                        credential.CredentialBlobSize as usize,
+                    )
+                    .to_vec();
+                    CredFree(credential_ptr as *const _);
+                    Ok(Some(blob))
+                }
+                Err(e) if e.code() == ERROR_NOT_FOUND.to_hresult() => Ok(None),
+                Err(e) => Err(crate::AcpError::storage(format!("CredRead failed for {}: {}", key, e))),
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        use windows::Win32::Security::Credentials::{CredDeleteW, CRED_TYPE_GENERIC};
+        use windows::Win32::Foundation::ERROR_NOT_FOUND;
+
+        let target_name = self.target_name(key);
+
+        unsafe {
+            match CredDeleteW(windows::core::PCWSTR(target_name.as_ptr()), CRED_TYPE_GENERIC, 0) {
+                Ok(()) => {}
+                Err(e) if e.code() == ERROR_NOT_FOUND.to_hresult() => {}
+                Err(e) => return Err(crate::AcpError::storage(format!("CredDelete failed for {}: {}", key, e))),
+            }
+        }
+
+        index_remove(self, key).await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        index_list(self, prefix).await
+    }
+}
+
+/// S3-compatible object-store secret storage implementation
+///
+/// Stores each namespaced key (`token:*`, `plugin:*`, `credential:*`, ...) as an
+/// object in a bucket, so `TokenCache`, `Registry`, and `find_matching_plugin`
+/// can all run against a single shared backend across multiple ACP hosts.
+/// Works against AWS S3 as well as S3-compatible endpoints (Garage, MinIO) by
+/// allowing a custom `endpoint` and forcing path-style addressing.
+pub struct ObjectStore {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+    prefix: String,
+}
+
+/// Configuration for connecting an [`ObjectStore`] to an S3-compatible endpoint
+#[derive(Debug, Clone)]
+pub struct ObjectStoreConfig {
+    pub bucket: String,
+    pub region: String,
+    /// Optional key prefix applied to every object (e.g. "acp/")
+    pub prefix: String,
+    /// Optional custom endpoint for S3-compatible services like MinIO/Garage
+    pub endpoint: Option<String>,
+    /// Optional static access key, for endpoints that don't support the
+    /// standard AWS SDK provider chain. Must be set together with
+    /// `secret_access_key`; leave both `None` to use the provider chain.
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+impl ObjectStore {
+    /// Create a new ObjectStore from the given configuration
+    ///
+    /// Credentials are resolved through the standard AWS SDK provider chain
+    /// (environment, shared config, IMDS) unless `access_key_id` and
+    /// `secret_access_key` are both set, in which case those static
+    /// credentials are used directly. When `endpoint` is set, path-style
+    /// addressing is forced since most S3-compatible services don't support
+    /// virtual-hosted buckets.
+    pub async fn new(config: ObjectStoreConfig) -> Result<Self> {
+        let region = aws_sdk_s3::config::Region::new(config.region.clone());
+        let shared_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(region)
+            .load()
+            .await;
+
+        let mut s3_config_builder = aws_sdk_s3::config::Builder::from(&shared_config);
+        if let Some(endpoint) = &config.endpoint {
+            s3_config_builder = s3_config_builder
+                .endpoint_url(endpoint)
+                .force_path_style(true);
+        }
+        if let (Some(access_key_id), Some(secret_access_key)) =
+            (&config.access_key_id, &config.secret_access_key)
+        {
+            s3_config_builder = s3_config_builder.credentials_provider(aws_sdk_s3::config::Credentials::new(
+                access_key_id,
+                secret_access_key,
+                None,
+                None,
+                "acp-object-store-config",
+            ));
+        }
+
+        let client = aws_sdk_s3::Client::from_conf(s3_config_builder.build());
+
+        Ok(Self {
+            client,
+            bucket: config.bucket,
+            prefix: config.prefix,
+        })
+    }
+
+    /// Map a namespaced key to an object key under our prefix
+    fn object_key(&self, key: &str) -> String {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        format!("{}{}", self.prefix, URL_SAFE_NO_PAD.encode(key.as_bytes()))
+    }
+}
+
+#[async_trait]
+impl SecretStore for ObjectStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .body(value.to_vec().into())
+            .send()
+            .await
+            .map_err(|e| crate::AcpError::storage(format!("S3 put_object failed for {}: {}", key, e)))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+        {
+            Ok(output) => {
+                let bytes = output
+                    .body
+                    .collect()
+                    .await
+                    .map_err(|e| crate::AcpError::storage(format!("S3 body read failed for {}: {}", key, e)))?
+                    .into_bytes()
+                    .to_vec();
+                Ok(Some(bytes))
+            }
+            Err(err) => {
+                let service_err = err.into_service_error();
+                if service_err.is_no_such_key() {
+                    Ok(None)
+                } else {
+                    Err(AcpError::storage(format!(
+                        "S3 get_object failed for {}: {}",
+                        key, service_err
+                    )))
+                }
+            }
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        // DeleteObject is idempotent on S3 already - a missing key is not an error.
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(self.object_key(key))
+            .send()
+            .await
+            .map_err(|e| crate::AcpError::storage(format!("S3 delete_object failed for {}: {}", key, e)))?;
+
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+        use base64::Engine;
+
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let mut request = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket)
+                .prefix(&self.prefix);
+            if let Some(token) = continuation_token.take() {
+                request = request.continuation_token(token);
+            }
+
+            let output = request
+                .send()
+                .await
+                .map_err(|e| crate::AcpError::storage(format!("S3 list_objects_v2 failed: {}", e)))?;
+
+            for object in output.contents() {
+                let Some(object_key) = object.key() else {
+                    continue;
+                };
+                let Some(encoded) = object_key.strip_prefix(&self.prefix) else {
+                    continue;
+                };
+                let Ok(decoded) = URL_SAFE_NO_PAD.decode(encoded) else {
+                    continue;
+                };
+                let Ok(key) = String::from_utf8(decoded) else {
+                    continue;
+                };
+                if key.starts_with(prefix) {
+                    keys.push(key);
+                }
+            }
+
+            match output.next_continuation_token() {
+                Some(token) => continuation_token = Some(token.to_string()),
+                None => break,
+            }
+        }
+
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+/// Envelope-encrypting wrapper around any [`SecretStore`]
+///
+/// Seals every value with XChaCha20-Poly1305 under a master key before
+/// delegating to the inner store, so a backend that only offers read access
+/// control (shared S3 bucket, a backup snapshot) never sees plaintext
+/// secrets. Each `set` generates a fresh random 24-byte nonce and stores
+/// `nonce || ciphertext`; `get` splits the two back apart before decrypting.
+pub struct EncryptingStore<S: SecretStore> {
+    inner: S,
+    key: chacha20poly1305::XChaCha20Poly1305,
+}
+
+impl<S: SecretStore> EncryptingStore<S> {
+    /// Wrap `inner` using a 32-byte master key directly (e.g. loaded from an
+    /// external key file).
+    pub fn new(inner: S, master_key: &[u8; 32]) -> Self {
+        use chacha20poly1305::KeyInit;
+
+        Self {
+            inner,
+            key: chacha20poly1305::XChaCha20Poly1305::new(master_key.into()),
+        }
+    }
+
+    /// Wrap `inner`, deriving the master key from a passphrase with Argon2.
+    ///
+    /// The salt must be stable across process restarts (e.g. persisted
+    /// alongside the store) since it's needed to re-derive the same key.
+    pub fn from_passphrase(inner: S, passphrase: &str, salt: &[u8]) -> Result<Self> {
+        use argon2::Argon2;
+
+        let mut master_key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut master_key)
+            .map_err(|e| AcpError::storage(format!("Failed to derive key from passphrase: {}", e)))?;
+
+        Ok(Self::new(inner, &master_key))
+    }
+}
+
+#[async_trait]
+impl<S: SecretStore> SecretStore for EncryptingStore<S> {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        use chacha20poly1305::aead::{Aead, OsRng};
+        use chacha20poly1305::AeadCore;
+
+        let nonce = chacha20poly1305::XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .key
+            .encrypt(&nonce, value)
+            .map_err(|e| AcpError::storage(format!("Encryption failed for {}: {}", key, e)))?;
+
+        let mut sealed = Vec::with_capacity(nonce.len() + ciphertext.len());
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+
+        self.inner.set(key, &sealed).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use chacha20poly1305::aead::Aead;
+
+        let sealed = match self.inner.get(key).await? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+
+        const NONCE_LEN: usize = 24;
+        if sealed.len() < NONCE_LEN {
+            return Err(AcpError::storage(format!(
+                "Sealed value for {} is too short to contain a nonce",
+                key
+            )));
+        }
+
+        let (nonce_bytes, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce = chacha20poly1305::XNonce::from_slice(nonce_bytes);
+
+        let plaintext = self
+            .key
+            .decrypt(nonce, ciphertext)
+            .map_err(|_| AcpError::storage(format!("Decryption failed for {} (wrong key or tampered data)", key)))?;
+
+        Ok(Some(plaintext))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.inner.delete(key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        // Keys themselves aren't encrypted, only values - so the inner store
+        // can enumerate them directly.
+        self.inner.list(prefix).await
+    }
+}
+
+/// Routes keys to one of two backends by namespace prefix
+///
+/// `create_store`'s platform default uses this to keep `credential:*`
+/// entries in an OS secret service (Keychain, Secret Service, Credential
+/// Manager) while leaving `token:*`, `plugin:*`, and `_registry` in a plain
+/// `FileStore` - those backends expect lookup by a small, known set of keys,
+/// not the high-churn, enumerate-everything access pattern the rest of the
+/// registry needs.
+pub struct CompositeStore {
+    credentials: Box<dyn SecretStore>,
+    other: Box<dyn SecretStore>,
+}
+
+impl CompositeStore {
+    /// `credentials` backs every `credential:*` key; `other` backs everything
+    /// else.
+    pub fn new(credentials: Box<dyn SecretStore>, other: Box<dyn SecretStore>) -> Self {
+        Self { credentials, other }
+    }
+
+    fn backend_for(&self, key: &str) -> &dyn SecretStore {
+        if key.starts_with("credential:") {
+            self.credentials.as_ref()
+        } else {
+            self.other.as_ref()
+        }
+    }
+}
+
+#[async_trait]
+impl SecretStore for CompositeStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.backend_for(key).set(key, value).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.backend_for(key).get(key).await
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.backend_for(key).delete(key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        // A namespace prefix never spans both backends in practice (the
+        // registry always lists by one of "credential:", "token:", or
+        // "plugin:"), so just pick the backend the prefix itself belongs to.
+        if prefix.starts_with("credential:") {
+            self.credentials.list(prefix).await
+        } else {
+            self.other.list(prefix).await
+        }
+    }
+
+    async fn revision(&self) -> Result<Option<u64>> {
+        match (self.credentials.revision().await?, self.other.revision().await?) {
+            (Some(a), Some(b)) => Ok(Some(a + b)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// External credential-helper secret storage implementation
+///
+/// Shells out to a user-configured helper binary for every operation, using
+/// a line-based JSON protocol on stdin/stdout - one request, one response,
+/// one process per operation, the same shape as `credential_provider`'s
+/// provider protocol and Cargo's credential-provider model. This lets the
+/// ecosystem add new backends (1Password, `pass`, Vault, ...) without
+/// modifying this crate: point `ACP_CREDENTIAL_HELPER` (or the
+/// `credential_helper` config field) at the helper's path and `create_store`
+/// picks it up.
+///
+/// ```text
+/// -> {"op":"get","key":"credential:aws-s3:access_key"}
+/// <- {"value":"<base64>"}
+/// <- {"found":false}
+///
+/// -> {"op":"set","key":"...","value":"<base64>"}
+/// <- {"ok":true}
+///
+/// -> {"op":"delete","key":"..."}
+/// <- {"ok":true}
+/// ```
+///
+/// The helper can't be asked to enumerate by prefix, so `list` is backed by
+/// the same `__index__`-key scheme used by `KeychainStore` and
+/// `SecretServiceStore`.
+pub struct ProcessStore {
+    helper: String,
+}
+
+#[derive(Debug, Clone, serde::Deserialize)]
+struct HelperResponse {
+    #[serde(default)]
+    ok: Option<bool>,
+    #[serde(default)]
+    found: Option<bool>,
+    #[serde(default)]
+    value: Option<String>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+impl ProcessStore {
+    /// Create a new ProcessStore that shells out to `helper` for every operation.
+    ///
+    /// `helper` is parsed with shell-style quoting rules (`shell_words::split`),
+    /// so a fixed argument can itself contain embedded spaces: the first word
+    /// is the executable, the rest are fixed arguments.
+    pub fn new(helper: impl Into<String>) -> Self {
+        Self {
+            helper: helper.into(),
+        }
+    }
+
+    async fn run(&self, request: serde_json::Value) -> Result<HelperResponse> {
+        use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+        let mut parts = shell_words::split(&self.helper)
+            .map_err(|e| AcpError::storage(format!("invalid ACP_CREDENTIAL_HELPER '{}': {}", self.helper, e)))?
+            .into_iter();
+        let program = parts
+            .next()
+            .ok_or_else(|| AcpError::storage("ACP_CREDENTIAL_HELPER is empty".to_string()))?;
+        let args: Vec<String> = parts.collect();
+
+        let mut child = tokio::process::Command::new(&program)
+            .args(&args)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| AcpError::storage(format!("failed to spawn credential helper '{}': {}", self.helper, e)))?;
+
+        let mut request_line = serde_json::to_vec(&request)
+            .map_err(|e| AcpError::storage(format!("failed to encode credential helper request: {}", e)))?;
+        request_line.push(b'\n');
+
+        {
+            let stdin = child
+                .stdin
+                .as_mut()
+                .ok_or_else(|| AcpError::storage("credential helper stdin unavailable".to_string()))?;
+            stdin
+                .write_all(&request_line)
+                .await
+                .map_err(|e| AcpError::storage(format!("failed to write to credential helper: {}", e)))?;
+        }
+
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| AcpError::storage("credential helper stdout unavailable".to_string()))?;
+        let mut lines = BufReader::new(stdout).lines();
+        let response_line = lines
+            .next_line()
+            .await
+            .map_err(|e| AcpError::storage(format!("failed to read from credential helper: {}", e)))?
+            .ok_or_else(|| AcpError::storage("credential helper closed stdout without a response".to_string()))?;
+
+        let _ = child.wait().await;
+
+        let response: HelperResponse = serde_json::from_str(&response_line)
+            .map_err(|e| AcpError::storage(format!("invalid credential helper response: {}", e)))?;
+
+        if let Some(message) = &response.error {
+            return Err(AcpError::storage(format!("credential helper error: {}", message)));
+        }
+
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl SecretStore for ProcessStore {
+    async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+
+        self.run(serde_json::json!({
+            "op": "set",
+            "key": key,
+            "value": STANDARD.encode(value),
+        }))
+        .await?;
+
+        index_insert(self, key).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use base64::engine::general_purpose::STANDARD;
+        use base64::Engine;
+
+        let response = self
+            .run(serde_json::json!({
+                "op": "get",
+                "key": key,
+            }))
+            .await?;
+
+        if response.found == Some(false) {
+            return Ok(None);
+        }
+
+        match response.value {
+            Some(encoded) => {
+                let decoded = STANDARD
+                    .decode(&encoded)
+                    .map_err(|e| AcpError::storage(format!("credential helper returned invalid base64 for {}: {}", key, e)))?;
+                Ok(Some(decoded))
+            }
+            None => Ok(None),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        self.run(serde_json::json!({
+            "op": "delete",
+            "key": key,
+        }))
+        .await?;
+
+        index_remove(self, key).await?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        index_list(self, prefix).await
+    }
+}
+
+/// The object key under which [`create_object_store_from_url`] persists the
+/// scrypt-derived encryption salt, so restarts re-derive the same key from
+/// `ACP_STORAGE_PASSPHRASE` instead of locking themselves out.
+const S3_ENCRYPTION_SALT_KEY: &str = "__acp_encryption_salt__";
+
+/// Build an [`ObjectStore`] from an `ACP_STORAGE_URL` of the form
+/// `s3://bucket/prefix`, wrapping it in [`EncryptingStore`] when
+/// `ACP_STORAGE_PASSPHRASE` is set.
+///
+/// A bucket is far less trusted than a local keychain, so unlike the other
+/// `create_store` branches this one defaults to encrypting at rest whenever
+/// a passphrase is available. Region, endpoint, and static credentials are
+/// read from `ACP_S3_REGION`, `ACP_S3_ENDPOINT`, `ACP_S3_ACCESS_KEY_ID`, and
+/// `ACP_S3_SECRET_ACCESS_KEY`.
+async fn create_object_store_from_url(url: &str) -> Result<Box<dyn SecretStore>> {
+    let (bucket, prefix) = parse_s3_url(url)?;
+
+    let config = ObjectStoreConfig {
+        bucket,
+        region: std::env::var("ACP_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+        prefix,
+        endpoint: std::env::var("ACP_S3_ENDPOINT").ok(),
+        access_key_id: std::env::var("ACP_S3_ACCESS_KEY_ID").ok(),
+        secret_access_key: std::env::var("ACP_S3_SECRET_ACCESS_KEY").ok(),
+    };
+
+    let store = ObjectStore::new(config).await?;
+
+    match std::env::var("ACP_STORAGE_PASSPHRASE") {
+        Ok(passphrase) => {
+            let salt = load_or_create_salt(&store).await?;
+            let encrypted = EncryptingStore::from_passphrase(store, &passphrase, &salt)?;
+            Ok(Box::new(encrypted))
+        }
+        Err(_) => Ok(Box::new(store)),
+    }
+}
+
+/// Split an `s3://bucket/prefix` URL into its bucket and a trailing-slash-
+/// normalized prefix (`""` if the URL has no prefix segment).
+fn parse_s3_url(url: &str) -> Result<(String, String)> {
+    let rest = url
+        .strip_prefix("s3://")
+        .ok_or_else(|| crate::AcpError::storage(format!("ACP_STORAGE_URL '{}' is not an s3:// URL", url)))?;
+    let (bucket, prefix) = match rest.split_once('/') {
+        Some((bucket, prefix)) if !prefix.trim_matches('/').is_empty() => {
+            (bucket.to_string(), format!("{}/", prefix.trim_matches('/')))
+        }
+        Some((bucket, _)) => (bucket.to_string(), String::new()),
+        None => (rest.to_string(), String::new()),
+    };
+    if bucket.is_empty() {
+        return Err(crate::AcpError::storage(format!(
+            "ACP_STORAGE_URL '{}' is missing a bucket name",
+            url
+        )));
+    }
+
+    Ok((bucket, prefix))
+}
+
+/// Fetch the encryption salt persisted under [`S3_ENCRYPTION_SALT_KEY`],
+/// generating and storing a fresh one on first use.
+async fn load_or_create_salt(store: &ObjectStore) -> Result<Vec<u8>> {
+    if let Some(salt) = store.get(S3_ENCRYPTION_SALT_KEY).await? {
+        return Ok(salt);
+    }
+
+    use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    store.set(S3_ENCRYPTION_SALT_KEY, &salt).await?;
+    Ok(salt)
+}
+
+/// Factory function to create the appropriate SecretStore implementation
+///
+/// On macOS, returns a CompositeStore pairing KeychainStore (for
+/// `credential:*`) with FileStore (for everything else) by default. If
+/// `data_dir` is provided, returns a plain FileStore instead (useful for
+/// containers/testing).
+///
+/// On Linux, does the same pairing with a SecretServiceStore by default,
+/// falling back to a plain FileStore when no Secret Service daemon (GNOME
+/// Keyring, KWallet) is reachable over D-Bus - e.g. a headless server or
+/// container.
+///
+/// On Windows, pairs a WinCredStore backed by Credential Manager the same way.
+///
+/// On other platforms, always returns a FileStore.
+///
+/// If `ACP_CREDENTIAL_HELPER` names an executable, it takes priority over
+/// every platform default and returns a [`ProcessStore`] instead, letting
+/// users plug in a backend (1Password, `pass`, Vault, ...) we don't ship.
+///
+/// # Arguments
+/// * `data_dir` - Optional directory for FileStore. If None, uses the
+///   platform default described above.
+pub async fn create_store(data_dir: Option<PathBuf>) -> Result<Box<dyn SecretStore>> {
+    // An explicit credential helper always wins - it's the most specific
+    // thing the user could have told us.
+    if let Ok(helper) = std::env::var("ACP_CREDENTIAL_HELPER") {
+        return Ok(Box::new(ProcessStore::new(helper)));
+    }
+
+    // A remote object-storage URL is the next most specific override, for
+    // multi-node deployments that need a shared backend rather than one
+    // host's disk or keychain.
+    if let Ok(url) = std::env::var("ACP_STORAGE_URL") {
+        return create_object_store_from_url(&url).await;
+    }
 
-            // Write a file and check permissions
-            store
-                .set("test:perm", b"value")
-                .await
-                .expect("set should succeed");
+    // Check for ACP_DATA_DIR environment variable first (useful for testing)
+    if let Ok(env_path) = std::env::var("ACP_DATA_DIR") {
+        let store = FileStore::new(PathBuf::from(env_path)).await?;
+        return Ok(Box::new(store));
+    }
 
-            let file_path = store.key_to_filename("test:perm");
-            let file_metadata = std::fs::metadata(&file_path).expect("get file metadata");
-            let file_mode = file_metadata.permissions().mode();
-            assert_eq!(
-                file_mode & 0o777,
-                0o600,
-                "file should have mode 0600"
-            );
+    match data_dir {
+        Some(path) => {
+            // Explicit file storage requested
+            let store = FileStore::new(path).await?;
+            Ok(Box::new(store))
+        }
+        None => {
+            // Platform-specific default. Everything but `credential:*` keys
+            // goes to FileStore (tokens, plugins, and the registry itself
+            // enumerate by prefix and churn on every write, which doesn't
+            // suit a keyring); `credential:*` keys route to the OS secret
+            // service when one is reachable, via CompositeStore.
+            #[cfg(target_os = "macos")]
+            {
+                let path = default_file_store_path()?;
+                let file_store = FileStore::new(path).await?;
+                let keychain = KeychainStore::new("com.acp.credentials")?;
+                return Ok(Box::new(CompositeStore::new(Box::new(keychain), Box::new(file_store))));
+            }
 
-            store.delete("test:perm").await.ok();
+            #[cfg(target_os = "linux")]
+            {
+                let path = default_file_store_path()?;
+                let file_store = FileStore::new(path).await?;
+                match SecretServiceStore::new("com.acp.credentials").await {
+                    Ok(store) => {
+                        return Ok(Box::new(CompositeStore::new(Box::new(store), Box::new(file_store))));
+                    }
+                    Err(_) => {
+                        // No Secret Service daemon reachable (headless box,
+                        // container without D-Bus) - fall back to FileStore
+                        // for everything.
+                        return Ok(Box::new(file_store));
+                    }
+                }
+            }
+
+            #[cfg(target_os = "windows")]
+            {
+                let path = default_file_store_path()?;
+                let file_store = FileStore::new(path).await?;
+                let store = WinCredStore::new("acp")?;
+                return Ok(Box::new(CompositeStore::new(Box::new(store), Box::new(file_store))));
+            }
+
+            #[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+            {
+                let path = default_file_store_path()?;
+                let store = FileStore::new(path).await?;
+                Ok(Box::new(store))
+            }
         }
     }
+}
 
-    #[cfg(target_os = "macos")]
-    #[tokio::test]
-    async fn test_keychain_store() {
-        // Use a unique service name for testing
-        let service_name = format!("com.acp.test.{}", std::process::id());
-        let store = KeychainStore::new(&service_name).expect("create KeychainStore");
+/// Default `~/.acp/secrets` (or `%USERPROFILE%\.acp\secrets`) location used
+/// for the `FileStore` half of the platform default, and as the sole
+/// backend on platforms with no OS secret service integration.
+fn default_file_store_path() -> Result<PathBuf> {
+    let home = std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .map_err(|_| crate::AcpError::storage("Cannot determine home directory"))?;
+    Ok(PathBuf::from(home).join(".acp").join("secrets"))
+}
 
-        // Test basic operations (not list, since KeychainStore.list() returns empty)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test helper to verify SecretStore implementation
+    async fn test_store_implementation<S: SecretStore>(store: S) {
+        // Test set and get
         store
             .set("test:key1", b"value1")
             .await
@@ -467,7 +1561,375 @@ mod tests {
             .await
             .expect("second delete should succeed");
 
+        // Test list
+        store
+            .set("test:list:a", b"a")
+            .await
+            .expect("set should succeed");
+        store
+            .set("test:list:b", b"b")
+            .await
+            .expect("set should succeed");
+        let listed = store
+            .list("test:list:")
+            .await
+            .expect("list should succeed");
+        assert_eq!(listed, vec!["test:list:a".to_string(), "test:list:b".to_string()]);
+
+        store.delete("test:list:a").await.ok();
+        let listed = store
+            .list("test:list:")
+            .await
+            .expect("list should succeed");
+        assert_eq!(listed, vec!["test:list:b".to_string()]);
+
         // Cleanup
-        let _ = store.delete("test:binary").await;
+        store.delete("test:binary").await.ok();
+        store.delete("test:list:b").await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_file_store() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+
+        test_store_implementation(store).await;
+    }
+
+    #[tokio::test]
+    async fn test_file_store_permissions() {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let temp_dir = tempfile::tempdir().expect("create temp dir");
+            let store = FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore");
+
+            // Check directory permissions
+            let metadata = std::fs::metadata(temp_dir.path()).expect("get metadata");
+            let mode = metadata.permissions().mode();
+            assert_eq!(mode & 0o777, 0o700, "directory should have mode 0700");
+
+            // Write a file and check permissions
+            store
+                .set("test:perm", b"value")
+                .await
+                .expect("set should succeed");
+
+            let file_path = store.key_to_filename("test:perm");
+            let file_metadata = std::fs::metadata(&file_path).expect("get file metadata");
+            let file_mode = file_metadata.permissions().mode();
+            assert_eq!(
+                file_mode & 0o777,
+                0o600,
+                "file should have mode 0600"
+            );
+
+            store.delete("test:perm").await.ok();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_store_roundtrip() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let encrypted = EncryptingStore::new(store, &[7u8; 32]);
+
+        encrypted
+            .set("token:abc", b"super-secret-value")
+            .await
+            .expect("set should succeed");
+
+        let value = encrypted
+            .get("token:abc")
+            .await
+            .expect("get should succeed")
+            .expect("value should exist");
+        assert_eq!(value, b"super-secret-value");
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_store_ciphertext_not_plaintext_on_disk() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let encrypted = EncryptingStore::new(store, &[7u8; 32]);
+
+        encrypted
+            .set("token:abc", b"super-secret-value")
+            .await
+            .expect("set should succeed");
+
+        // The inner FileStore should never see the plaintext on disk.
+        let raw = tokio::fs::read_dir(temp_dir.path())
+            .await
+            .expect("read dir");
+        let mut raw = raw;
+        let entry = raw.next_entry().await.expect("next entry").expect("one file");
+        let bytes = tokio::fs::read(entry.path()).await.expect("read raw file");
+        assert!(!bytes
+            .windows(b"super-secret-value".len())
+            .any(|w| w == b"super-secret-value"));
+    }
+
+    #[tokio::test]
+    async fn test_encrypting_store_wrong_key_fails() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let encrypted = EncryptingStore::new(store, &[1u8; 32]);
+        encrypted.set("token:abc", b"value").await.expect("set should succeed");
+
+        // Re-open the same files under a different key - decryption must fail loudly
+        // rather than silently returning garbage.
+        let store2 = FileStore::new(temp_dir.path().to_path_buf())
+            .await
+            .expect("reopen FileStore");
+        let wrong_key = EncryptingStore::new(store2, &[2u8; 32]);
+        let result = wrong_key.get("token:abc").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_composite_store_routes_by_prefix() {
+        let credentials_dir = tempfile::tempdir().expect("create temp dir");
+        let credentials = FileStore::new(credentials_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let credential_path = credentials.key_to_filename("credential:exa:api_key");
+
+        let other_dir = tempfile::tempdir().expect("create temp dir");
+        let other = FileStore::new(other_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let token_path = other.key_to_filename("token:abc");
+
+        let composite = CompositeStore::new(Box::new(credentials), Box::new(other));
+
+        composite
+            .set("credential:exa:api_key", b"secret")
+            .await
+            .expect("set should succeed");
+        composite
+            .set("token:abc", b"acp_abc123")
+            .await
+            .expect("set should succeed");
+
+        assert!(credential_path.exists(), "credential should land in the credentials backend");
+        assert!(token_path.exists(), "token should land in the other backend");
+
+        let value = composite
+            .get("credential:exa:api_key")
+            .await
+            .expect("get should succeed")
+            .expect("value should exist");
+        assert_eq!(value, b"secret");
+    }
+
+    #[tokio::test]
+    async fn test_composite_store_list_is_scoped_per_backend() {
+        let credentials_dir = tempfile::tempdir().expect("create temp dir");
+        let credentials = FileStore::new(credentials_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let other_dir = tempfile::tempdir().expect("create temp dir");
+        let other = FileStore::new(other_dir.path().to_path_buf())
+            .await
+            .expect("create FileStore");
+        let composite = CompositeStore::new(Box::new(credentials), Box::new(other));
+
+        composite.set("credential:exa:api_key", b"secret").await.expect("set");
+        composite.set("token:abc", b"acp_abc123").await.expect("set");
+
+        let credentials = composite.list("credential:").await.expect("list should succeed");
+        assert_eq!(credentials, vec!["credential:exa:api_key".to_string()]);
+
+        let tokens = composite.list("token:").await.expect("list should succeed");
+        assert_eq!(tokens, vec!["token:abc".to_string()]);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[tokio::test]
+    async fn test_keychain_store() {
+        // Use a unique service name for testing
+        let service_name = format!("com.acp.test.{}", std::process::id());
+        let store = KeychainStore::new(&service_name).expect("create KeychainStore");
+
+        test_store_implementation(store).await;
+    }
+
+    #[cfg(target_os = "macos")]
+    #[tokio::test]
+    async fn test_keychain_store_list_survives_index_key() {
+        // The index itself is stored under a reserved key; listing with an
+        // empty prefix must not return it.
+        let service_name = format!("com.acp.test.{}", std::process::id());
+        let store = KeychainStore::new(&service_name).expect("create KeychainStore");
+
+        store.set("credential:exa:key", b"v").await.expect("set should succeed");
+        let listed = store.list("").await.expect("list should succeed");
+        assert!(!listed.iter().any(|k| k == INDEX_KEY));
+
+        store.delete("credential:exa:key").await.ok();
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_store_roundtrip() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        // log_n=4 keeps the test fast; production callers should use the default.
+        let store = EncryptedFileStore::with_log_n(temp_dir.path().to_path_buf(), "correct-horse", 4)
+            .await
+            .expect("create EncryptedFileStore");
+
+        test_store_implementation(store).await;
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_store_ciphertext_not_plaintext_on_disk() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = EncryptedFileStore::with_log_n(temp_dir.path().to_path_buf(), "correct-horse", 4)
+            .await
+            .expect("create EncryptedFileStore");
+
+        store
+            .set("token:abc", b"super-secret-value")
+            .await
+            .expect("set should succeed");
+
+        let mut entries = tokio::fs::read_dir(temp_dir.path()).await.expect("read dir");
+        let entry = entries.next_entry().await.expect("next entry").expect("one file");
+        let bytes = tokio::fs::read(entry.path()).await.expect("read raw file");
+        assert!(!bytes
+            .windows(b"super-secret-value".len())
+            .any(|w| w == b"super-secret-value"));
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_file_store_wrong_passphrase_fails() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = EncryptedFileStore::with_log_n(temp_dir.path().to_path_buf(), "correct-horse", 4)
+            .await
+            .expect("create EncryptedFileStore");
+        store.set("token:abc", b"value").await.expect("set should succeed");
+
+        let wrong = EncryptedFileStore::with_log_n(temp_dir.path().to_path_buf(), "wrong-passphrase", 4)
+            .await
+            .expect("create EncryptedFileStore");
+        let result = wrong.get("token:abc").await;
+        assert!(result.is_err());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_secret_service_store() {
+        // Only runs where a Secret Service daemon is actually reachable
+        // (e.g. a desktop session with GNOME Keyring) - CI/headless
+        // environments without D-Bus are expected to skip it.
+        let service_name = format!("com.acp.test.{}", std::process::id());
+        let store = match SecretServiceStore::new(&service_name).await {
+            Ok(store) => store,
+            Err(_) => return,
+        };
+
+        test_store_implementation(store).await;
+    }
+
+    #[cfg(target_os = "windows")]
+    #[tokio::test]
+    async fn test_wincred_store() {
+        let prefix = format!("acp-test-{}", std::process::id());
+        let store = WinCredStore::new(&prefix).expect("create WinCredStore");
+
+        test_store_implementation(store).await;
+    }
+
+    #[cfg(target_os = "windows")]
+    #[tokio::test]
+    async fn test_wincred_store_rejects_oversized_blob() {
+        let prefix = format!("acp-test-{}", std::process::id());
+        let store = WinCredStore::new(&prefix).expect("create WinCredStore");
+
+        let oversized = vec![0u8; MAX_CREDENTIAL_BLOB_SIZE + 1];
+        let result = store.set("test:oversized", &oversized).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_process_store_roundtrip() {
+        // A tiny python3 helper backed by a JSON file, implementing the
+        // get/set/delete protocol ProcessStore speaks.
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let backing_file = temp_dir.path().join("store.json");
+
+        let script = format!(
+            r#"python3 -c "import sys, json, os
+path = '{path}'
+data = json.load(open(path)) if os.path.exists(path) else {{}}
+req = json.loads(sys.stdin.readline())
+op, key = req['op'], req['key']
+if op == 'get':
+    print(json.dumps({{'value': data[key]}} if key in data else {{'found': False}}))
+elif op == 'set':
+    data[key] = req['value']
+    json.dump(data, open(path, 'w'))
+    print(json.dumps({{'ok': True}}))
+elif op == 'delete':
+    data.pop(key, None)
+    json.dump(data, open(path, 'w'))
+    print(json.dumps({{'ok': True}}))
+"
+"#,
+            path = backing_file.display()
+        );
+
+        let store = ProcessStore::new(script);
+
+        store.set("test:key1", b"value1").await.expect("set should succeed");
+
+        let value = store
+            .get("test:key1")
+            .await
+            .expect("get should succeed")
+            .expect("value should exist");
+        assert_eq!(value, b"value1");
+
+        let missing = store.get("test:missing").await.expect("get should succeed");
+        assert!(missing.is_none());
+
+        store.delete("test:key1").await.expect("delete should succeed");
+        let deleted = store.get("test:key1").await.expect("get should succeed");
+        assert!(deleted.is_none());
+    }
+
+    #[test]
+    fn test_parse_s3_url_with_prefix() {
+        let (bucket, prefix) = parse_s3_url("s3://my-bucket/acp/secrets").expect("should parse");
+        assert_eq!(bucket, "my-bucket");
+        assert_eq!(prefix, "acp/secrets/");
+    }
+
+    #[test]
+    fn test_parse_s3_url_without_prefix() {
+        let (bucket, prefix) = parse_s3_url("s3://my-bucket").expect("should parse");
+        assert_eq!(bucket, "my-bucket");
+        assert_eq!(prefix, "");
+    }
+
+    #[test]
+    fn test_parse_s3_url_rejects_non_s3_scheme() {
+        assert!(parse_s3_url("https://example.com/bucket").is_err());
+    }
+
+    #[test]
+    fn test_parse_s3_url_rejects_missing_bucket() {
+        assert!(parse_s3_url("s3://").is_err());
     }
 }
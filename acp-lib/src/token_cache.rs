@@ -11,7 +11,14 @@ use crate::storage::SecretStore;
 use crate::types::AgentToken;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+
+/// How often the fallback poller checks the store's revision counter for
+/// out-of-band writes, when the backend doesn't support filesystem
+/// notifications.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
 
 /// Token cache with invalidate-on-write pattern
 ///
@@ -155,6 +162,41 @@ impl TokenCache {
         *self.cache.write().await = None;
     }
 
+    /// Spawn a background task that watches the underlying store for
+    /// out-of-band changes and invalidates the cache automatically.
+    ///
+    /// `invalidate()` only catches writes made through this same `TokenCache`
+    /// instance - an operator editing tokens via the CLI in a separate
+    /// process leaves a running proxy's cache stale until something notices.
+    /// This polls [`SecretStore::revision`] (a cheap generation counter) and
+    /// invalidates whenever it changes, so a hot-reload happens within one
+    /// poll interval instead of requiring a manual `invalidate()` call.
+    ///
+    /// On filesystem-backed stores, a real implementation would prefer
+    /// `notify` watching the data directory directly; the revision-counter
+    /// poll is the portable fallback that also works for remote backends.
+    pub fn watch(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut last_revision = self.store.revision().await.ok().flatten();
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+
+                let current = match self.store.revision().await {
+                    Ok(rev) => rev,
+                    Err(e) => {
+                        tracing::warn!("Failed to poll store revision: {}", e);
+                        continue;
+                    }
+                };
+
+                if current != last_revision {
+                    self.invalidate().await;
+                    last_revision = current;
+                }
+            }
+        })
+    }
+
     /// Load all tokens from Registry into cache
     ///
     /// Uses Registry to get token metadata, then loads token values from storage.
@@ -192,6 +234,60 @@ mod tests {
     use crate::registry::Registry;
     use crate::storage::FileStore;
 
+    /// Minimal SecretStore wrapper that exposes a controllable revision, to
+    /// test `watch()` without depending on real filesystem notifications.
+    struct RevisionedStore {
+        inner: FileStore,
+        revision: std::sync::atomic::AtomicU64,
+    }
+
+    #[async_trait::async_trait]
+    impl SecretStore for RevisionedStore {
+        async fn set(&self, key: &str, value: &[u8]) -> Result<()> {
+            self.inner.set(key, value).await
+        }
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            self.inner.get(key).await
+        }
+        async fn delete(&self, key: &str) -> Result<()> {
+            self.inner.delete(key).await
+        }
+        async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+            self.inner.list(prefix).await
+        }
+        async fn revision(&self) -> Result<Option<u64>> {
+            Ok(Some(self.revision.load(std::sync::atomic::Ordering::SeqCst)))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_invalidates_on_revision_change() {
+        let temp_dir = tempfile::tempdir().expect("create temp dir");
+        let store = Arc::new(RevisionedStore {
+            inner: FileStore::new(temp_dir.path().to_path_buf())
+                .await
+                .expect("create FileStore"),
+            revision: std::sync::atomic::AtomicU64::new(0),
+        });
+        let registry = Arc::new(Registry::new(Arc::clone(&store) as Arc<dyn SecretStore>));
+        let cache = Arc::new(TokenCache::new(Arc::clone(&store) as Arc<dyn SecretStore>, registry));
+
+        // Prime the cache.
+        cache.list().await.expect("list tokens");
+
+        let handle = Arc::clone(&cache).watch();
+
+        // Simulate an out-of-band write elsewhere bumping the revision.
+        store.revision.store(1, std::sync::atomic::Ordering::SeqCst);
+
+        // Give the poller a couple of intervals to notice.
+        tokio::time::sleep(POLL_INTERVAL * 3).await;
+
+        assert!(cache.cache.read().await.is_none(), "cache should have been invalidated");
+
+        handle.abort();
+    }
+
     #[tokio::test]
     async fn test_create_and_get_token() {
         let _temp_dir = tempfile::tempdir().expect("create temp dir");
@@ -0,0 +1,170 @@
+//! Host+path pattern compilation for `PluginEntry.hosts`
+//!
+//! A `hosts` entry like `api.exa.ai/search/:id` or `*.s3.amazonaws.com/*`
+//! names a host and, optionally, a path template in one string. Each entry
+//! is tokenized once into literal and parameter tokens and compiled into an
+//! anchored regex: `:name` becomes `(?P<name>[^/]+)` and `*` becomes `(.*)`.
+//! Matching joins a request URL's host and path into the same shape so
+//! scheme differences never affect the result.
+
+use crate::error::{AcpError, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use url::Url;
+
+/// A compiled `host/path` pattern, e.g. `api.exa.ai/search/:id`.
+pub struct UrlPattern {
+    regex: Regex,
+}
+
+impl UrlPattern {
+    /// Tokenize `pattern` into literal, `:name`, and `*` tokens and compile
+    /// it into an anchored regex.
+    pub fn compile(pattern: &str) -> Result<Self> {
+        let mut regex_src = String::from("^");
+        let chars: Vec<char> = pattern.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            match chars[i] {
+                ':' => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && (chars[end].is_alphanumeric() || chars[end] == '_') {
+                        end += 1;
+                    }
+                    if end == start {
+                        return Err(AcpError::plugin(format!(
+                            "invalid match pattern '{}': ':' must be followed by a parameter name",
+                            pattern
+                        )));
+                    }
+                    let name: String = chars[start..end].iter().collect();
+                    regex_src.push_str(&format!("(?P<{}>[^/]+)", name));
+                    i = end;
+                }
+                '*' => {
+                    regex_src.push_str("(.*)");
+                    i += 1;
+                }
+                c => {
+                    regex_src.push_str(&regex::escape(&c.to_string()));
+                    i += 1;
+                }
+            }
+        }
+        regex_src.push_str("/?$");
+
+        let regex = Regex::new(&regex_src)
+            .map_err(|e| AcpError::plugin(format!("invalid match pattern '{}': {}", pattern, e)))?;
+
+        Ok(Self { regex })
+    }
+
+    /// Match `target` (a normalized `host/path` string), returning the
+    /// captured named parameters on success.
+    pub fn matches(&self, target: &str) -> Option<HashMap<String, String>> {
+        let captures = self.regex.captures(target)?;
+        let params = self
+            .regex
+            .capture_names()
+            .flatten()
+            .filter_map(|name| captures.name(name).map(|m| (name.to_string(), m.as_str().to_string())))
+            .collect();
+        Some(params)
+    }
+}
+
+/// Process-lifetime cache of compiled `UrlPattern`s, keyed by their source
+/// pattern string, so a plugin's patterns are only tokenized and compiled
+/// to a regex once no matter how many times it's matched against.
+pub struct PatternCache {
+    entries: RwLock<HashMap<String, Arc<UrlPattern>>>,
+}
+
+impl Default for PatternCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PatternCache {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Return the compiled pattern for `pattern`, compiling and caching it
+    /// on a miss.
+    pub async fn get_or_compile(&self, pattern: &str) -> Result<Arc<UrlPattern>> {
+        if let Some(compiled) = self.entries.read().await.get(pattern) {
+            return Ok(compiled.clone());
+        }
+
+        let compiled = Arc::new(UrlPattern::compile(pattern)?);
+        self.entries
+            .write()
+            .await
+            .insert(pattern.to_string(), compiled.clone());
+        Ok(compiled)
+    }
+}
+
+/// Join a request URL's host and path into the `host/path` shape patterns
+/// are compiled against, ignoring scheme and normalizing away a trailing
+/// slash so `api.exa.ai/search/` and `api.exa.ai/search` match the same
+/// pattern.
+pub fn normalize(url: &Url) -> String {
+    let host = url.host_str().unwrap_or("");
+    let path = url.path().trim_end_matches('/');
+    format!("{}{}", host, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_and_match_named_param() {
+        let pattern = UrlPattern::compile("api.exa.ai/search/:id").unwrap();
+        let params = pattern.matches("api.exa.ai/search/42").unwrap();
+        assert_eq!(params.get("id"), Some(&"42".to_string()));
+    }
+
+    #[test]
+    fn test_compile_and_match_wildcard_host_and_path() {
+        let pattern = UrlPattern::compile("*.s3.amazonaws.com/*").unwrap();
+        assert!(pattern.matches("my-bucket.s3.amazonaws.com/objects/a.txt").is_some());
+        assert!(pattern.matches("api.exa.ai/search/42").is_none());
+    }
+
+    #[test]
+    fn test_match_fails_on_non_matching_host() {
+        let pattern = UrlPattern::compile("api.exa.ai/search/:id").unwrap();
+        assert!(pattern.matches("api.other.com/search/42").is_none());
+    }
+
+    #[test]
+    fn test_compile_rejects_bare_colon() {
+        assert!(UrlPattern::compile("api.exa.ai/:").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pattern_cache_reuses_compiled_regex() {
+        let cache = PatternCache::new();
+        let first = cache.get_or_compile("api.exa.ai/search/:id").await.unwrap();
+        let second = cache.get_or_compile("api.exa.ai/search/:id").await.unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+    }
+
+    #[test]
+    fn test_normalize_strips_scheme_and_trailing_slash() {
+        let url = Url::parse("https://api.exa.ai/search/").unwrap();
+        assert_eq!(normalize(&url), "api.exa.ai/search");
+
+        let url = Url::parse("http://api.exa.ai/search").unwrap();
+        assert_eq!(normalize(&url), "api.exa.ai/search");
+    }
+}
@@ -6,23 +6,33 @@
 //! - Credential management
 //! - Token management
 //! - Activity monitoring
+//!
+//! The full contract is also published as an OpenAPI document at
+//! `GET /openapi.json`, with an interactive Swagger UI at `/docs`.
 
+use acp_lib::storage::SecretStore;
 use acp_lib::AgentToken;
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum::{
     async_trait,
     body::Bytes,
-    extract::{FromRequestParts, Path, State},
-    http::{request::Parts, StatusCode},
+    extract::{FromRequest, FromRequestParts, Path, Query, Request, State},
+    http::{header::AUTHORIZATION, request::Parts, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
     response::{IntoResponse, Response},
     routing::{delete, get, post},
     Json, Router,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use utoipa::{OpenApi, ToSchema};
+use utoipa_swagger_ui::SwaggerUi;
 
 /// API server state
 #[derive(Clone)]
@@ -35,31 +45,92 @@ pub struct ApiState {
     pub api_port: u16,
     /// Password hash (Argon2)
     pub password_hash: Arc<RwLock<Option<String>>>,
-    /// Stored agent tokens
-    pub tokens: Arc<RwLock<HashMap<String, AgentToken>>>,
-    /// Recent activity log
+    /// HS256 signing secret for session tokens, generated on `/init`
+    pub jwt_secret: Arc<RwLock<Option<Vec<u8>>>>,
+    /// Durable backend for tokens, plugins, and credentials - see
+    /// `acp_lib::storage::SecretStore`. Everything under this goes through
+    /// namespaced keys (`token:{id}`, `credential:{plugin}:{key}`, ...) so it
+    /// survives a server restart.
+    pub store: Arc<dyn SecretStore>,
+    /// Recent activity log (bounded, see [`ACTIVITY_LOG_CAPACITY`])
     pub activity: Arc<RwLock<Vec<ActivityEntry>>>,
+    /// Broadcasts every newly recorded `ActivityEntry`, for `GET
+    /// /activity/stream`. Lagging subscribers simply miss entries rather than
+    /// blocking the writer - see [`record_activity`].
+    pub activity_tx: broadcast::Sender<ActivityEntry>,
+    /// Pending ACME http-01 key authorizations, keyed by challenge token
+    pub acme_challenges: Arc<RwLock<HashMap<String, String>>>,
 }
 
+/// How many recent activity entries `ApiState::activity` retains
+const ACTIVITY_LOG_CAPACITY: usize = 1000;
+
+/// Capacity of the `ApiState::activity_tx` broadcast channel
+const ACTIVITY_CHANNEL_CAPACITY: usize = 256;
+
 impl ApiState {
-    pub fn new(proxy_port: u16, api_port: u16) -> Self {
-        Self {
+    pub async fn new(proxy_port: u16, api_port: u16) -> acp_lib::Result<Self> {
+        use acp_lib::registry::Registry;
+        use acp_lib::storage::create_store;
+
+        let (activity_tx, _) = broadcast::channel(ACTIVITY_CHANNEL_CAPACITY);
+        let store: Arc<dyn SecretStore> = Arc::from(create_store(None).await?);
+
+        // `create_store` may have picked any backend (OS keyring, file,
+        // external helper, ...) depending on platform and environment. Run
+        // the old-token-format migration against whichever one it is so a
+        // keyring holding pre-token-value-keyed entries still gets fixed up.
+        if let Err(e) = Registry::new(store.clone()).migrate_old_token_format().await {
+            tracing::warn!("failed to migrate old-format tokens: {}", e);
+        }
+
+        // Bring a registry written by an older binary up to `SCHEMA_VERSION`
+        // so `schema_version` doesn't stay stale on real installs.
+        if let Err(e) = Registry::new(store.clone()).run_migrations(store.as_ref()).await {
+            tracing::warn!("failed to run registry schema migrations: {}", e);
+        }
+
+        Ok(Self {
             start_time: std::time::Instant::now(),
             proxy_port,
             api_port,
             password_hash: Arc::new(RwLock::new(None)),
-            tokens: Arc::new(RwLock::new(HashMap::new())),
+            jwt_secret: Arc::new(RwLock::new(None)),
+            store,
             activity: Arc::new(RwLock::new(Vec::new())),
+            activity_tx,
+            acme_challenges: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    /// Append `entry` to the bounded in-memory activity log and publish it to
+    /// every `GET /activity/stream` subscriber. Intended to be called by the
+    /// proxy every time it logs a request.
+    pub async fn record_activity(&self, entry: ActivityEntry) {
+        let mut activity = self.activity.write().await;
+        activity.push(entry.clone());
+        if activity.len() > ACTIVITY_LOG_CAPACITY {
+            let overflow = activity.len() - ACTIVITY_LOG_CAPACITY;
+            activity.drain(0..overflow);
         }
+        drop(activity);
+
+        // No subscribers is the common case outside of an active dashboard;
+        // that's not an error.
+        let _ = self.activity_tx.send(entry);
     }
 
     pub async fn set_password_hash(&self, hash: String) {
         *self.password_hash.write().await = Some(hash);
     }
+
+    pub async fn set_jwt_secret(&self, secret: Vec<u8>) {
+        *self.jwt_secret.write().await = Some(secret);
+    }
 }
 
 /// Activity log entry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ActivityEntry {
     pub timestamp: DateTime<Utc>,
     pub method: String,
@@ -69,7 +140,7 @@ pub struct ActivityEntry {
 }
 
 /// Status response
-#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, ToSchema)]
 pub struct StatusResponse {
     pub version: String,
     pub uptime_seconds: u64,
@@ -77,8 +148,10 @@ pub struct StatusResponse {
     pub api_port: u16,
 }
 
-/// Request body containing password_hash for authentication
-#[derive(Debug, Deserialize, Clone)]
+/// Request body containing password_hash for authentication. Only `/init`
+/// still authenticates this way, since there's no session token to send
+/// before the server has a password to check one against.
+#[derive(Debug, Deserialize, Clone, ToSchema)]
 pub struct AuthenticatedRequest<T> {
     /// SHA512 hash of password (hex encoded)
     pub password_hash: String,
@@ -86,91 +159,336 @@ pub struct AuthenticatedRequest<T> {
     pub data: T,
 }
 
-/// Extractor that validates authentication
+/// Claims of a `/login`-issued session token.
+#[derive(Debug, Serialize, Deserialize)]
+struct SessionClaims {
+    sub: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// How long a session token minted by `/login` stays valid.
+const SESSION_TOKEN_TTL_SECS: i64 = 15 * 60;
+
+/// Pulls the `Authorization: Bearer <token>` session token out of `parts`
+/// and validates its signature and expiry against `state.jwt_secret`.
+async fn validate_bearer_token(parts: &Parts, state: &ApiState) -> Result<SessionClaims, ApiError> {
+    let header = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(ApiError::MissingCredentials)?;
+
+    let token = header.strip_prefix("Bearer ").ok_or(ApiError::MissingCredentials)?;
+
+    let secret = state.jwt_secret.read().await.clone().ok_or(ApiError::NotInitialized)?;
+
+    let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::HS256);
+    jsonwebtoken::decode::<SessionClaims>(
+        token,
+        &jsonwebtoken::DecodingKey::from_secret(&secret),
+        &validation,
+    )
+    .map(|data| data.claims)
+    .map_err(|_| ApiError::Unauthorized)
+}
+
+/// Extractor that requires a valid session token but doesn't need the
+/// request body, for handlers with no JSON payload of their own.
+pub struct AuthOnly;
+
+#[async_trait]
+impl FromRequestParts<ApiState> for AuthOnly {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        validate_bearer_token(parts, state).await?;
+        Ok(AuthOnly)
+    }
+}
+
+/// Extractor that requires a valid session token, then deserializes the
+/// JSON request body into `T`.
 pub struct Authenticated<T>(pub T);
 
 #[async_trait]
-impl<T> FromRequestParts<ApiState> for Authenticated<T>
+impl<T> FromRequest<ApiState> for Authenticated<T>
 where
     T: for<'de> Deserialize<'de> + Send,
 {
-    type Rejection = (StatusCode, String);
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.into_parts();
+        validate_bearer_token(&parts, state).await?;
+
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| ApiError::BadJson(format!("Failed to read body: {}", e)))?;
+        let data: T = serde_json::from_slice(&bytes)
+            .map_err(|e| ApiError::BadJson(format!("Invalid JSON: {}", e)))?;
 
-    async fn from_request_parts(
-        _parts: &mut Parts,
-        _state: &ApiState,
-    ) -> Result<Self, Self::Rejection> {
-        // For now, this is a placeholder - actual auth will be done in handlers
-        // that have access to the request body
-        Err((
-            StatusCode::UNAUTHORIZED,
-            "Use request body for authentication".to_string(),
-        ))
+        Ok(Authenticated(data))
     }
 }
 
-/// Helper function to verify authentication from request body
-async fn verify_auth<T>(
-    state: &ApiState,
-    body: &[u8],
-) -> Result<T, (StatusCode, String)>
+/// Names one of [`KNOWN_SCOPES`] at the type level, so a handler declares
+/// the capability it needs via `ScopedAuth<S>`/`ScopedAuthenticated<S, T>`'s
+/// type parameter rather than a runtime string.
+trait ScopeMarker {
+    const SCOPE: &'static str;
+}
+
+macro_rules! scope_marker {
+    ($name:ident, $scope:literal) => {
+        pub struct $name;
+        impl ScopeMarker for $name {
+            const SCOPE: &'static str = $scope;
+        }
+    };
+}
+
+scope_marker!(PluginsRead, "plugins:read");
+scope_marker!(PluginsWrite, "plugins:write");
+scope_marker!(CredentialsWrite, "credentials:write");
+scope_marker!(ActivityRead, "activity:read");
+
+/// Authorizes a request as either an operator (a valid session token - full
+/// access, no scope check) or an agent token carrying `scope` - see
+/// `require_scope`. Shared by `ScopedAuth`/`ScopedAuthenticated`.
+async fn authorize(parts: &Parts, state: &ApiState, scope: &str) -> Result<(), ApiError> {
+    if validate_bearer_token(parts, state).await.is_ok() {
+        return Ok(());
+    }
+
+    let token = agent_token_from_header(parts, state).await?;
+    require_scope(&token, scope)
+}
+
+/// Resolves the `Authorization: Bearer` value on `parts` to the agent token
+/// whose stored hash it matches, for `authorize`'s non-operator path. Does
+/// not itself check scope or expiry - that's `require_scope`'s job.
+async fn agent_token_from_header(parts: &Parts, state: &ApiState) -> Result<AgentToken, ApiError> {
+    let header = parts
+        .headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(ApiError::MissingCredentials)?;
+    let presented = header.strip_prefix("Bearer ").ok_or(ApiError::MissingCredentials)?;
+    let presented_hash = hex::encode(Sha256::digest(presented.as_bytes()));
+
+    let stored = load_stored_tokens(&state.store)
+        .await?
+        .into_iter()
+        .find(|t| t.token_hash == presented_hash)
+        .ok_or(ApiError::Unauthorized)?;
+
+    let mut token = AgentToken::new(&stored.name);
+    token.id = stored.id;
+    token.prefix = stored.prefix;
+    token.created_at = stored.created_at;
+    token.scopes = stored.scopes;
+    token.expires_at = stored.expires_at;
+    Ok(token)
+}
+
+/// Like [`AuthOnly`], but also admits an agent bearer token carrying
+/// `S::SCOPE` - for plugin/credential/activity handlers that capability-
+/// scoped tokens (see [`require_scope`]) should be able to reach without an
+/// operator session.
+pub struct ScopedAuth<S>(std::marker::PhantomData<S>);
+
+#[async_trait]
+impl<S> FromRequestParts<ApiState> for ScopedAuth<S>
 where
-    T: for<'de> Deserialize<'de>,
+    S: ScopeMarker + Send + Sync,
 {
-    // Parse as authenticated request
-    let auth_req: AuthenticatedRequest<T> =
-        serde_json::from_slice(body).map_err(|e| {
-            (
-                StatusCode::BAD_REQUEST,
-                format!("Invalid JSON: {}", e),
-            )
-        })?;
+    type Rejection = ApiError;
 
-    // Verify password hash
-    let stored_hash = state.password_hash.read().await;
-    if let Some(ref hash_str) = *stored_hash {
-        // Hash the provided SHA512 hash with Argon2 (stored hash is Argon2 of SHA512)
-        let parsed_hash = PasswordHash::new(hash_str).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Invalid stored hash: {}", e),
-            )
-        })?;
+    async fn from_request_parts(parts: &mut Parts, state: &ApiState) -> Result<Self, Self::Rejection> {
+        authorize(parts, state, S::SCOPE).await?;
+        Ok(ScopedAuth(std::marker::PhantomData))
+    }
+}
 
-        // The client sends SHA512(password), we verify Argon2(SHA512(password))
-        Argon2::default()
-            .verify_password(auth_req.password_hash.as_bytes(), &parsed_hash)
-            .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid credentials".to_string()))?;
+/// Like [`Authenticated`], but also admits an agent bearer token carrying
+/// `S::SCOPE` - see [`ScopedAuth`].
+pub struct ScopedAuthenticated<S, T>(pub T, std::marker::PhantomData<S>);
 
-        Ok(auth_req.data)
-    } else {
-        Err((
-            StatusCode::UNAUTHORIZED,
-            "Server not initialized".to_string(),
-        ))
+#[async_trait]
+impl<S, T> FromRequest<ApiState> for ScopedAuthenticated<S, T>
+where
+    S: ScopeMarker + Send + Sync,
+    T: for<'de> Deserialize<'de> + Send,
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &ApiState) -> Result<Self, Self::Rejection> {
+        let (parts, body) = req.into_parts();
+        authorize(&parts, state, S::SCOPE).await?;
+
+        let bytes = axum::body::to_bytes(body, usize::MAX)
+            .await
+            .map_err(|e| ApiError::BadJson(format!("Failed to read body: {}", e)))?;
+        let data: T = serde_json::from_slice(&bytes)
+            .map_err(|e| ApiError::BadJson(format!("Invalid JSON: {}", e)))?;
+
+        Ok(ScopedAuthenticated(data, std::marker::PhantomData))
+    }
+}
+
+/// The Argon2id cost parameters every stored password hash should meet.
+/// `init` uses these unless the caller supplies stronger overrides; any
+/// stored hash that verifies successfully but falls short (e.g. it was
+/// created under an older, weaker policy) is transparently re-hashed under
+/// this policy in `verify_auth`.
+fn current_argon2_policy() -> argon2::Params {
+    argon2::Params::new(
+        argon2::Params::DEFAULT_M_COST,
+        argon2::Params::DEFAULT_T_COST,
+        argon2::Params::DEFAULT_P_COST,
+        None,
+    )
+    .expect("default argon2 params are valid")
+}
+
+/// An Argon2id instance configured with `params`, producing/verifying the
+/// standard PHC string (`$argon2id$v=19$m=...,t=...,p=...$salt$hash`).
+fn argon2_with_params(params: argon2::Params) -> Argon2<'static> {
+    Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+}
+
+/// Whether `hash`'s embedded cost parameters meet or exceed `policy`.
+fn meets_policy(hash: &PasswordHash, policy: &argon2::Params) -> bool {
+    match argon2::Params::try_from(hash) {
+        Ok(params) => {
+            params.m_cost() >= policy.m_cost()
+                && params.t_cost() >= policy.t_cost()
+                && params.p_cost() >= policy.p_cost()
+        }
+        Err(_) => false,
+    }
+}
+
+/// Verifies `password_hash` (the client-sent SHA512(password)) against the
+/// stored Argon2 hash, used by `/login`. Transparently re-hashes the stored
+/// value under the current policy if it falls short (e.g. it predates a
+/// cost-parameter bump).
+async fn verify_password(state: &ApiState, password_hash: &str) -> Result<(), ApiError> {
+    let stored_hash = state.password_hash.read().await.clone();
+    let Some(hash_str) = stored_hash else {
+        return Err(ApiError::NotInitialized);
+    };
+
+    // Hash the provided SHA512 hash with Argon2 (stored hash is Argon2 of SHA512)
+    let parsed_hash = PasswordHash::new(&hash_str)
+        .map_err(|e| ApiError::Internal(format!("Invalid stored hash: {}", e)))?;
+
+    // The client sends SHA512(password), we verify Argon2(SHA512(password)).
+    // `verify_password` checks against the cost parameters embedded in
+    // `parsed_hash` itself, not `Argon2::default()`'s, so this accepts
+    // hashes created under any past policy.
+    Argon2::default()
+        .verify_password(password_hash.as_bytes(), &parsed_hash)
+        .map_err(|_| ApiError::InvalidCredentials)?;
+
+    let policy = current_argon2_policy();
+    if !meets_policy(&parsed_hash, &policy) {
+        use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+        let salt = SaltString::generate(&mut OsRng);
+        if let Ok(upgraded) = argon2_with_params(policy).hash_password(password_hash.as_bytes(), &salt) {
+            state.set_password_hash(upgraded.to_string()).await;
+        }
     }
+
+    Ok(())
 }
 
 /// Plugin list response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PluginsResponse {
     pub plugins: Vec<String>,
 }
 
 /// Token creation request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTokenRequest {
     pub name: String,
+    /// "symmetric" (default) or "paseto" - see `TokenType`
+    #[serde(default)]
+    pub token_type: Option<String>,
+    /// Optional expiry, for `token_type: "paseto"`
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Optional plugin allowlist, for `token_type: "paseto"`
+    #[serde(default)]
+    pub allowed_plugins: Vec<String>,
+    /// Capability scopes, e.g. `"plugins:read"`, `"credentials:write"` - see
+    /// [`KNOWN_SCOPES`]. Only meaningful for symmetric tokens; rejected with
+    /// 400 if any entry isn't recognized.
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    /// Optional lifetime in seconds from creation, for symmetric tokens
+    #[serde(default)]
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Capability scopes a symmetric agent token may be granted. Checked against
+/// [`CreateTokenRequest::scopes`] at creation time and enforced later via
+/// [`require_scope`].
+pub const KNOWN_SCOPES: &[&str] = &[
+    "plugins:read",
+    "plugins:write",
+    "credentials:read",
+    "credentials:write",
+    "activity:read",
+];
+
+/// Reject a scope list containing anything outside [`KNOWN_SCOPES`]
+fn validate_scopes(scopes: &[String]) -> Result<(), ApiError> {
+    for scope in scopes {
+        if !KNOWN_SCOPES.contains(&scope.as_str()) {
+            return Err(ApiError::BadJson(format!("Unknown scope: {}", scope)));
+        }
+    }
+    Ok(())
+}
+
+/// Check that a token is unexpired and carries `scope`, for proxy/management
+/// code paths that authenticate a request using an [`AgentToken`] rather than
+/// an operator session - see `ScopedAuth`/`ScopedAuthenticated`. Returns 403
+/// rather than 401 since the bearer token itself is valid - it's simply not
+/// authorized for this action.
+pub fn require_scope(token: &AgentToken, scope: &str) -> Result<(), ApiError> {
+    if let Some(expires_at) = token.expires_at {
+        if expires_at <= Utc::now() {
+            return Err(ApiError::Forbidden);
+        }
+    }
+
+    if !token.scopes.iter().any(|s| s == scope) {
+        return Err(ApiError::Forbidden);
+    }
+
+    Ok(())
+}
+
+/// Token verification request, for `POST /tokens/verify`
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct VerifyTokenRequest {
+    pub token: String,
 }
 
 /// Token response (includes full token only on creation)
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TokenResponse {
     pub id: String,
     pub name: String,
     pub prefix: String,
     pub token: Option<String>,
     pub created_at: DateTime<Utc>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl From<AgentToken> for TokenResponse {
@@ -181,70 +499,280 @@ impl From<AgentToken> for TokenResponse {
             prefix: token.prefix.clone(),
             token: None, // Don't expose token by default
             created_at: token.created_at,
+            scopes: token.scopes.clone(),
+            expires_at: token.expires_at,
         }
     }
 }
 
 /// Tokens list response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TokensResponse {
     pub tokens: Vec<TokenResponse>,
 }
 
+/// Durable on-disk representation of an agent token, stored at `token:{id}`.
+///
+/// Holds a SHA-256 hash of the token secret rather than the plaintext, so a
+/// compromised store doesn't hand out usable bearer credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredToken {
+    id: String,
+    name: String,
+    prefix: String,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<&AgentToken> for StoredToken {
+    fn from(token: &AgentToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            prefix: token.prefix.clone(),
+            token_hash: hex::encode(Sha256::digest(token.token.as_bytes())),
+            created_at: token.created_at,
+            scopes: token.scopes.clone(),
+            expires_at: token.expires_at,
+        }
+    }
+}
+
+impl From<StoredToken> for TokenResponse {
+    fn from(token: StoredToken) -> Self {
+        Self {
+            id: token.id,
+            name: token.name,
+            prefix: token.prefix,
+            token: None, // the store only ever keeps a hash
+            created_at: token.created_at,
+            scopes: token.scopes,
+            expires_at: token.expires_at,
+        }
+    }
+}
+
 /// Credential set request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SetCredentialRequest {
     pub value: String,
 }
 
 /// Activity response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ActivityResponse {
     pub entries: Vec<ActivityEntry>,
 }
 
 /// Init request
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct InitRequest {
     pub ca_path: Option<String>,
+    /// Subject alternative names for the management/proxy certificate
+    pub management_sans: Option<Vec<String>>,
+    /// ACME directory URL; when set, obtain a publicly-trusted certificate
+    /// via ACME instead of generating a self-signed CA
+    pub acme_directory: Option<String>,
+    /// Contact email for the ACME account
+    pub acme_email: Option<String>,
+    /// Argon2id memory cost in KiB; defaults to [`current_argon2_policy`]'s
+    pub argon2_memory_kib: Option<u32>,
+    /// Argon2id iteration (time) cost; defaults to [`current_argon2_policy`]'s
+    pub argon2_iterations: Option<u32>,
+    /// Argon2id parallelism (lanes); defaults to [`current_argon2_policy`]'s
+    pub argon2_parallelism: Option<u32>,
 }
 
 /// Init response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct InitResponse {
     pub ca_path: String,
 }
 
-/// API error response
-#[derive(Debug, Serialize)]
-pub struct ApiError {
-    pub error: String,
+/// Login request
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    /// SHA512 hash of password (hex encoded)
+    pub password_hash: String,
+}
+
+/// Login response
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LoginResponse {
+    pub token: String,
+}
+
+/// API error, carrying enough information for `IntoResponse` to pick both an
+/// HTTP status code and a machine-readable `code` string, so clients don't
+/// have to pattern-match on free-form messages.
+#[derive(Debug)]
+pub enum ApiError {
+    NotInitialized,
+    AlreadyInitialized,
+    InvalidCredentials,
+    MissingCredentials,
+    BadJson(String),
+    NotFound,
+    Conflict(String),
+    Internal(String),
+    Unauthorized,
+    Forbidden,
+}
+
+impl ApiError {
+    fn status(&self) -> StatusCode {
+        match self {
+            ApiError::NotInitialized => StatusCode::UNAUTHORIZED,
+            ApiError::AlreadyInitialized => StatusCode::CONFLICT,
+            ApiError::InvalidCredentials => StatusCode::UNAUTHORIZED,
+            ApiError::MissingCredentials => StatusCode::UNAUTHORIZED,
+            ApiError::BadJson(_) => StatusCode::BAD_REQUEST,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+        }
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            ApiError::NotInitialized => "not_initialized",
+            ApiError::AlreadyInitialized => "already_initialized",
+            ApiError::InvalidCredentials => "invalid_credentials",
+            ApiError::MissingCredentials => "missing_credentials",
+            ApiError::BadJson(_) => "bad_json",
+            ApiError::NotFound => "not_found",
+            ApiError::Conflict(_) => "conflict",
+            ApiError::Internal(_) => "internal",
+            ApiError::Unauthorized => "unauthorized",
+            ApiError::Forbidden => "forbidden",
+        }
+    }
+
+    fn message(&self) -> String {
+        match self {
+            ApiError::NotInitialized => "Server not initialized".to_string(),
+            ApiError::AlreadyInitialized => "Server already initialized".to_string(),
+            ApiError::InvalidCredentials => "Invalid credentials".to_string(),
+            ApiError::MissingCredentials => "Missing or malformed Authorization header".to_string(),
+            ApiError::BadJson(msg) => msg.clone(),
+            ApiError::NotFound => "Not found".to_string(),
+            ApiError::Conflict(msg) => msg.clone(),
+            ApiError::Internal(msg) => msg.clone(),
+            ApiError::Unauthorized => "Unauthorized".to_string(),
+            ApiError::Forbidden => "Forbidden".to_string(),
+        }
+    }
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, Json(self)).into_response()
+        let status = self.status();
+        let body = Json(serde_json::json!({
+            "error": {
+                "code": self.code(),
+                "message": self.message(),
+            }
+        }));
+        (status, body).into_response()
+    }
+}
+
+/// Registers the `session_token` bearer scheme so `security(("session_token"
+/// = []))` on each `#[utoipa::path]` renders an "Authorize" button in
+/// Swagger UI instead of a dangling reference.
+struct SecurityAddon;
+
+impl utoipa::Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+
+        let components = openapi.components.as_mut().expect("paths register at least one schema");
+        components.add_security_scheme(
+            "session_token",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()),
+        );
     }
 }
 
+/// The generated OpenAPI document, served at `GET /openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_status,
+        login,
+        init,
+        serve_acme_challenge,
+        get_acme_accounts,
+        post_acme_renew,
+        get_plugins,
+        post_plugins,
+        list_tokens,
+        post_list_tokens,
+        create_token,
+        verify_token,
+        get_token,
+        delete_token,
+        set_credential,
+        delete_credential,
+        get_activity,
+        post_activity,
+        activity_stream,
+    ),
+    components(schemas(
+        StatusResponse,
+        LoginRequest,
+        LoginResponse,
+        InitRequest,
+        InitResponse,
+        AuthenticatedRequest<InitRequest>,
+        AcmeAccountInfo,
+        PluginsResponse,
+        TokensResponse,
+        TokenResponse,
+        CreateTokenRequest,
+        VerifyTokenRequest,
+        SetCredentialRequest,
+        ActivityEntry,
+        ActivityResponse,
+    )),
+    modifiers(&SecurityAddon),
+)]
+struct ApiDoc;
+
 /// Create the API router
 pub fn create_router(state: ApiState) -> Router {
     Router::new()
         .route("/status", get(get_status))
         .route("/init", post(init))
+        .route("/login", post(login))
+        .route("/.well-known/acme-challenge/:token", get(serve_acme_challenge))
+        .route("/acme/accounts", get(get_acme_accounts))
+        .route("/acme/renew", post(post_acme_renew))
         .route("/plugins", get(get_plugins).post(post_plugins))
         .route("/tokens", get(list_tokens).post(post_list_tokens))
         .route("/tokens/create", post(create_token))
-        .route("/tokens/:id", delete(delete_token))
+        .route("/tokens/verify", post(verify_token))
+        .route("/tokens/:id", get(get_token).delete(delete_token))
         .route(
             "/credentials/:plugin/:key",
             post(set_credential).delete(delete_credential),
         )
         .route("/activity", get(get_activity).post(post_activity))
+        .route("/activity/stream", get(activity_stream))
+        .merge(SwaggerUi::new("/docs").url("/openapi.json", ApiDoc::openapi()))
         .with_state(state)
 }
 
 /// GET /status - Server status (no auth required)
+#[utoipa::path(
+    get,
+    path = "/status",
+    responses((status = 200, description = "Server status", body = StatusResponse)),
+)]
 async fn get_status(State(state): State<ApiState>) -> Json<StatusResponse> {
     let uptime = state.start_time.elapsed().as_secs();
 
@@ -256,131 +784,573 @@ async fn get_status(State(state): State<ApiState>) -> Json<StatusResponse> {
     })
 }
 
+/// POST /login - Exchange the account password for a short-lived session
+/// token (no auth required; this endpoint *is* the auth).
+#[utoipa::path(
+    post,
+    path = "/login",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "Session token issued", body = LoginResponse),
+        (status = 401, description = "Invalid credentials or server not initialized"),
+    ),
+)]
+async fn login(
+    State(state): State<ApiState>,
+    body: Bytes,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let req: LoginRequest = serde_json::from_slice(&body)
+        .map_err(|e| ApiError::BadJson(format!("Invalid JSON: {}", e)))?;
+
+    verify_password(&state, &req.password_hash).await?;
+
+    let secret = state
+        .jwt_secret
+        .read()
+        .await
+        .clone()
+        .ok_or(ApiError::NotInitialized)?;
+
+    let now = Utc::now();
+    let claims = SessionClaims {
+        sub: "admin".to_string(),
+        iat: now.timestamp(),
+        exp: (now + chrono::Duration::seconds(SESSION_TOKEN_TTL_SECS)).timestamp(),
+    };
+
+    let token = jsonwebtoken::encode(
+        &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+        &claims,
+        &jsonwebtoken::EncodingKey::from_secret(&secret),
+    )
+    .map_err(|e| ApiError::Internal(format!("Failed to sign session token: {}", e)))?;
+
+    Ok(Json(LoginResponse { token }))
+}
+
 /// POST /init - Initialize server with password and CA (no auth required initially)
+#[utoipa::path(
+    post,
+    path = "/init",
+    request_body = AuthenticatedRequest<InitRequest>,
+    responses(
+        (status = 200, description = "Server initialized", body = InitResponse),
+        (status = 409, description = "Server already initialized"),
+    ),
+)]
 async fn init(
     State(state): State<ApiState>,
     body: Bytes,
-) -> Result<Json<InitResponse>, (StatusCode, String)> {
+) -> Result<Json<InitResponse>, ApiError> {
     use acp_lib::storage::create_store;
     use acp_lib::tls::CertificateAuthority;
-    use argon2::password_hash::{rand_core::OsRng, SaltString};
+    use argon2::password_hash::{
+        rand_core::{OsRng, RngCore},
+        SaltString,
+    };
     use argon2::{Argon2, PasswordHasher};
 
     // Check if already initialized
     {
         let hash = state.password_hash.read().await;
         if hash.is_some() {
-            return Err((StatusCode::CONFLICT, "Server already initialized".to_string()));
+            return Err(ApiError::AlreadyInitialized);
         }
     }
 
     // Parse request
     let req: AuthenticatedRequest<InitRequest> = serde_json::from_slice(&body)
-        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid JSON: {}", e)))?;
-
-    // Hash the password_hash with Argon2 (password_hash is already SHA512 from client)
+        .map_err(|e| ApiError::BadJson(format!("Invalid JSON: {}", e)))?;
+
+    // Hash the password_hash with Argon2id (password_hash is already SHA512
+    // from client), using the caller's cost overrides if given, falling back
+    // to the current policy otherwise.
+    let default_policy = current_argon2_policy();
+    let params = argon2::Params::new(
+        req.data.argon2_memory_kib.unwrap_or_else(|| default_policy.m_cost()),
+        req.data.argon2_iterations.unwrap_or_else(|| default_policy.t_cost()),
+        req.data.argon2_parallelism.unwrap_or_else(|| default_policy.p_cost()),
+        None,
+    )
+    .map_err(|e| ApiError::BadJson(format!("Invalid argon2 parameters: {}", e)))?;
     let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-    let password_hash = argon2
+    let password_hash = argon2_with_params(params)
         .hash_password(req.password_hash.as_bytes(), &salt)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to hash password: {}", e)))?
+        .map_err(|e| ApiError::Internal(format!("Failed to hash password: {}", e)))?
         .to_string();
 
     // Store password hash
     state.set_password_hash(password_hash).await;
 
+    // Generate the HS256 secret `/login` will sign session tokens with,
+    // persisting it so it survives a restart.
+    let mut jwt_secret = vec![0u8; 32];
+    OsRng.fill_bytes(&mut jwt_secret);
+    let secret_store = create_store(None)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
+    secret_store
+        .set("api:jwt_secret", &jwt_secret)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store session secret: {}", e)))?;
+    state.set_jwt_secret(jwt_secret).await;
+
+    // If an ACME directory was supplied, obtain a publicly-trusted
+    // certificate instead of generating a self-signed CA.
+    if let Some(directory_url) = req.data.acme_directory.clone() {
+        let email = req.data.acme_email.clone().ok_or_else(|| {
+            ApiError::BadJson("acme_email is required when acme_directory is set".to_string())
+        })?;
+        let sans = req.data.management_sans.clone().unwrap_or_default();
+        if sans.is_empty() {
+            return Err(ApiError::BadJson(
+                "management_sans is required when acme_directory is set".to_string(),
+            ));
+        }
+
+        let cert_path = provision_via_acme(&state, &directory_url, &email, &sans)
+            .await
+            .map_err(|e| ApiError::Internal(format!("ACME provisioning failed: {}", e)))?;
+
+        return Ok(Json(InitResponse { ca_path: cert_path }));
+    }
+
     // Generate CA
     let ca = CertificateAuthority::generate()
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to generate CA: {}", e)))?;
+        .map_err(|e| ApiError::Internal(format!("Failed to generate CA: {}", e)))?;
 
     // Store CA private key in SecretStore
     let store = create_store(None)
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create store: {}", e)))?;
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
 
     store
         .set("ca:private_key", ca.ca_key_pem().as_bytes())
         .await
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to store CA key: {}", e)))?;
+        .map_err(|e| ApiError::Internal(format!("Failed to store CA key: {}", e)))?;
 
     // Determine CA certificate path
     let ca_path = if let Some(path) = req.data.ca_path {
         path
     } else {
         // Default to ~/.config/acp/ca.crt
-        let home = std::env::var("HOME")
-            .map_err(|_| (StatusCode::INTERNAL_SERVER_ERROR, "HOME env var not set".to_string()))?;
+        let home = std::env::var("HOME").map_err(|_| ApiError::Internal("HOME env var not set".to_string()))?;
         format!("{}/.config/acp/ca.crt", home)
     };
 
     // Export CA certificate to filesystem
-    let ca_dir = std::path::Path::new(&ca_path).parent()
-        .ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid CA path".to_string()))?;
+    let ca_dir = std::path::Path::new(&ca_path)
+        .parent()
+        .ok_or_else(|| ApiError::BadJson("Invalid CA path".to_string()))?;
 
     std::fs::create_dir_all(ca_dir)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create CA directory: {}", e)))?;
+        .map_err(|e| ApiError::Internal(format!("Failed to create CA directory: {}", e)))?;
 
     std::fs::write(&ca_path, ca.ca_cert_pem())
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to write CA cert: {}", e)))?;
+        .map_err(|e| ApiError::Internal(format!("Failed to write CA cert: {}", e)))?;
 
     Ok(Json(InitResponse { ca_path }))
 }
 
-/// GET /plugins - List installed plugins (requires auth)
+/// Run the ACME flow for `sans` against `directory_url`: register an account,
+/// order the certificate, satisfy the http-01 challenges (serving the key
+/// authorization via `/.well-known/acme-challenge/:token`), finalize with a
+/// freshly generated CSR, and persist the issued chain and key.
+///
+/// Returns the filesystem path the certificate chain was written to.
+async fn provision_via_acme(
+    state: &ApiState,
+    directory_url: &str,
+    email: &str,
+    sans: &[String],
+) -> acp_lib::Result<String> {
+    use acp_lib::acme::{generate_csr, needs_renewal, AcmeClient};
+    use acp_lib::storage::create_store;
+
+    let client = AcmeClient::new(directory_url).await?;
+    let account = client.new_account(email).await?;
+    let (order, order_url) = client.new_order(&account, sans).await?;
+
+    for authz_url in &order.authorizations {
+        let authz = client.fetch_authorization(authz_url).await?;
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|c| c.challenge_type == "http-01")
+            .ok_or_else(|| acp_lib::AcpError::network("no http-01 challenge offered".to_string()))?;
+
+        let key_authorization = account.http01_response(&challenge.token);
+        state
+            .acme_challenges
+            .write()
+            .await
+            .insert(challenge.token.clone(), key_authorization);
+
+        client.respond_to_challenge(&account, challenge).await?;
+    }
+
+    let ready_order = client.poll_order(&order_url, 30).await?;
+    let (csr_der, private_key_pem) = generate_csr(sans)?;
+    let finalized = client.finalize(&account, &ready_order, &csr_der).await?;
+
+    let certificate_url = finalized
+        .certificate
+        .ok_or_else(|| acp_lib::AcpError::network("order finalized without a certificate URL".to_string()))?;
+    let cert_chain_pem = client.download_certificate(&certificate_url).await?;
+
+    let store = create_store(None).await?;
+    store.set("acme:account_key", account.to_pkcs8_pem()?.as_bytes()).await?;
+    store.set("acme:kid", account.kid.as_bytes()).await?;
+    store.set("acme:private_key", private_key_pem.as_bytes()).await?;
+    store.set("acme:cert_chain", cert_chain_pem.as_bytes()).await?;
+    store.set("acme:directory", directory_url.as_bytes()).await?;
+    store.set("acme:email", email.as_bytes()).await?;
+    store.set("acme:sans", serde_json::to_vec(sans).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?.as_slice()).await?;
+
+    debug_assert!(!needs_renewal(&cert_chain_pem, 30)?);
+
+    let home = std::env::var("HOME").map_err(|_| acp_lib::AcpError::storage("HOME env var not set".to_string()))?;
+    let cert_path = format!("{}/.config/acp/management.crt", home);
+    let cert_dir = std::path::Path::new(&cert_path)
+        .parent()
+        .ok_or_else(|| acp_lib::AcpError::storage("invalid certificate path".to_string()))?;
+    std::fs::create_dir_all(cert_dir).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+    std::fs::write(&cert_path, &cert_chain_pem).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+
+    Ok(cert_path)
+}
+
+/// Background task: every 12 hours, check whether the ACME-issued
+/// certificate is within 30 days of expiry and re-run the order if so.
+pub fn spawn_acme_renewal_task(state: ApiState) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        use acp_lib::acme::needs_renewal;
+        use acp_lib::storage::create_store;
+
+        loop {
+            tokio::time::sleep(std::time::Duration::from_secs(12 * 60 * 60)).await;
+
+            let result: acp_lib::Result<()> = async {
+                let store = create_store(None).await?;
+                let Some(cert_bytes) = store.get("acme:cert_chain").await? else {
+                    return Ok(());
+                };
+                let cert_chain_pem = String::from_utf8_lossy(&cert_bytes).to_string();
+
+                if !needs_renewal(&cert_chain_pem, 30)? {
+                    return Ok(());
+                }
+
+                let directory_url = String::from_utf8_lossy(
+                    &store.get("acme:directory").await?.unwrap_or_default(),
+                )
+                .to_string();
+                let email = String::from_utf8_lossy(&store.get("acme:email").await?.unwrap_or_default()).to_string();
+                let sans: Vec<String> = store
+                    .get("acme:sans")
+                    .await?
+                    .map(|b| serde_json::from_slice(&b).unwrap_or_default())
+                    .unwrap_or_default();
+
+                tracing::info!("ACME certificate nearing expiry, renewing");
+                provision_via_acme(&state, &directory_url, &email, &sans).await?;
+                Ok(())
+            }
+            .await;
+
+            if let Err(e) = result {
+                tracing::warn!("ACME renewal check failed: {}", e);
+            }
+        }
+    })
+}
+
+/// GET /.well-known/acme-challenge/:token - serve the http-01 key authorization
+#[utoipa::path(
+    get,
+    path = "/.well-known/acme-challenge/{token}",
+    params(("token" = String, Path, description = "ACME http-01 challenge token")),
+    responses(
+        (status = 200, description = "Key authorization", body = String),
+        (status = 404, description = "Unknown challenge token"),
+    ),
+)]
+async fn serve_acme_challenge(
+    State(state): State<ApiState>,
+    Path(token): Path<String>,
+) -> Result<String, ApiError> {
+    state
+        .acme_challenges
+        .read()
+        .await
+        .get(&token)
+        .cloned()
+        .ok_or(ApiError::NotFound)
+}
+
+/// ACME account/certificate status, as reported by `GET /acme/accounts`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AcmeAccountInfo {
+    pub directory: String,
+    pub email: String,
+    pub sans: Vec<String>,
+    pub expires_at: String,
+    pub needs_renewal: bool,
+}
+
+/// GET /acme/accounts - ACME account and certificate status (requires auth)
+#[utoipa::path(
+    get,
+    path = "/acme/accounts",
+    responses((status = 200, description = "ACME account/certificate status", body = Vec<AcmeAccountInfo>)),
+    security(("session_token" = [])),
+)]
+async fn get_acme_accounts(
+    State(state): State<ApiState>,
+    _auth: AuthOnly,
+) -> Result<Json<Vec<AcmeAccountInfo>>, ApiError> {
+    use acp_lib::acme::needs_renewal;
+    use acp_lib::storage::create_store;
+
+    let store = create_store(None)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
+
+    let Some(cert_bytes) = store
+        .get("acme:cert_chain")
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+    else {
+        return Ok(Json(vec![]));
+    };
+    let cert_chain_pem = String::from_utf8_lossy(&cert_bytes).to_string();
+
+    let directory = String::from_utf8_lossy(
+        &store
+            .get("acme:directory")
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .unwrap_or_default(),
+    )
+    .to_string();
+    let email = String::from_utf8_lossy(
+        &store
+            .get("acme:email")
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .unwrap_or_default(),
+    )
+    .to_string();
+    let sans: Vec<String> = store
+        .get("acme:sans")
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map(|b| serde_json::from_slice(&b).unwrap_or_default())
+        .unwrap_or_default();
+    let renewal_due = needs_renewal(&cert_chain_pem, 30)
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(Json(vec![AcmeAccountInfo {
+        directory,
+        email,
+        sans,
+        expires_at: "see certificate".to_string(),
+        needs_renewal: renewal_due,
+    }]))
+}
+
+/// POST /acme/renew - force an immediate renewal check (requires auth)
+#[utoipa::path(
+    post,
+    path = "/acme/renew",
+    responses(
+        (status = 200, description = "Renewal check ran (renewed if due)"),
+        (status = 404, description = "No ACME account configured"),
+    ),
+    security(("session_token" = [])),
+)]
+async fn post_acme_renew(
+    State(state): State<ApiState>,
+    _auth: AuthOnly,
+) -> Result<StatusCode, ApiError> {
+    use acp_lib::acme::needs_renewal;
+    use acp_lib::storage::create_store;
+
+    let store = create_store(None)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
+
+    let Some(cert_bytes) = store
+        .get("acme:cert_chain")
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+    else {
+        return Err(ApiError::NotFound);
+    };
+    let cert_chain_pem = String::from_utf8_lossy(&cert_bytes).to_string();
+
+    if !needs_renewal(&cert_chain_pem, 30).map_err(|e| ApiError::Internal(e.to_string()))? {
+        return Ok(StatusCode::OK);
+    }
+
+    let directory_url = String::from_utf8_lossy(
+        &store
+            .get("acme:directory")
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .unwrap_or_default(),
+    )
+    .to_string();
+    let email = String::from_utf8_lossy(
+        &store
+            .get("acme:email")
+            .await
+            .map_err(|e| ApiError::Internal(e.to_string()))?
+            .unwrap_or_default(),
+    )
+    .to_string();
+    let sans: Vec<String> = store
+        .get("acme:sans")
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map(|b| serde_json::from_slice(&b).unwrap_or_default())
+        .unwrap_or_default();
+
+    provision_via_acme(&state, &directory_url, &email, &sans)
+        .await
+        .map_err(|e| ApiError::Internal(format!("ACME renewal failed: {}", e)))?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Storage key holding the JSON array of installed plugin names
+const PLUGINS_INDEX_KEY: &str = "plugins";
+
+/// GET /plugins - List installed plugins (requires auth, or an agent token
+/// carrying `plugins:read`)
+#[utoipa::path(
+    get,
+    path = "/plugins",
+    responses((status = 200, description = "Installed plugins", body = PluginsResponse)),
+    security(("session_token" = [])),
+)]
 async fn get_plugins(
     State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<PluginsResponse>, (StatusCode, String)> {
-    verify_auth::<serde_json::Value>(&state, &body).await?;
+    _auth: ScopedAuth<PluginsRead>,
+) -> Result<Json<PluginsResponse>, ApiError> {
+    let plugins = state
+        .store
+        .get(PLUGINS_INDEX_KEY)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .map(|bytes| serde_json::from_slice(&bytes).unwrap_or_default())
+        .unwrap_or_default();
 
-    // TODO: Load from storage in future implementation
-    Ok(Json(PluginsResponse {
-        plugins: vec![],
-    }))
+    Ok(Json(PluginsResponse { plugins }))
 }
 
 /// POST /plugins - List installed plugins (requires auth, same as GET)
+#[utoipa::path(
+    post,
+    path = "/plugins",
+    responses((status = 200, description = "Installed plugins", body = PluginsResponse)),
+    security(("session_token" = [])),
+)]
 async fn post_plugins(
-    State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<PluginsResponse>, (StatusCode, String)> {
-    get_plugins(State(state), body).await
+    state: State<ApiState>,
+    auth: ScopedAuth<PluginsRead>,
+) -> Result<Json<PluginsResponse>, ApiError> {
+    get_plugins(state, auth).await
+}
+
+/// Storage key prefix under which each agent token is kept, as `token:{id}`
+const TOKEN_KEY_PREFIX: &str = "token:";
+
+/// Load every `StoredToken` under [`TOKEN_KEY_PREFIX`]
+async fn load_stored_tokens(store: &Arc<dyn SecretStore>) -> Result<Vec<StoredToken>, ApiError> {
+    let keys = store
+        .list(TOKEN_KEY_PREFIX)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    let mut tokens = Vec::with_capacity(keys.len());
+    for key in keys {
+        if let Some(bytes) = store.get(&key).await.map_err(|e| ApiError::Internal(e.to_string()))? {
+            match serde_json::from_slice::<StoredToken>(&bytes) {
+                Ok(token) => tokens.push(token),
+                Err(e) => tracing::warn!("Failed to deserialize {}: {}", key, e),
+            }
+        }
+    }
+
+    Ok(tokens)
 }
 
 /// GET /tokens - List agent tokens (requires auth)
+#[utoipa::path(
+    get,
+    path = "/tokens",
+    responses((status = 200, description = "Agent tokens", body = TokensResponse)),
+    security(("session_token" = [])),
+)]
 async fn list_tokens(
     State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<TokensResponse>, (StatusCode, String)> {
-    verify_auth::<serde_json::Value>(&state, &body).await?;
-
-    let tokens = state.tokens.read().await;
-    let token_list: Vec<TokenResponse> = tokens.values().map(|t| t.clone().into()).collect();
+    _auth: AuthOnly,
+) -> Result<Json<TokensResponse>, ApiError> {
+    let tokens = load_stored_tokens(&state.store).await?;
+    let token_list: Vec<TokenResponse> = tokens.into_iter().map(Into::into).collect();
 
     Ok(Json(TokensResponse { tokens: token_list }))
 }
 
 /// POST /tokens - List agent tokens (requires auth, same as GET)
+#[utoipa::path(
+    post,
+    path = "/tokens",
+    responses((status = 200, description = "Agent tokens", body = TokensResponse)),
+    security(("session_token" = [])),
+)]
 async fn post_list_tokens(
-    State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<TokensResponse>, (StatusCode, String)> {
-    list_tokens(State(state), body).await
+    state: State<ApiState>,
+    auth: AuthOnly,
+) -> Result<Json<TokensResponse>, ApiError> {
+    list_tokens(state, auth).await
 }
 
 /// POST /tokens/create - Create new agent token (requires auth)
+#[utoipa::path(
+    post,
+    path = "/tokens/create",
+    request_body = CreateTokenRequest,
+    responses((status = 200, description = "Token created (secret revealed once)", body = TokenResponse)),
+    security(("session_token" = [])),
+)]
 async fn create_token(
     State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<TokenResponse>, (StatusCode, String)> {
-    let req: CreateTokenRequest = verify_auth(&state, &body).await?;
+    Authenticated(req): Authenticated<CreateTokenRequest>,
+) -> Result<Json<TokenResponse>, ApiError> {
+    if req.token_type.as_deref() == Some("paseto") {
+        return create_paseto_token(&state, &req).await;
+    }
 
-    let token = AgentToken::new(&req.name);
+    validate_scopes(&req.scopes)?;
+
+    let mut token = AgentToken::new(&req.name);
+    token.scopes = req.scopes.clone();
+    token.expires_at = req
+        .expires_in_secs
+        .map(|secs| token.created_at + chrono::Duration::seconds(secs as i64));
     let token_value = token.token.clone();
 
-    // Store token
-    let mut tokens = state.tokens.write().await;
-    tokens.insert(token.id.clone(), token.clone());
+    // Persist the token (hashed, never the plaintext secret)
+    let stored = StoredToken::from(&token);
+    let stored_json = serde_json::to_vec(&stored)
+        .map_err(|e| ApiError::Internal(format!("Failed to serialize token: {}", e)))?;
+    state
+        .store
+        .set(&format!("{}{}", TOKEN_KEY_PREFIX, token.id), &stored_json)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store token: {}", e)))?;
 
     // Return with full token (only time it's revealed)
     Ok(Json(TokenResponse {
@@ -389,70 +1359,345 @@ async fn create_token(
         prefix: token.prefix,
         token: Some(token_value),
         created_at: token.created_at,
+        scopes: token.scopes,
+        expires_at: token.expires_at,
     }))
 }
 
-/// DELETE /tokens/:id - Revoke agent token (requires auth)
-async fn delete_token(
-    State(state): State<ApiState>,
-    Path(id): Path<String>,
-    body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    verify_auth::<serde_json::Value>(&state, &body).await?;
+/// Generate a `v4.public` PASETO agent token: sign the claims with a
+/// freshly generated Ed25519 keypair, then persist only the public key
+/// (keyed by its PASERK id) - no secret material is kept at rest.
+async fn create_paseto_token(
+    state: &ApiState,
+    req: &CreateTokenRequest,
+) -> Result<Json<TokenResponse>, ApiError> {
+    use acp_lib::paseto::{paserk_id, sign, PasetoClaims};
+    use acp_lib::storage::create_store;
+    use ed25519_dalek::SigningKey;
+    use rand::rngs::OsRng;
 
-    let mut tokens = state.tokens.write().await;
-    if tokens.remove(&id).is_some() {
-        Ok(StatusCode::OK)
-    } else {
-        Ok(StatusCode::NOT_FOUND)
-    }
-}
+    let signing_key = SigningKey::generate(&mut OsRng);
+    let kid = paserk_id(&signing_key.verifying_key());
 
-/// POST /credentials/:plugin/:key - Set credential (requires auth)
-async fn set_credential(
-    State(state): State<ApiState>,
-    Path((plugin, key)): Path<(String, String)>,
-    body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let _req: SetCredentialRequest = verify_auth(&state, &body).await?;
+    let claims = PasetoClaims {
+        sub: req.name.clone(),
+        iat: Utc::now(),
+        exp: req.expires_at,
+        allowed_plugins: req.allowed_plugins.clone(),
+    };
+    let token_value = sign(&signing_key, &claims)
+        .map_err(|e| ApiError::Internal(format!("Failed to sign PASETO token: {}", e)))?;
 
-    // TODO: Store in SecretStore in future implementation
-    tracing::info!("Setting credential {}:{}", plugin, key);
-    Ok(StatusCode::OK)
+    let store = create_store(None)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
+    store
+        .set(&format!("paseto_pubkey:{}", kid), signing_key.verifying_key().as_bytes())
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store PASETO public key: {}", e)))?;
+
+    Ok(Json(TokenResponse {
+        id: kid,
+        name: req.name.clone(),
+        prefix: "v4.public".to_string(),
+        token: Some(token_value),
+        created_at: claims.iat,
+        scopes: Vec::new(),
+        expires_at: claims.exp,
+    }))
 }
 
-/// DELETE /credentials/:plugin/:key - Delete credential (requires auth)
-async fn delete_credential(
+/// POST /tokens/verify - verify a PASETO agent token, for debugging (requires auth)
+#[utoipa::path(
+    post,
+    path = "/tokens/verify",
+    request_body = VerifyTokenRequest,
+    responses(
+        (status = 200, description = "Token is valid; body carries its PASETO claims"),
+        (status = 400, description = "Malformed PASETO token"),
+        (status = 401, description = "Signature verification failed"),
+        (status = 404, description = "Unknown PASETO key id"),
+    ),
+    security(("session_token" = [])),
+)]
+async fn verify_token(
     State(state): State<ApiState>,
-    Path((plugin, key)): Path<(String, String)>,
-    body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    verify_auth::<serde_json::Value>(&state, &body).await?;
+    Authenticated(req): Authenticated<VerifyTokenRequest>,
+) -> Result<Json<acp_lib::paseto::PasetoClaims>, ApiError> {
+    use acp_lib::paseto::verify;
+    use acp_lib::storage::create_store;
+    use ed25519_dalek::VerifyingKey;
+
+    let body_part = req
+        .token
+        .strip_prefix("v4.public.")
+        .ok_or_else(|| ApiError::BadJson("Not a v4.public PASETO token".to_string()))?;
+    let (_, footer_b64) = body_part
+        .split_once('.')
+        .ok_or_else(|| ApiError::BadJson("PASETO token missing footer".to_string()))?;
+    let kid = base64::Engine::decode(&base64::engine::general_purpose::URL_SAFE_NO_PAD, footer_b64)
+        .ok()
+        .and_then(|b| String::from_utf8(b).ok())
+        .ok_or_else(|| ApiError::BadJson("Invalid PASETO footer".to_string()))?;
 
-    // TODO: Delete from SecretStore in future implementation
-    tracing::info!("Deleting credential {}:{}", plugin, key);
-    Ok(StatusCode::OK)
-}
+    let store = create_store(None)
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to create store: {}", e)))?;
+    let public_key_bytes = store
+        .get(&format!("paseto_pubkey:{}", kid))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .ok_or(ApiError::NotFound)?;
+    let public_key_array: [u8; 32] = public_key_bytes
+        .try_into()
+        .map_err(|_| ApiError::Internal("Stored PASETO public key is malformed".to_string()))?;
+    let public_key = VerifyingKey::from_bytes(&public_key_array)
+        .map_err(|e| ApiError::Internal(format!("Invalid stored PASETO public key: {}", e)))?;
 
-/// GET /activity - Get recent activity (requires auth)
-async fn get_activity(
+    let claims = verify(&req.token, &public_key).map_err(|_| ApiError::Unauthorized)?;
+
+    Ok(Json(claims))
+}
+
+/// GET /tokens/:id - Introspect an agent token's scopes and remaining
+/// validity (requires auth). Never returns the token secret.
+#[utoipa::path(
+    get,
+    path = "/tokens/{id}",
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 200, description = "Token metadata", body = TokenResponse),
+        (status = 404, description = "Token not found"),
+    ),
+    security(("session_token" = [])),
+)]
+async fn get_token(
     State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<ActivityResponse>, (StatusCode, String)> {
-    verify_auth::<serde_json::Value>(&state, &body).await?;
+    Path(id): Path<String>,
+    _auth: AuthOnly,
+) -> Result<Json<TokenResponse>, ApiError> {
+    let bytes = state
+        .store
+        .get(&format!("{}{}", TOKEN_KEY_PREFIX, id))
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .ok_or(ApiError::NotFound)?;
+    let token: StoredToken = serde_json::from_slice(&bytes)
+        .map_err(|e| ApiError::Internal(format!("Failed to parse stored token: {}", e)))?;
+
+    Ok(Json(token.into()))
+}
 
+/// DELETE /tokens/:id - Revoke agent token (requires auth)
+#[utoipa::path(
+    delete,
+    path = "/tokens/{id}",
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 200, description = "Token revoked"),
+        (status = 404, description = "Token not found"),
+    ),
+    security(("session_token" = [])),
+)]
+async fn delete_token(
+    State(state): State<ApiState>,
+    Path(id): Path<String>,
+    _auth: AuthOnly,
+) -> Result<StatusCode, ApiError> {
+    let key = format!("{}{}", TOKEN_KEY_PREFIX, id);
+    let existed = state
+        .store
+        .get(&key)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?
+        .is_some();
+
+    if !existed {
+        return Err(ApiError::NotFound);
+    }
+
+    state
+        .store
+        .delete(&key)
+        .await
+        .map_err(|e| ApiError::Internal(e.to_string()))?;
+
+    Ok(StatusCode::OK)
+}
+
+/// Namespaced storage key for a plugin credential field
+fn credential_key(plugin: &str, key: &str) -> String {
+    format!("credential:{}:{}", plugin, key)
+}
+
+/// POST /credentials/:plugin/:key - Set credential (requires auth, or an
+/// agent token carrying `credentials:write`)
+#[utoipa::path(
+    post,
+    path = "/credentials/{plugin}/{key}",
+    params(
+        ("plugin" = String, Path, description = "Plugin name"),
+        ("key" = String, Path, description = "Credential key"),
+    ),
+    request_body = SetCredentialRequest,
+    responses((status = 200, description = "Credential stored")),
+    security(("session_token" = [])),
+)]
+async fn set_credential(
+    State(state): State<ApiState>,
+    Path((plugin, key)): Path<(String, String)>,
+    ScopedAuthenticated(req, _): ScopedAuthenticated<CredentialsWrite, SetCredentialRequest>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .set(&credential_key(&plugin, &key), req.value.as_bytes())
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to store credential: {}", e)))?;
+
+    tracing::info!("Set credential {}:{}", plugin, key);
+    Ok(StatusCode::OK)
+}
+
+/// DELETE /credentials/:plugin/:key - Delete credential (requires auth, or
+/// an agent token carrying `credentials:write`)
+#[utoipa::path(
+    delete,
+    path = "/credentials/{plugin}/{key}",
+    params(
+        ("plugin" = String, Path, description = "Plugin name"),
+        ("key" = String, Path, description = "Credential key"),
+    ),
+    responses((status = 200, description = "Credential deleted")),
+    security(("session_token" = [])),
+)]
+async fn delete_credential(
+    State(state): State<ApiState>,
+    Path((plugin, key)): Path<(String, String)>,
+    _auth: ScopedAuth<CredentialsWrite>,
+) -> Result<StatusCode, ApiError> {
+    state
+        .store
+        .delete(&credential_key(&plugin, &key))
+        .await
+        .map_err(|e| ApiError::Internal(format!("Failed to delete credential: {}", e)))?;
+
+    tracing::info!("Deleted credential {}:{}", plugin, key);
+    Ok(StatusCode::OK)
+}
+
+/// Query parameters accepted by `GET`/`POST /activity`, applied in the
+/// order: filter, then `since`, then offset/limit pagination over the
+/// remaining (newest-first order preserved) entries.
+#[derive(Debug, Deserialize, Default, utoipa::IntoParams)]
+pub struct ActivityQuery {
+    pub agent_id: Option<String>,
+    pub method: Option<String>,
+    pub status: Option<u16>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+}
+
+impl ActivityQuery {
+    fn matches(&self, entry: &ActivityEntry) -> bool {
+        if let Some(agent_id) = &self.agent_id {
+            if entry.agent_id.as_deref() != Some(agent_id.as_str()) {
+                return false;
+            }
+        }
+        if let Some(method) = &self.method {
+            if !entry.method.eq_ignore_ascii_case(method) {
+                return false;
+            }
+        }
+        if let Some(status) = self.status {
+            if entry.status != status {
+                return false;
+            }
+        }
+        if let Some(since) = self.since {
+            if entry.timestamp < since {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn apply(&self, entries: &[ActivityEntry]) -> Vec<ActivityEntry> {
+        let filtered: Vec<ActivityEntry> =
+            entries.iter().filter(|e| self.matches(e)).cloned().collect();
+        let offset = self.offset.unwrap_or(0).min(filtered.len());
+        match self.limit {
+            Some(limit) => filtered.into_iter().skip(offset).take(limit).collect(),
+            None => filtered.into_iter().skip(offset).collect(),
+        }
+    }
+}
+
+/// GET /activity - Get recent activity, optionally filtered and paginated
+/// via [`ActivityQuery`] (requires auth, or an agent token carrying
+/// `activity:read`)
+#[utoipa::path(
+    get,
+    path = "/activity",
+    params(ActivityQuery),
+    responses((status = 200, description = "Matching activity entries", body = ActivityResponse)),
+    security(("session_token" = [])),
+)]
+async fn get_activity(
+    State(state): State<ApiState>,
+    Query(query): Query<ActivityQuery>,
+    _auth: ScopedAuth<ActivityRead>,
+) -> Result<Json<ActivityResponse>, ApiError> {
     let activity = state.activity.read().await;
     Ok(Json(ActivityResponse {
-        entries: activity.clone(),
+        entries: query.apply(&activity),
     }))
 }
 
 /// POST /activity - Get recent activity (requires auth, same as GET)
+#[utoipa::path(
+    post,
+    path = "/activity",
+    params(ActivityQuery),
+    responses((status = 200, description = "Matching activity entries", body = ActivityResponse)),
+    security(("session_token" = [])),
+)]
 async fn post_activity(
+    state: State<ApiState>,
+    query: Query<ActivityQuery>,
+    auth: ScopedAuth<ActivityRead>,
+) -> Result<Json<ActivityResponse>, ApiError> {
+    get_activity(state, query, auth).await
+}
+
+/// GET /activity/stream - Server-Sent Events stream of new activity entries
+/// as they're recorded, filtered the same way as `GET /activity` (requires
+/// auth, or an agent token carrying `activity:read`). Sends a keep-alive
+/// comment periodically so idle proxies/load balancers don't time out the
+/// connection.
+#[utoipa::path(
+    get,
+    path = "/activity/stream",
+    params(ActivityQuery),
+    responses((status = 200, description = "text/event-stream of ActivityEntry JSON events")),
+    security(("session_token" = [])),
+)]
+async fn activity_stream(
     State(state): State<ApiState>,
-    body: Bytes,
-) -> Result<Json<ActivityResponse>, (StatusCode, String)> {
-    get_activity(State(state), body).await
+    Query(query): Query<ActivityQuery>,
+    _auth: ScopedAuth<ActivityRead>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let stream = BroadcastStream::new(state.activity_tx.subscribe())
+        .filter_map(move |entry| {
+            let entry = entry.ok()?;
+            if !query.matches(&entry) {
+                return None;
+            }
+            Some(Ok(Event::default().json_data(&entry).expect("ActivityEntry serializes")))
+        });
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
 }
 
 #[cfg(test)]
@@ -462,9 +1707,50 @@ mod tests {
     use axum::http::Request;
     use tower::ServiceExt; // for `oneshot`
 
+    /// Sets up an `ApiState` as if `/init` had already run (password hash
+    /// and session secret both set), then exercises the real `/login`
+    /// handler to mint a bearer token for use by the caller's own requests.
+    async fn ready_state_with_token(password: &str) -> (ApiState, String) {
+        use argon2::password_hash::{rand_core::OsRng, SaltString};
+        use argon2::{Argon2, PasswordHasher};
+
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default().hash_password(password.as_bytes(), &salt).unwrap().to_string();
+        state.set_password_hash(password_hash).await;
+        state.set_jwt_secret(vec![7u8; 32]).await;
+
+        let token = login_and_get_token(state.clone(), password).await;
+        (state, token)
+    }
+
+    /// Posts `/login` with `password` as the (already-hashed, in this test
+    /// suite's simplified model) credential and returns the issued token.
+    async fn login_and_get_token(state: ApiState, password: &str) -> String {
+        let app = create_router(state);
+        let body = serde_json::json!({ "password_hash": password });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let login_response: LoginResponse = serde_json::from_slice(&body).unwrap();
+        login_response.token
+    }
+
     #[tokio::test]
     async fn test_get_status_without_auth() {
-        let state = ApiState::new(9443, 9080);
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
         let app = create_router(state);
 
         let response = app
@@ -507,30 +1793,57 @@ mod tests {
 
     #[tokio::test]
     async fn test_post_plugins_endpoint() {
-        use argon2::password_hash::{rand_core::OsRng, SaltString};
-        use argon2::{Argon2, PasswordHasher};
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
 
-        let state = ApiState::new(9443, 9080);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/plugins")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
 
-        // Set up password hash
-        let password = "testpass123";
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt).unwrap().to_string();
-        state.set_password_hash(password_hash).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
 
+    #[tokio::test]
+    async fn test_post_tokens_list_endpoint() {
+        let (state, token) = ready_state_with_token("testpass123").await;
         let app = create_router(state);
 
-        // Create auth request body
-        let body = serde_json::json!({
-            "password_hash": password
-        });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_post_tokens_create_endpoint() {
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
+
+        let body = serde_json::json!({ "name": "test-token" });
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/plugins")
+                    .uri("/tokens/create")
+                    .header("authorization", format!("Bearer {}", token))
                     .header("content-type", "application/json")
                     .body(Body::from(serde_json::to_vec(&body).unwrap()))
                     .unwrap(),
@@ -539,34 +1852,55 @@ mod tests {
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::OK);
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let token_response: TokenResponse = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(token_response.name, "test-token");
+        assert!(token_response.token.is_some());
     }
 
     #[tokio::test]
-    async fn test_post_tokens_list_endpoint() {
-        use argon2::password_hash::{rand_core::OsRng, SaltString};
-        use argon2::{Argon2, PasswordHasher};
+    async fn test_post_tokens_create_endpoint_rejects_missing_bearer_token() {
+        let (state, _token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
 
-        let state = ApiState::new(9443, 9080);
+        let body = serde_json::json!({ "name": "test-token" });
 
-        // Set up password hash
-        let password = "testpass123";
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt).unwrap().to_string();
-        state.set_password_hash(password_hash).await;
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens/create")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 
+    #[tokio::test]
+    async fn test_create_token_with_scopes_and_expiry() {
+        let (state, token) = ready_state_with_token("testpass123").await;
         let app = create_router(state);
 
-        // Create auth request body
         let body = serde_json::json!({
-            "password_hash": password
+            "name": "scoped-token",
+            "scopes": ["plugins:read", "activity:read"],
+            "expires_in_secs": 3600,
         });
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/tokens")
+                    .uri("/tokens/create")
+                    .header("authorization", format!("Bearer {}", token))
                     .header("content-type", "application/json")
                     .body(Body::from(serde_json::to_vec(&body).unwrap()))
                     .unwrap(),
@@ -575,28 +1909,25 @@ mod tests {
             .unwrap();
 
         assert_eq!(response.status(), StatusCode::OK);
-    }
-
-    #[tokio::test]
-    async fn test_post_tokens_create_endpoint() {
-        use argon2::password_hash::{rand_core::OsRng, SaltString};
-        use argon2::{Argon2, PasswordHasher};
 
-        let state = ApiState::new(9443, 9080);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let token_response: TokenResponse = serde_json::from_slice(&body).unwrap();
 
-        // Set up password hash
-        let password = "testpass123";
-        let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt).unwrap().to_string();
-        state.set_password_hash(password_hash).await;
+        assert_eq!(token_response.scopes, vec!["plugins:read", "activity:read"]);
+        assert!(token_response.expires_at.is_some());
+        assert!(token_response.expires_at.unwrap() > Utc::now());
+    }
 
+    #[tokio::test]
+    async fn test_create_token_rejects_unknown_scope() {
+        let (state, token) = ready_state_with_token("testpass123").await;
         let app = create_router(state);
 
-        // Create auth request body with name
         let body = serde_json::json!({
-            "password_hash": password,
-            "name": "test-token"
+            "name": "bad-scope-token",
+            "scopes": ["root:everything"],
         });
 
         let response = app
@@ -604,6 +1935,7 @@ mod tests {
                 Request::builder()
                     .method("POST")
                     .uri("/tokens/create")
+                    .header("authorization", format!("Bearer {}", token))
                     .header("content-type", "application/json")
                     .body(Body::from(serde_json::to_vec(&body).unwrap()))
                     .unwrap(),
@@ -611,43 +1943,377 @@ mod tests {
             .await
             .unwrap();
 
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_get_token_introspection_endpoint() {
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
+
+        let body = serde_json::json!({ "name": "introspect-me", "scopes": ["plugins:read"] });
+        let create_response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens/create")
+                    .header("authorization", format!("Bearer {}", token))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let create_body = axum::body::to_bytes(create_response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let created: TokenResponse = serde_json::from_slice(&create_body).unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/tokens/{}", created.id))
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
         assert_eq!(response.status(), StatusCode::OK);
 
         let body = axum::body::to_bytes(response.into_body(), usize::MAX)
             .await
             .unwrap();
-        let token_response: TokenResponse = serde_json::from_slice(&body).unwrap();
+        let introspected: TokenResponse = serde_json::from_slice(&body).unwrap();
 
-        assert_eq!(token_response.name, "test-token");
-        assert!(token_response.token.is_some());
+        assert_eq!(introspected.id, created.id);
+        assert_eq!(introspected.scopes, vec!["plugins:read"]);
+        assert!(introspected.token.is_none(), "introspection must never return the secret");
+    }
+
+    #[tokio::test]
+    async fn test_tokens_survive_across_api_state_instances() {
+        let data_dir = std::env::temp_dir().join("acp-test-token-durability");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::env::set_var("ACP_DATA_DIR", &data_dir);
+
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
+
+        let body = serde_json::json!({ "name": "durable-token" });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens/create")
+                    .header("authorization", format!("Bearer {}", token))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let create_body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let created: TokenResponse = serde_json::from_slice(&create_body).unwrap();
+
+        // A brand new ApiState (simulating a server restart) backed by the
+        // same data directory should see the token that the first instance wrote.
+        let (restarted_state, restarted_token) = ready_state_with_token("testpass123").await;
+        let restarted_app = create_router(restarted_state);
+
+        let response = restarted_app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri(format!("/tokens/{}", created.id))
+                    .header("authorization", format!("Bearer {}", restarted_token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        std::env::remove_var("ACP_DATA_DIR");
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_plugins_reads_from_store() {
+        let data_dir = std::env::temp_dir().join("acp-test-plugins-index");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::env::set_var("ACP_DATA_DIR", &data_dir);
+
+        let (state, token) = ready_state_with_token("testpass123").await;
+        state
+            .store
+            .set(PLUGINS_INDEX_KEY, serde_json::to_vec(&vec!["exa", "github"]).unwrap().as_slice())
+            .await
+            .unwrap();
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/plugins")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let plugins: PluginsResponse = serde_json::from_slice(&body).unwrap();
+
+        std::env::remove_var("ACP_DATA_DIR");
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert_eq!(plugins.plugins, vec!["exa", "github"]);
+    }
+
+    #[tokio::test]
+    async fn test_set_and_delete_credential_persist_through_store() {
+        let data_dir = std::env::temp_dir().join("acp-test-credential-store");
+        std::fs::create_dir_all(&data_dir).unwrap();
+        std::env::set_var("ACP_DATA_DIR", &data_dir);
+
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state.clone());
+
+        let body = serde_json::json!({ "value": "sk-test-value" });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/credentials/exa/api_key")
+                    .header("authorization", format!("Bearer {}", token))
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let stored = state.store.get(&credential_key("exa", "api_key")).await.unwrap();
+        assert_eq!(stored, Some(b"sk-test-value".to_vec()));
+
+        let app = create_router(state.clone());
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/credentials/exa/api_key")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        std::env::remove_var("ACP_DATA_DIR");
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(state.store.get(&credential_key("exa", "api_key")).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_require_scope_rejects_missing_scope() {
+        let mut token = AgentToken::new("agent-1");
+        token.scopes = vec!["plugins:read".to_string()];
+        token.expires_at = None;
+
+        let result = require_scope(&token, "credentials:write");
+        assert_eq!(result.unwrap_err().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_require_scope_rejects_expired_token() {
+        let mut token = AgentToken::new("agent-1");
+        token.scopes = vec!["plugins:read".to_string()];
+        token.expires_at = Some(Utc::now() - chrono::Duration::seconds(5));
+
+        let result = require_scope(&token, "plugins:read");
+        assert_eq!(result.unwrap_err().status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_require_scope_allows_valid_token() {
+        let mut token = AgentToken::new("agent-1");
+        token.scopes = vec!["plugins:read".to_string()];
+        token.expires_at = Some(Utc::now() + chrono::Duration::seconds(60));
+
+        assert!(require_scope(&token, "plugins:read").is_ok());
     }
 
     #[tokio::test]
     async fn test_post_activity_endpoint() {
+        let (state, token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/activity")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_activity_filters_by_method_and_status() {
+        let (state, token) = ready_state_with_token("testpass123").await;
+        state
+            .record_activity(ActivityEntry {
+                timestamp: Utc::now(),
+                method: "GET".to_string(),
+                url: "https://example.com/a".to_string(),
+                agent_id: Some("agent-1".to_string()),
+                status: 200,
+            })
+            .await;
+        state
+            .record_activity(ActivityEntry {
+                timestamp: Utc::now(),
+                method: "POST".to_string(),
+                url: "https://example.com/b".to_string(),
+                agent_id: Some("agent-2".to_string()),
+                status: 500,
+            })
+            .await;
+
+        let app = create_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/activity?method=post&status=500")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let activity: ActivityResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(activity.entries.len(), 1);
+        assert_eq!(activity.entries[0].agent_id.as_deref(), Some("agent-2"));
+    }
+
+    #[tokio::test]
+    async fn test_get_activity_paginates_with_limit_and_offset() {
+        let (state, token) = ready_state_with_token("testpass123").await;
+        for i in 0..5 {
+            state
+                .record_activity(ActivityEntry {
+                    timestamp: Utc::now(),
+                    method: "GET".to_string(),
+                    url: format!("https://example.com/{}", i),
+                    agent_id: None,
+                    status: 200,
+                })
+                .await;
+        }
+
+        let app = create_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/activity?offset=2&limit=2")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let activity: ActivityResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(activity.entries.len(), 2);
+        assert_eq!(activity.entries[0].url, "https://example.com/2");
+    }
+
+    #[tokio::test]
+    async fn test_activity_stream_rejects_missing_bearer_token() {
+        let (state, _token) = ready_state_with_token("testpass123").await;
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("GET")
+                    .uri("/activity/stream")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_record_activity_broadcasts_to_subscribers() {
+        let (state, _token) = ready_state_with_token("testpass123").await;
+        let mut rx = state.activity_tx.subscribe();
+
+        state
+            .record_activity(ActivityEntry {
+                timestamp: Utc::now(),
+                method: "GET".to_string(),
+                url: "https://example.com".to_string(),
+                agent_id: Some("agent-1".to_string()),
+                status: 200,
+            })
+            .await;
+
+        let received = rx.recv().await.unwrap();
+        assert_eq!(received.agent_id.as_deref(), Some("agent-1"));
+    }
+
+    #[tokio::test]
+    async fn test_login_issues_valid_session_token() {
+        let (_state, token) = ready_state_with_token("testpass123").await;
+        assert!(!token.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_login_rejects_wrong_password() {
         use argon2::password_hash::{rand_core::OsRng, SaltString};
         use argon2::{Argon2, PasswordHasher};
 
-        let state = ApiState::new(9443, 9080);
-
-        // Set up password hash
-        let password = "testpass123";
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
         let salt = SaltString::generate(&mut OsRng);
-        let argon2 = Argon2::default();
-        let password_hash = argon2.hash_password(password.as_bytes(), &salt).unwrap().to_string();
+        let password_hash = Argon2::default().hash_password(b"correct-password", &salt).unwrap().to_string();
         state.set_password_hash(password_hash).await;
+        state.set_jwt_secret(vec![7u8; 32]).await;
 
         let app = create_router(state);
-
-        // Create auth request body
-        let body = serde_json::json!({
-            "password_hash": password
-        });
+        let body = serde_json::json!({ "password_hash": "wrong-password" });
 
         let response = app
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/activity")
+                    .uri("/login")
                     .header("content-type", "application/json")
                     .body(Body::from(serde_json::to_vec(&body).unwrap()))
                     .unwrap(),
@@ -655,12 +2321,76 @@ mod tests {
             .await
             .unwrap();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_rejects_token_signed_with_different_secret() {
+        let (state, _token) = ready_state_with_token("testpass123").await;
+
+        let forged_claims = SessionClaims {
+            sub: "admin".to_string(),
+            iat: Utc::now().timestamp(),
+            exp: (Utc::now() + chrono::Duration::seconds(SESSION_TOKEN_TTL_SECS)).timestamp(),
+        };
+        let forged_token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+            &forged_claims,
+            &jsonwebtoken::EncodingKey::from_secret(b"not-the-real-secret"),
+        )
+        .unwrap();
+
+        let app = create_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens")
+                    .header("authorization", format!("Bearer {}", forged_token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_authenticated_rejects_expired_token() {
+        let (state, _token) = ready_state_with_token("testpass123").await;
+
+        let expired_claims = SessionClaims {
+            sub: "admin".to_string(),
+            iat: (Utc::now() - chrono::Duration::seconds(SESSION_TOKEN_TTL_SECS * 2)).timestamp(),
+            exp: (Utc::now() - chrono::Duration::seconds(1)).timestamp(),
+        };
+        let expired_token = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::HS256),
+            &expired_claims,
+            &jsonwebtoken::EncodingKey::from_secret(&[7u8; 32]),
+        )
+        .unwrap();
+
+        let app = create_router(state);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/tokens")
+                    .header("authorization", format!("Bearer {}", expired_token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
     #[tokio::test]
     async fn test_init_endpoint() {
-        let state = ApiState::new(9443, 9080);
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
         let app = create_router(state.clone());
 
         let password = "testpass123";
@@ -695,5 +2425,124 @@ mod tests {
         // Password hash should be set in state
         let hash = state.password_hash.read().await;
         assert!(hash.is_some());
+
+        // A session-signing secret should have been generated too
+        let secret = state.jwt_secret.read().await;
+        assert_eq!(secret.as_ref().map(Vec::len), Some(32));
+    }
+
+    #[tokio::test]
+    async fn test_init_endpoint_honors_custom_argon2_params() {
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
+        let app = create_router(state.clone());
+
+        let body = serde_json::json!({
+            "password_hash": "testpass123",
+            "argon2_memory_kib": 32768,
+            "argon2_iterations": 3,
+            "argon2_parallelism": 2,
+        });
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/init")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let hash = state.password_hash.read().await.clone().unwrap();
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(hash.contains("m=32768,t=3,p=2"));
+    }
+
+    #[tokio::test]
+    async fn test_login_upgrades_weak_stored_hash() {
+        use argon2::password_hash::{rand_core::OsRng, PasswordHasher, SaltString};
+
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
+
+        // Simulate a hash created under a much weaker, older policy.
+        let weak_params = argon2::Params::new(8, 1, 1, None).unwrap();
+        let weak_argon2 = argon2_with_params(weak_params);
+        let salt = SaltString::generate(&mut OsRng);
+        let weak_hash = weak_argon2
+            .hash_password("testpass123".as_bytes(), &salt)
+            .unwrap()
+            .to_string();
+        state.set_password_hash(weak_hash.clone()).await;
+        state.set_jwt_secret(vec![7u8; 32]).await;
+
+        let app = create_router(state.clone());
+
+        let body = serde_json::json!({ "password_hash": "testpass123" });
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header("content-type", "application/json")
+                    .body(Body::from(serde_json::to_vec(&body).unwrap()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let upgraded = state.password_hash.read().await.clone().unwrap();
+        assert_ne!(upgraded, weak_hash);
+        let parsed = PasswordHash::new(&upgraded).unwrap();
+        assert!(meets_policy(&parsed, &current_argon2_policy()));
+    }
+
+    #[tokio::test]
+    async fn test_serve_acme_challenge() {
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
+        state
+            .acme_challenges
+            .write()
+            .await
+            .insert("tok123".to_string(), "tok123.thumbprint".to_string());
+
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/.well-known/acme-challenge/tok123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"tok123.thumbprint");
+    }
+
+    #[tokio::test]
+    async fn test_serve_acme_challenge_unknown_token() {
+        let state = ApiState::new(9443, 9080).await.expect("create ApiState");
+        let app = create_router(state);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/.well-known/acme-challenge/missing")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
 }
@@ -0,0 +1,135 @@
+//! Typed Apple property-list (plist) builder
+//!
+//! Generates plist XML from a small structured model rather than
+//! `format!`-ing strings directly, so values are always escaped correctly
+//! and the keyset is easy to extend.
+
+/// A single plist value. Mirrors the subset of plist types launchd configs
+/// actually use.
+pub enum PlistValue {
+    String(String),
+    Bool(bool),
+    Integer(i64),
+    Array(Vec<PlistValue>),
+    Dict(Vec<(String, PlistValue)>),
+}
+
+impl PlistValue {
+    fn write_xml(&self, out: &mut String, indent: usize) {
+        let pad = "  ".repeat(indent);
+        match self {
+            PlistValue::String(s) => {
+                out.push_str(&pad);
+                out.push_str("<string>");
+                out.push_str(&escape(s));
+                out.push_str("</string>\n");
+            }
+            PlistValue::Bool(b) => {
+                out.push_str(&pad);
+                out.push_str(if *b { "<true/>\n" } else { "<false/>\n" });
+            }
+            PlistValue::Integer(n) => {
+                out.push_str(&pad);
+                out.push_str("<integer>");
+                out.push_str(&n.to_string());
+                out.push_str("</integer>\n");
+            }
+            PlistValue::Array(items) => {
+                out.push_str(&pad);
+                out.push_str("<array>\n");
+                for item in items {
+                    item.write_xml(out, indent + 1);
+                }
+                out.push_str(&pad);
+                out.push_str("</array>\n");
+            }
+            PlistValue::Dict(entries) => {
+                out.push_str(&pad);
+                out.push_str("<dict>\n");
+                for (key, value) in entries {
+                    out.push_str(&"  ".repeat(indent + 1));
+                    out.push_str("<key>");
+                    out.push_str(&escape(key));
+                    out.push_str("</key>\n");
+                    value.write_xml(out, indent + 1);
+                }
+                out.push_str(&pad);
+                out.push_str("</dict>\n");
+            }
+        }
+    }
+}
+
+/// Escape the five XML special characters for use inside plist text nodes.
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+/// Render a top-level plist document wrapping `root` in the standard
+/// doctype and `<plist version="1.0">` header.
+pub fn render(root: PlistValue) -> String {
+    let mut body = String::new();
+    root.write_xml(&mut body, 0);
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+<plist version=\"1.0\">\n{}</plist>\n",
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escapes_special_characters() {
+        let plist = render(PlistValue::Dict(vec![(
+            "Program".to_string(),
+            PlistValue::String("/path/with & <special> \"chars\"".to_string()),
+        )]));
+
+        assert!(plist.contains("/path/with &amp; &lt;special&gt; &quot;chars&quot;"));
+        assert!(!plist.contains("with & <special>"));
+    }
+
+    #[test]
+    fn test_renders_bool_integer_array() {
+        let plist = render(PlistValue::Dict(vec![
+            ("RunAtLoad".to_string(), PlistValue::Bool(true)),
+            ("Nice".to_string(), PlistValue::Integer(5)),
+            (
+                "ProgramArguments".to_string(),
+                PlistValue::Array(vec![
+                    PlistValue::String("/usr/local/bin/acp-server".to_string()),
+                    PlistValue::String("run".to_string()),
+                ]),
+            ),
+        ]));
+
+        assert!(plist.contains("<true/>"));
+        assert!(plist.contains("<integer>5</integer>"));
+        assert!(plist.contains("<string>/usr/local/bin/acp-server</string>"));
+        assert!(plist.contains("<string>run</string>"));
+    }
+
+    #[test]
+    fn test_wraps_in_plist_document_header() {
+        let plist = render(PlistValue::Dict(vec![]));
+        assert!(plist.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"));
+        assert!(plist.trim().ends_with("</plist>"));
+        assert!(plist.contains("<plist version=\"1.0\">"));
+    }
+}
@@ -0,0 +1,1142 @@
+//! Cross-platform background service management
+//!
+//! Provides unit-file generation and lifecycle management for running
+//! acp-server as a user-level background service: a macOS LaunchAgent, a
+//! Linux systemd user unit, or a Windows Task Scheduler task. Each platform
+//! implements the same [`ServiceManager`] trait so callers get a uniform
+//! API regardless of `target_os` - `install`/`uninstall`/`status`/`restart`
+//! shell out to that platform's native service manager (`launchctl`,
+//! `systemctl --user`, `schtasks`). The LaunchAgent plist is built through
+//! the typed `crate::plist` model rather than string formatting. Both the
+//! LaunchAgent and systemd backends inject a sanitized environment (see
+//! [`normalize_service_env`]) so the managed process sees a sane `PATH`
+//! instead of whatever minimal set it's launched with.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[cfg(target_os = "macos")]
+use crate::plist::{render, PlistValue};
+
+/// Build a sanitized environment to inject into the managed service's unit
+/// file. LaunchAgents and systemd user units both launch with a minimal
+/// environment (often a `PATH` missing `/usr/local/bin`, and on Linux no
+/// `XDG_DATA_DIRS`/`XDG_CONFIG_DIRS`), so `acp-server` and any plugins it
+/// shells out to can fail to find tools or config it would see in an
+/// interactive shell.
+///
+/// Starts from the current process environment, then normalizes `PATH`
+/// (and, on Linux, `XDG_DATA_DIRS`/`XDG_CONFIG_DIRS`) by splitting on the
+/// platform's path separator, dropping empty and duplicate entries
+/// (first-seen wins), and appending baseline directories if they're
+/// missing. Callers can inspect or override the result before passing it
+/// into unit generation.
+pub fn normalize_service_env() -> BTreeMap<String, String> {
+    let mut env: BTreeMap<String, String> = std::env::vars().collect();
+
+    let path = env.get("PATH").cloned().unwrap_or_default();
+    env.insert("PATH".to_string(), normalize_path_list(&path, &["/usr/local/bin", "/usr/bin"]));
+
+    #[cfg(target_os = "linux")]
+    {
+        let data_dirs = env.get("XDG_DATA_DIRS").cloned().unwrap_or_default();
+        env.insert(
+            "XDG_DATA_DIRS".to_string(),
+            normalize_path_list(&data_dirs, &["/usr/local/share", "/usr/share"]),
+        );
+
+        let config_dirs = env.get("XDG_CONFIG_DIRS").cloned().unwrap_or_default();
+        env.insert(
+            "XDG_CONFIG_DIRS".to_string(),
+            normalize_path_list(&config_dirs, &["/etc/xdg"]),
+        );
+    }
+
+    env
+}
+
+/// Split `list` on the platform path separator, drop empty and duplicate
+/// entries (first-seen wins), then append any `defaults` not already
+/// present, and rejoin with the platform separator.
+fn normalize_path_list(list: &str, defaults: &[&str]) -> String {
+    let mut seen = std::collections::HashSet::new();
+    let mut entries = Vec::new();
+
+    for entry in std::env::split_paths(list) {
+        let entry = entry.to_string_lossy().to_string();
+        if entry.is_empty() {
+            continue;
+        }
+        if seen.insert(entry.clone()) {
+            entries.push(entry);
+        }
+    }
+
+    for &default in defaults {
+        if seen.insert(default.to_string()) {
+            entries.push(default.to_string());
+        }
+    }
+
+    std::env::join_paths(entries)
+        .map(|joined| joined.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Whether the installed service is currently running, stopped, or not
+/// registered with the platform's service manager at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServiceState {
+    Running,
+    Stopped,
+    NotInstalled,
+}
+
+/// Snapshot of the service's current state, as reported by the platform's
+/// service manager.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceStatus {
+    pub state: ServiceState,
+    /// Exit code from the last run, when the service manager reports one.
+    pub last_exit_code: Option<i32>,
+}
+
+/// Operations needed to install and manage acp-server as a user-level
+/// background service, implemented per-platform.
+pub trait ServiceManager {
+    /// Render the service unit file contents for `binary_path`.
+    fn unit_contents(&self, binary_path: &str) -> String;
+
+    /// Where the rendered unit file should be installed.
+    fn install_path(&self) -> PathBuf;
+
+    /// Directory service stdout/stderr logs are written to.
+    fn log_dir(&self) -> PathBuf;
+
+    /// Write the unit file and register the service to start automatically.
+    fn install(&self, binary_path: &str) -> acp_lib::Result<()>;
+
+    /// Stop the service if running, then remove the unit file.
+    fn uninstall(&self) -> acp_lib::Result<()>;
+
+    /// Report whether the service is running, stopped, or not installed.
+    fn status(&self) -> acp_lib::Result<ServiceStatus>;
+
+    /// Restart the service, starting it if it isn't already running.
+    fn restart(&self) -> acp_lib::Result<()>;
+}
+
+/// Write `contents` to `path`, creating `log_dir` and `path`'s parent
+/// directory first if they don't exist yet.
+fn write_unit_file(path: &std::path::Path, contents: &str, log_dir: &std::path::Path) -> acp_lib::Result<()> {
+    std::fs::create_dir_all(log_dir).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+    }
+    std::fs::write(path, contents).map_err(|e| acp_lib::AcpError::storage(e.to_string()))
+}
+
+/// Run `command`, mapping a failure to spawn it (e.g. the binary isn't on
+/// `PATH`) to [`acp_lib::AcpError`]. Does not itself check the exit status -
+/// callers inspect `output.status` since a non-zero exit is often a
+/// meaningful result (e.g. "service not installed") rather than a hard error.
+fn run_command(mut command: std::process::Command) -> acp_lib::Result<std::process::Output> {
+    command
+        .output()
+        .map_err(|e| acp_lib::AcpError::storage(format!("failed to run {:?}: {}", command, e)))
+}
+
+#[cfg(target_os = "macos")]
+/// Optional LaunchAgent keys beyond the `RunAtLoad`/`KeepAlive` baseline,
+/// for callers that need throttling, scheduling, or process tuning.
+#[derive(Default)]
+pub struct LaunchAgentOptions {
+    /// `ThrottleInterval` - minimum seconds between relaunches after a crash.
+    pub throttle_interval: Option<i64>,
+    /// `StartInterval` - run every N seconds instead of only at login.
+    pub start_interval: Option<i64>,
+    /// `ProcessType` - e.g. `"Background"`, `"Interactive"`, `"Adaptive"`.
+    pub process_type: Option<String>,
+    /// `Nice` - scheduling priority.
+    pub nice: Option<i64>,
+    /// `WorkingDirectory` - directory the process is launched in.
+    pub working_directory: Option<String>,
+    /// `EnvironmentVariables` - defaults to [`normalize_service_env`] when unset.
+    pub env: Option<BTreeMap<String, String>>,
+}
+
+#[cfg(target_os = "macos")]
+/// macOS LaunchAgent. Runs as a user-level daemon after login with access
+/// to the user's Keychain.
+#[derive(Default)]
+pub struct LaunchAgentService {
+    pub options: LaunchAgentOptions,
+}
+
+#[cfg(target_os = "macos")]
+impl ServiceManager for LaunchAgentService {
+    /// Generates plist XML that:
+    /// - Runs at login (`RunAtLoad`)
+    /// - Keeps the service alive (`KeepAlive`)
+    /// - Logs stdout/stderr to `~/.acp/logs/`
+    fn unit_contents(&self, binary_path: &str) -> String {
+        let log_dir = self.log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        let mut entries = vec![
+            ("Label".to_string(), PlistValue::String("com.acp.server".to_string())),
+            ("Program".to_string(), PlistValue::String(binary_path.to_string())),
+            (
+                "ProgramArguments".to_string(),
+                PlistValue::Array(vec![
+                    PlistValue::String(binary_path.to_string()),
+                    PlistValue::String("run".to_string()),
+                ]),
+            ),
+            ("RunAtLoad".to_string(), PlistValue::Bool(true)),
+            ("KeepAlive".to_string(), PlistValue::Bool(true)),
+            (
+                "StandardOutPath".to_string(),
+                PlistValue::String(format!("{}/acp-server.log", log_dir_str)),
+            ),
+            (
+                "StandardErrorPath".to_string(),
+                PlistValue::String(format!("{}/acp-server.err", log_dir_str)),
+            ),
+        ];
+
+        if let Some(throttle_interval) = self.options.throttle_interval {
+            entries.push(("ThrottleInterval".to_string(), PlistValue::Integer(throttle_interval)));
+        }
+        if let Some(start_interval) = self.options.start_interval {
+            entries.push(("StartInterval".to_string(), PlistValue::Integer(start_interval)));
+        }
+        if let Some(process_type) = &self.options.process_type {
+            entries.push(("ProcessType".to_string(), PlistValue::String(process_type.clone())));
+        }
+        if let Some(nice) = self.options.nice {
+            entries.push(("Nice".to_string(), PlistValue::Integer(nice)));
+        }
+        if let Some(working_directory) = &self.options.working_directory {
+            entries.push((
+                "WorkingDirectory".to_string(),
+                PlistValue::String(working_directory.clone()),
+            ));
+        }
+
+        let env = self.options.env.clone().unwrap_or_else(normalize_service_env);
+        entries.push((
+            "EnvironmentVariables".to_string(),
+            PlistValue::Dict(
+                env.into_iter()
+                    .map(|(key, value)| (key, PlistValue::String(value)))
+                    .collect(),
+            ),
+        ));
+
+        render(PlistValue::Dict(entries))
+    }
+
+    /// Returns `~/Library/LaunchAgents/com.acp.server.plist`
+    fn install_path(&self) -> PathBuf {
+        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+        home_dir
+            .join("Library")
+            .join("LaunchAgents")
+            .join("com.acp.server.plist")
+    }
+
+    /// Returns `~/.acp/logs/`
+    fn log_dir(&self) -> PathBuf {
+        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+        home_dir.join(".acp").join("logs")
+    }
+
+    /// Writes the plist, then `launchctl bootstrap gui/$UID <plist>` -
+    /// falling back to the legacy `launchctl load -w` on macOS versions
+    /// that predate `bootstrap`.
+    fn install(&self, binary_path: &str) -> acp_lib::Result<()> {
+        let contents = self.unit_contents(binary_path);
+        write_unit_file(&self.install_path(), &contents, &self.log_dir())?;
+
+        let uid = launchctl_uid()?;
+        let bootstrap = run_command({
+            let mut cmd = std::process::Command::new("launchctl");
+            cmd.arg("bootstrap").arg(format!("gui/{}", uid)).arg(self.install_path());
+            cmd
+        })?;
+        if bootstrap.status.success() {
+            return Ok(());
+        }
+
+        let load = run_command({
+            let mut cmd = std::process::Command::new("launchctl");
+            cmd.args(["load", "-w"]).arg(self.install_path());
+            cmd
+        })?;
+        if load.status.success() {
+            return Ok(());
+        }
+
+        Err(acp_lib::AcpError::storage(format!(
+            "launchctl failed to load the service: {}",
+            String::from_utf8_lossy(&bootstrap.stderr)
+        )))
+    }
+
+    /// `launchctl bootout gui/$UID/com.acp.server` - falling back to the
+    /// legacy `launchctl unload` - then removes the plist.
+    fn uninstall(&self) -> acp_lib::Result<()> {
+        let uid = launchctl_uid()?;
+        let bootout = run_command({
+            let mut cmd = std::process::Command::new("launchctl");
+            cmd.arg("bootout").arg(format!("gui/{}/com.acp.server", uid));
+            cmd
+        })?;
+        if !bootout.status.success() {
+            let _ = run_command({
+                let mut cmd = std::process::Command::new("launchctl");
+                cmd.args(["unload", "-w"]).arg(self.install_path());
+                cmd
+            });
+        }
+
+        let path = self.install_path();
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Parses `launchctl print gui/$UID/com.acp.server` for `state` and
+    /// `last exit code`.
+    fn status(&self) -> acp_lib::Result<ServiceStatus> {
+        let uid = launchctl_uid()?;
+        let output = run_command({
+            let mut cmd = std::process::Command::new("launchctl");
+            cmd.arg("print").arg(format!("gui/{}/com.acp.server", uid));
+            cmd
+        })?;
+
+        if !output.status.success() {
+            return Ok(ServiceStatus { state: ServiceState::NotInstalled, last_exit_code: None });
+        }
+
+        Ok(parse_launchctl_print(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// `launchctl kickstart -k gui/$UID/com.acp.server`.
+    fn restart(&self) -> acp_lib::Result<()> {
+        let uid = launchctl_uid()?;
+        let output = run_command({
+            let mut cmd = std::process::Command::new("launchctl");
+            cmd.args(["kickstart", "-k"]).arg(format!("gui/{}/com.acp.server", uid));
+            cmd
+        })?;
+
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(acp_lib::AcpError::storage(format!(
+                "launchctl kickstart failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+/// Current console user's UID, via `id -u` - the domain target for
+/// `launchctl` (`gui/$UID`) isn't available as an env var.
+fn launchctl_uid() -> acp_lib::Result<String> {
+    let output = run_command({
+        let mut cmd = std::process::Command::new("id");
+        cmd.arg("-u");
+        cmd
+    })?;
+    if !output.status.success() {
+        return Err(acp_lib::AcpError::storage("failed to determine current user id".to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+/// Parses the `state = ...` and `last exit code = ...` lines out of
+/// `launchctl print` output.
+fn parse_launchctl_print(text: &str) -> ServiceStatus {
+    let mut state = ServiceState::Stopped;
+    let mut last_exit_code = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if let Some(value) = line.strip_prefix("state = ") {
+            state = if value.trim() == "running" {
+                ServiceState::Running
+            } else {
+                ServiceState::Stopped
+            };
+        } else if let Some(value) = line.strip_prefix("last exit code = ") {
+            last_exit_code = value.trim().parse::<i32>().ok();
+        }
+    }
+
+    ServiceStatus { state, last_exit_code }
+}
+
+#[cfg(target_os = "linux")]
+/// Optional overrides for the systemd user unit.
+#[derive(Default)]
+pub struct SystemdOptions {
+    /// `Environment=` lines - defaults to [`normalize_service_env`] when unset.
+    pub env: Option<BTreeMap<String, String>>,
+}
+
+#[cfg(target_os = "linux")]
+/// Linux systemd user unit. Runs under the user's systemd instance
+/// (`systemctl --user`), started on login via the default target.
+#[derive(Default)]
+pub struct SystemdUserService {
+    pub options: SystemdOptions,
+}
+
+#[cfg(target_os = "linux")]
+impl ServiceManager for SystemdUserService {
+    /// Generates a `.service` unit mapping the LaunchAgent semantics onto
+    /// systemd equivalents:
+    /// - `RunAtLoad` -> `WantedBy=default.target`
+    /// - `KeepAlive` -> `Restart=always`
+    /// - `ProgramArguments` -> `ExecStart=<binary_path> run`
+    /// - stdout/stderr appended to `<log_dir>/acp-server.log`
+    fn unit_contents(&self, binary_path: &str) -> String {
+        let log_dir = self.log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        let env = self.options.env.clone().unwrap_or_else(normalize_service_env);
+        let env_lines: String = env
+            .iter()
+            .map(|(key, value)| format!("Environment=\"{}={}\"\n", key, value))
+            .collect();
+
+        format!(
+            r#"[Unit]
+Description=ACP server
+
+[Service]
+ExecStart={} run
+Restart=always
+{}StandardOutput=append:{}/acp-server.log
+StandardError=append:{}/acp-server.log
+
+[Install]
+WantedBy=default.target
+"#,
+            binary_path, env_lines, log_dir_str, log_dir_str
+        )
+    }
+
+    /// Returns `$XDG_CONFIG_HOME/systemd/user/acp-server.service`, falling
+    /// back to `~/.config/systemd/user/acp-server.service`.
+    fn install_path(&self) -> PathBuf {
+        config_home()
+            .join("systemd")
+            .join("user")
+            .join("acp-server.service")
+    }
+
+    /// Returns `~/.acp/logs/`
+    fn log_dir(&self) -> PathBuf {
+        let home_dir = dirs::home_dir().expect("Could not determine home directory");
+        home_dir.join(".acp").join("logs")
+    }
+
+    /// Writes the unit file, then `systemctl --user enable --now`.
+    fn install(&self, binary_path: &str) -> acp_lib::Result<()> {
+        let contents = self.unit_contents(binary_path);
+        write_unit_file(&self.install_path(), &contents, &self.log_dir())?;
+
+        run_systemctl(&["daemon-reload"])?;
+        let output = run_systemctl(&["enable", "--now", SYSTEMD_UNIT])?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(acp_lib::AcpError::storage(format!(
+                "systemctl enable --now failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+
+    /// `systemctl --user disable --now`, then removes the unit file.
+    fn uninstall(&self) -> acp_lib::Result<()> {
+        let _ = run_systemctl(&["disable", "--now", SYSTEMD_UNIT]);
+
+        let path = self.install_path();
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+        }
+        run_systemctl(&["daemon-reload"])?;
+        Ok(())
+    }
+
+    /// `systemctl --user is-active` for running/stopped, plus
+    /// `ExecMainStatus` for the last exit code.
+    fn status(&self) -> acp_lib::Result<ServiceStatus> {
+        let output = run_systemctl(&["is-active", SYSTEMD_UNIT])?;
+        let state = match String::from_utf8_lossy(&output.stdout).trim() {
+            "active" => ServiceState::Running,
+            "inactive" | "failed" | "activating" | "deactivating" => ServiceState::Stopped,
+            _ => ServiceState::NotInstalled,
+        };
+
+        let exit_output = run_systemctl(&["show", SYSTEMD_UNIT, "--property=ExecMainStatus", "--value"])?;
+        let last_exit_code = String::from_utf8_lossy(&exit_output.stdout).trim().parse::<i32>().ok();
+
+        Ok(ServiceStatus { state, last_exit_code })
+    }
+
+    /// `systemctl --user restart`.
+    fn restart(&self) -> acp_lib::Result<()> {
+        let output = run_systemctl(&["restart", SYSTEMD_UNIT])?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(acp_lib::AcpError::storage(format!(
+                "systemctl restart failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+const SYSTEMD_UNIT: &str = "acp-server.service";
+
+#[cfg(target_os = "linux")]
+fn run_systemctl(args: &[&str]) -> acp_lib::Result<std::process::Output> {
+    let mut cmd = std::process::Command::new("systemctl");
+    cmd.arg("--user").args(args);
+    run_command(cmd)
+}
+
+#[cfg(target_os = "linux")]
+/// Resolves `$XDG_CONFIG_HOME`, falling back to `~/.config`.
+fn config_home() -> PathBuf {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            dirs::home_dir()
+                .expect("Could not determine home directory")
+                .join(".config")
+        })
+}
+
+#[cfg(target_os = "windows")]
+/// Windows background service, registered as a per-user logon task via
+/// Task Scheduler (no admin rights required, unlike an SCM service).
+pub struct WindowsTaskService;
+
+#[cfg(target_os = "windows")]
+impl ServiceManager for WindowsTaskService {
+    /// Generates a Task Scheduler XML task definition that runs at logon
+    /// and restarts on failure, mirroring `RunAtLoad`/`KeepAlive`.
+    fn unit_contents(&self, binary_path: &str) -> String {
+        let log_dir = self.log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-16"?>
+<Task version="1.2" xmlns="http://schemas.microsoft.com/windows/2004/02/mit/task">
+  <Triggers>
+    <LogonTrigger>
+      <Enabled>true</Enabled>
+    </LogonTrigger>
+  </Triggers>
+  <Settings>
+    <RestartOnFailure>
+      <Interval>PT1M</Interval>
+      <Count>999</Count>
+    </RestartOnFailure>
+    <MultipleInstancesPolicy>IgnoreNew</MultipleInstancesPolicy>
+  </Settings>
+  <Actions>
+    <Exec>
+      <Command>{}</Command>
+      <Arguments>run</Arguments>
+      <WorkingDirectory>{}</WorkingDirectory>
+    </Exec>
+  </Actions>
+</Task>
+"#,
+            binary_path, log_dir_str
+        )
+    }
+
+    /// Returns `%APPDATA%\acp\acp-server-task.xml`
+    fn install_path(&self) -> PathBuf {
+        dirs::config_dir()
+            .expect("Could not determine config directory")
+            .join("acp")
+            .join("acp-server-task.xml")
+    }
+
+    /// Returns `%APPDATA%\acp\logs`
+    fn log_dir(&self) -> PathBuf {
+        dirs::config_dir()
+            .expect("Could not determine config directory")
+            .join("acp")
+            .join("logs")
+    }
+
+    /// Writes the task XML, then `schtasks /Create /TN ACPServer /XML ... /F`.
+    fn install(&self, binary_path: &str) -> acp_lib::Result<()> {
+        let contents = self.unit_contents(binary_path);
+        write_unit_file(&self.install_path(), &contents, &self.log_dir())?;
+
+        let output = run_command({
+            let mut cmd = std::process::Command::new("schtasks");
+            cmd.args(["/Create", "/TN", WINDOWS_TASK_NAME, "/XML"])
+                .arg(self.install_path())
+                .arg("/F");
+            cmd
+        })?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(acp_lib::AcpError::storage(format!(
+                "schtasks /Create failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+
+    /// `schtasks /Delete /TN ACPServer /F`, then removes the task XML.
+    fn uninstall(&self) -> acp_lib::Result<()> {
+        let _ = run_command({
+            let mut cmd = std::process::Command::new("schtasks");
+            cmd.args(["/Delete", "/TN", WINDOWS_TASK_NAME, "/F"]);
+            cmd
+        });
+
+        let path = self.install_path();
+        if path.exists() {
+            std::fs::remove_file(&path).map_err(|e| acp_lib::AcpError::storage(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Parses `schtasks /Query /TN ACPServer` for the task's run state.
+    fn status(&self) -> acp_lib::Result<ServiceStatus> {
+        let output = run_command({
+            let mut cmd = std::process::Command::new("schtasks");
+            cmd.args(["/Query", "/TN", WINDOWS_TASK_NAME, "/FO", "LIST"]);
+            cmd
+        })?;
+
+        if !output.status.success() {
+            return Ok(ServiceStatus { state: ServiceState::NotInstalled, last_exit_code: None });
+        }
+
+        let state = if String::from_utf8_lossy(&output.stdout).contains("Running") {
+            ServiceState::Running
+        } else {
+            ServiceState::Stopped
+        };
+        Ok(ServiceStatus { state, last_exit_code: None })
+    }
+
+    /// `schtasks /Run /TN ACPServer`.
+    fn restart(&self) -> acp_lib::Result<()> {
+        let output = run_command({
+            let mut cmd = std::process::Command::new("schtasks");
+            cmd.args(["/Run", "/TN", WINDOWS_TASK_NAME]);
+            cmd
+        })?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            Err(acp_lib::AcpError::storage(format!(
+                "schtasks /Run failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )))
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+const WINDOWS_TASK_NAME: &str = "ACPServer";
+
+#[cfg(target_os = "macos")]
+pub type PlatformService = LaunchAgentService;
+
+#[cfg(target_os = "linux")]
+pub type PlatformService = SystemdUserService;
+
+#[cfg(target_os = "windows")]
+pub type PlatformService = WindowsTaskService;
+
+#[cfg(target_os = "macos")]
+fn platform_service() -> PlatformService {
+    LaunchAgentService::default()
+}
+
+#[cfg(target_os = "linux")]
+fn platform_service() -> PlatformService {
+    SystemdUserService::default()
+}
+
+#[cfg(target_os = "windows")]
+fn platform_service() -> PlatformService {
+    WindowsTaskService
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+/// Generate the service unit file contents for acp-server on this platform.
+///
+/// # Arguments
+/// * `binary_path` - Absolute path to the acp-server binary
+pub fn generate_plist(binary_path: &str) -> String {
+    platform_service().unit_contents(binary_path)
+}
+
+/// Locate the `acp-server` executable so callers don't have to type an
+/// absolute path by hand.
+///
+/// Prefers the path of the currently running executable
+/// (`std::env::current_exe`), since `install` is almost always invoked as
+/// `acp-server install`. Falls back to a `PATH` search - trying platform
+/// executable extensions (`.exe` on Windows) and skipping entries that
+/// exist but aren't executable - for cases like running from a shell
+/// function or a symlinked dev build. Returns a canonicalized absolute
+/// path, or an error if nothing executable was found either way.
+pub fn resolve_binary_path() -> acp_lib::Result<PathBuf> {
+    if let Ok(exe) = std::env::current_exe() {
+        if let Ok(canonical) = exe.canonicalize() {
+            return Ok(canonical);
+        }
+    }
+
+    let path_var = std::env::var_os("PATH")
+        .ok_or_else(|| acp_lib::AcpError::storage("PATH is not set".to_string()))?;
+
+    for dir in std::env::split_paths(&path_var) {
+        for name in binary_candidate_names() {
+            let candidate = dir.join(name);
+            if is_executable_file(&candidate) {
+                return candidate
+                    .canonicalize()
+                    .map_err(|e| acp_lib::AcpError::storage(e.to_string()));
+            }
+        }
+    }
+
+    Err(acp_lib::AcpError::storage(
+        "could not locate the acp-server executable on PATH".to_string(),
+    ))
+}
+
+fn binary_candidate_names() -> &'static [&'static str] {
+    if cfg!(target_os = "windows") {
+        &["acp-server.exe", "acp-server"]
+    } else {
+        &["acp-server"]
+    }
+}
+
+#[cfg(unix)]
+fn is_executable_file(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable_file(path: &std::path::Path) -> bool {
+    std::fs::metadata(path).map(|meta| meta.is_file()).unwrap_or(false)
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+/// Generate the service unit file contents for acp-server, resolving the
+/// binary's location automatically instead of requiring the caller to
+/// supply a path.
+pub fn generate_plist_for_current_binary() -> acp_lib::Result<String> {
+    let binary_path = resolve_binary_path()?;
+    let binary_path = binary_path
+        .to_str()
+        .ok_or_else(|| acp_lib::AcpError::storage("binary path is not valid UTF-8".to_string()))?;
+    Ok(generate_plist(binary_path))
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+/// Get the default unit file install path for the current platform.
+pub fn get_plist_path() -> PathBuf {
+    platform_service().install_path()
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux", target_os = "windows"))]
+/// Get the log directory path for the current platform.
+pub fn get_log_dir() -> PathBuf {
+    platform_service().log_dir()
+}
+
+#[cfg(test)]
+mod env_tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path_list_drops_empty_and_duplicate_entries() {
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let list = format!("/a{sep}{sep}/b{sep}/a{sep}/c", sep = separator);
+        let normalized = normalize_path_list(&list, &[]);
+
+        let entries: Vec<_> = std::env::split_paths(&normalized)
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["/a", "/b", "/c"]);
+    }
+
+    #[test]
+    fn test_normalize_path_list_appends_missing_defaults() {
+        let normalized = normalize_path_list("/custom/bin", &["/usr/local/bin", "/usr/bin"]);
+        let entries: Vec<_> = std::env::split_paths(&normalized)
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["/custom/bin", "/usr/local/bin", "/usr/bin"]);
+    }
+
+    #[test]
+    fn test_normalize_path_list_does_not_duplicate_existing_defaults() {
+        let normalized = normalize_path_list("/usr/local/bin:/custom", &["/usr/local/bin", "/usr/bin"]);
+        let entries: Vec<_> = std::env::split_paths(&normalized)
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        assert_eq!(entries, vec!["/usr/local/bin", "/custom", "/usr/bin"]);
+    }
+
+    #[test]
+    fn test_normalize_service_env_guarantees_baseline_path_entries() {
+        let env = normalize_service_env();
+        let path = env.get("PATH").expect("PATH should be present");
+        assert!(path.contains("/usr/local/bin"));
+        assert!(path.contains("/usr/bin"));
+    }
+
+    #[test]
+    fn test_resolve_binary_path_finds_current_exe() {
+        let resolved = resolve_binary_path().expect("should resolve a path in the test binary");
+        assert!(resolved.is_absolute());
+    }
+
+    #[test]
+    fn test_is_executable_file_rejects_missing_path() {
+        assert!(!is_executable_file(std::path::Path::new(
+            "/nonexistent/path/to/acp-server"
+        )));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_is_executable_file_checks_mode_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!(
+            "acp-service-test-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("not-executable");
+        std::fs::write(&file, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(!is_executable_file(&file));
+
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(is_executable_file(&file));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "macos")]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_plist_contains_required_keys() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let plist = generate_plist(binary_path);
+
+        // Verify XML structure
+        assert!(plist.contains(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(plist.contains(r#"<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN""#));
+        assert!(plist.contains(r#"<plist version="1.0">"#));
+
+        // Verify required keys
+        assert!(plist.contains("<key>Label</key>"));
+        assert!(plist.contains("<string>com.acp.server</string>"));
+
+        assert!(plist.contains("<key>Program</key>"));
+        assert!(plist.contains(&format!("<string>{}</string>", binary_path)));
+
+        assert!(plist.contains("<key>ProgramArguments</key>"));
+        assert!(plist.contains("<array>"));
+        assert!(plist.contains("<string>run</string>"));
+
+        assert!(plist.contains("<key>RunAtLoad</key>"));
+        assert!(plist.contains("<true/>"));
+
+        assert!(plist.contains("<key>KeepAlive</key>"));
+
+        assert!(plist.contains("<key>StandardOutPath</key>"));
+        assert!(plist.contains("<key>StandardErrorPath</key>"));
+    }
+
+    #[test]
+    fn test_generate_plist_uses_correct_log_paths() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let plist = generate_plist(binary_path);
+        let log_dir = get_log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        // Verify log paths contain the log directory
+        assert!(plist.contains(&format!("<string>{}/acp-server.log</string>", log_dir_str)));
+        assert!(plist.contains(&format!("<string>{}/acp-server.err</string>", log_dir_str)));
+    }
+
+    #[test]
+    fn test_generate_plist_valid_xml_structure() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let plist = generate_plist(binary_path);
+
+        // Verify it starts and ends correctly
+        assert!(plist.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        assert!(plist.trim().ends_with("</plist>"));
+
+        // Verify dict structure
+        assert!(plist.contains("<dict>"));
+        assert!(plist.contains("</dict>"));
+    }
+
+    #[test]
+    fn test_get_plist_path_returns_correct_location() {
+        let path = get_plist_path();
+        let path_str = path.to_string_lossy();
+
+        // Should be in ~/Library/LaunchAgents/
+        assert!(path_str.contains("Library/LaunchAgents"));
+        assert!(path_str.ends_with("com.acp.server.plist"));
+    }
+
+    #[test]
+    fn test_get_log_dir_returns_acp_logs() {
+        let log_dir = get_log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        // Should be ~/.acp/logs/
+        assert!(log_dir_str.contains(".acp"));
+        assert!(log_dir_str.ends_with("logs"));
+    }
+
+    #[test]
+    fn test_generate_plist_escapes_special_characters() {
+        // Test with a path containing special characters that need XML escaping
+        let binary_path = "/path/with spaces/acp-server";
+        let plist = generate_plist(binary_path);
+
+        // The path should appear in the plist (spaces are allowed in XML strings)
+        assert!(plist.contains("/path/with spaces/acp-server"));
+    }
+
+    #[test]
+    fn test_generate_plist_program_arguments_order() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let plist = generate_plist(binary_path);
+
+        // Find the ProgramArguments array
+        let args_start = plist.find("<key>ProgramArguments</key>").expect("ProgramArguments key not found");
+        let args_section = &plist[args_start..];
+
+        // Find the array section
+        let array_start = args_section.find("<array>").expect("array not found");
+        let array_end = args_section.find("</array>").expect("array end not found");
+        let array_content = &args_section[array_start..array_end];
+
+        // First argument should be the binary path
+        let first_arg_pos = array_content.find(&format!("<string>{}</string>", binary_path))
+            .expect("binary path not found in array");
+
+        // Second argument should be "run"
+        let run_arg_pos = array_content.find("<string>run</string>")
+            .expect("run argument not found in array");
+
+        // Binary path should come before "run"
+        assert!(first_arg_pos < run_arg_pos, "Binary path should be first argument");
+    }
+
+    #[test]
+    fn test_generate_plist_escapes_ampersand_in_binary_path() {
+        let binary_path = "/Applications/Foo & Bar.app/acp-server";
+        let plist = generate_plist(binary_path);
+
+        assert!(plist.contains("Foo &amp; Bar.app"));
+        assert!(!plist.contains("Foo & Bar.app"));
+    }
+
+    #[test]
+    fn test_launch_agent_includes_optional_keys_when_set() {
+        let service = LaunchAgentService {
+            options: LaunchAgentOptions {
+                throttle_interval: Some(10),
+                start_interval: Some(300),
+                process_type: Some("Background".to_string()),
+                nice: Some(5),
+                working_directory: Some("/opt/acp".to_string()),
+            },
+        };
+        let plist = service.unit_contents("/usr/local/bin/acp-server");
+
+        assert!(plist.contains("<key>ThrottleInterval</key>"));
+        assert!(plist.contains("<integer>10</integer>"));
+        assert!(plist.contains("<key>StartInterval</key>"));
+        assert!(plist.contains("<integer>300</integer>"));
+        assert!(plist.contains("<key>ProcessType</key>"));
+        assert!(plist.contains("<string>Background</string>"));
+        assert!(plist.contains("<key>Nice</key>"));
+        assert!(plist.contains("<integer>5</integer>"));
+        assert!(plist.contains("<key>WorkingDirectory</key>"));
+        assert!(plist.contains("<string>/opt/acp</string>"));
+    }
+
+    #[test]
+    fn test_launch_agent_omits_optional_keys_by_default() {
+        let plist = LaunchAgentService::default().unit_contents("/usr/local/bin/acp-server");
+
+        assert!(!plist.contains("ThrottleInterval"));
+        assert!(!plist.contains("StartInterval"));
+        assert!(!plist.contains("ProcessType"));
+        assert!(!plist.contains("<key>Nice</key>"));
+        assert!(!plist.contains("WorkingDirectory"));
+    }
+
+    #[test]
+    fn test_launch_agent_injects_sanitized_environment() {
+        let mut env = std::collections::BTreeMap::new();
+        env.insert("PATH".to_string(), "/usr/local/bin:/usr/bin".to_string());
+        env.insert("XDG_DATA_HOME".to_string(), "/home/user/.local/share".to_string());
+
+        let service = LaunchAgentService {
+            options: LaunchAgentOptions {
+                env: Some(env),
+                ..Default::default()
+            },
+        };
+        let plist = service.unit_contents("/usr/local/bin/acp-server");
+
+        assert!(plist.contains("<key>EnvironmentVariables</key>"));
+        assert!(plist.contains("<key>PATH</key>"));
+        assert!(plist.contains("<string>/usr/local/bin:/usr/bin</string>"));
+        assert!(plist.contains("<key>XDG_DATA_HOME</key>"));
+    }
+
+    #[test]
+    fn test_launch_agent_defaults_environment_to_normalize_service_env() {
+        let plist = LaunchAgentService::default().unit_contents("/usr/local/bin/acp-server");
+        assert!(plist.contains("<key>EnvironmentVariables</key>"));
+        assert!(plist.contains("<key>PATH</key>"));
+    }
+
+    #[test]
+    fn test_parse_launchctl_print_running() {
+        let text = "com.acp.server = {\n\tstate = running\n\tlast exit code = 0\n}\n";
+        let status = parse_launchctl_print(text);
+        assert_eq!(status.state, ServiceState::Running);
+        assert_eq!(status.last_exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_parse_launchctl_print_stopped_with_nonzero_exit() {
+        let text = "com.acp.server = {\n\tstate = not running\n\tlast exit code = 1\n}\n";
+        let status = parse_launchctl_print(text);
+        assert_eq!(status.state, ServiceState::Stopped);
+        assert_eq!(status.last_exit_code, Some(1));
+    }
+
+    #[test]
+    fn test_parse_launchctl_print_missing_exit_code() {
+        let text = "com.acp.server = {\n\tstate = running\n}\n";
+        let status = parse_launchctl_print(text);
+        assert_eq!(status.state, ServiceState::Running);
+        assert_eq!(status.last_exit_code, None);
+    }
+}
+
+#[cfg(test)]
+#[cfg(target_os = "linux")]
+mod linux_tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_unit_maps_launchagent_semantics() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let unit = generate_plist(binary_path);
+
+        assert!(unit.contains("[Unit]"));
+        assert!(unit.contains("[Service]"));
+        assert!(unit.contains("[Install]"));
+
+        // RunAtLoad -> WantedBy=default.target
+        assert!(unit.contains("WantedBy=default.target"));
+        // KeepAlive -> Restart=always
+        assert!(unit.contains("Restart=always"));
+        // ProgramArguments -> ExecStart=<binary> run
+        assert!(unit.contains(&format!("ExecStart={} run", binary_path)));
+    }
+
+    #[test]
+    fn test_generate_unit_logs_to_log_dir() {
+        let binary_path = "/usr/local/bin/acp-server";
+        let unit = generate_plist(binary_path);
+        let log_dir = get_log_dir();
+        let log_dir_str = log_dir.to_string_lossy();
+
+        assert!(unit.contains(&format!("StandardOutput=append:{}/acp-server.log", log_dir_str)));
+    }
+
+    #[test]
+    fn test_install_path_respects_xdg_config_home() {
+        std::env::set_var("XDG_CONFIG_HOME", "/tmp/acp-test-xdg-config");
+        let path = SystemdUserService::default().install_path();
+        std::env::remove_var("XDG_CONFIG_HOME");
+
+        assert_eq!(
+            path,
+            PathBuf::from("/tmp/acp-test-xdg-config/systemd/user/acp-server.service")
+        );
+    }
+
+    #[test]
+    fn test_install_path_falls_back_to_dot_config_without_xdg() {
+        std::env::remove_var("XDG_CONFIG_HOME");
+        let path = SystemdUserService::default().install_path();
+        assert!(path.to_string_lossy().ends_with(".config/systemd/user/acp-server.service"));
+    }
+
+    #[test]
+    fn test_systemd_unit_injects_environment_lines() {
+        let mut env = std::collections::BTreeMap::new();
+        env.insert("PATH".to_string(), "/usr/local/bin:/usr/bin".to_string());
+
+        let service = SystemdUserService {
+            options: SystemdOptions { env: Some(env) },
+        };
+        let unit = service.unit_contents("/usr/local/bin/acp-server");
+
+        assert!(unit.contains(r#"Environment="PATH=/usr/local/bin:/usr/bin""#));
+    }
+
+    #[test]
+    fn test_systemd_unit_defaults_environment_to_normalize_service_env() {
+        let unit = SystemdUserService::default().unit_contents("/usr/local/bin/acp-server");
+        assert!(unit.contains("Environment=\"PATH="));
+    }
+}
@@ -0,0 +1,44 @@
+//! ACME account command implementation
+
+use crate::client::ApiClient;
+use anyhow::Result;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct AcmeAccountInfo {
+    pub directory: String,
+    pub email: String,
+    pub sans: Vec<String>,
+    pub expires_at: String,
+    pub needs_renewal: bool,
+}
+
+/// List the server's ACME account and certificate status
+pub async fn list(server_url: &str) -> Result<()> {
+    let client = ApiClient::new(server_url);
+    let accounts: Vec<AcmeAccountInfo> = client.get("/acme/accounts").await?;
+
+    if accounts.is_empty() {
+        println!("No ACME account configured. Run `acp init --acme-directory <url> --acme-email <addr>`.");
+        return Ok(());
+    }
+
+    for account in accounts {
+        println!("Directory:   {}", account.directory);
+        println!("Email:       {}", account.email);
+        println!("SANs:        {}", account.sans.join(", "));
+        println!("Expires:     {}", account.expires_at);
+        println!("Renewal due: {}", account.needs_renewal);
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Force an immediate renewal check against the ACME server
+pub async fn renew(server_url: &str) -> Result<()> {
+    let client = ApiClient::new(server_url);
+    client.post_auth_no_body("/acme/renew").await?;
+    println!("Renewal requested.");
+    Ok(())
+}
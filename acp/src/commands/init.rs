@@ -5,7 +5,16 @@ use crate::client::ApiClient;
 use anyhow::Result;
 use serde_json::json;
 
-pub async fn run(server_url: &str, ca_path: Option<&str>, management_sans: Option<&str>) -> Result<()> {
+pub async fn run(
+    server_url: &str,
+    ca_path: Option<&str>,
+    management_sans: Option<&str>,
+    acme_directory: Option<&str>,
+    acme_email: Option<&str>,
+    argon2_memory: Option<u32>,
+    argon2_iterations: Option<u32>,
+    argon2_parallelism: Option<u32>,
+) -> Result<()> {
     println!("Initializing ACP server...");
     println!();
 
@@ -23,6 +32,13 @@ pub async fn run(server_url: &str, ca_path: Option<&str>, management_sans: Optio
             .collect::<Vec<String>>()
     });
 
+    if acme_directory.is_some() && management_sans_vec.is_none() {
+        anyhow::bail!("--acme-directory requires --management-sans to be set");
+    }
+    if acme_directory.is_some() != acme_email.is_some() {
+        anyhow::bail!("--acme-directory and --acme-email must be used together");
+    }
+
     // Build request body
     let mut body = json!({});
     if let Some(path) = ca_path {
@@ -31,19 +47,42 @@ pub async fn run(server_url: &str, ca_path: Option<&str>, management_sans: Optio
     if let Some(sans) = management_sans_vec {
         body.as_object_mut().unwrap().insert("management_sans".to_string(), json!(sans));
     }
+    if let Some(directory) = acme_directory {
+        body.as_object_mut().unwrap().insert("acme_directory".to_string(), json!(directory));
+    }
+    if let Some(email) = acme_email {
+        body.as_object_mut().unwrap().insert("acme_email".to_string(), json!(email));
+    }
+    if let Some(memory) = argon2_memory {
+        body.as_object_mut().unwrap().insert("argon2_memory_kib".to_string(), json!(memory));
+    }
+    if let Some(iterations) = argon2_iterations {
+        body.as_object_mut().unwrap().insert("argon2_iterations".to_string(), json!(iterations));
+    }
+    if let Some(parallelism) = argon2_parallelism {
+        body.as_object_mut().unwrap().insert("argon2_parallelism".to_string(), json!(parallelism));
+    }
 
     let response: crate::client::InitResponse = client.post_auth("/init", &password_hash, body).await?;
 
     println!();
     println!("ACP initialized successfully!");
-    println!("CA certificate saved to: {}", response.ca_path);
+    if acme_directory.is_some() {
+        println!("Certificate saved to: {}", response.ca_path);
+    } else {
+        println!("CA certificate saved to: {}", response.ca_path);
+    }
     println!();
     println!("Next steps:");
     println!("  1. Install plugins: acp install <plugin>");
     println!("  2. Configure credentials: acp set <plugin>:<key>");
     println!("  3. Create agent tokens: acp token create <name>");
     println!();
-    println!("Clients should be configured to trust the CA cert at the path above.");
+    if acme_directory.is_some() {
+        println!("The certificate above is publicly trusted; no CA import is required.");
+    } else {
+        println!("Clients should be configured to trust the CA cert at the path above.");
+    }
 
     Ok(())
 }
@@ -0,0 +1,56 @@
+//! Agent token command implementation
+
+use crate::client::ApiClient;
+use anyhow::Result;
+use serde_json::json;
+
+/// Create a new agent token
+///
+/// `token_type` selects between the default symmetric secret and a
+/// `v4.public` PASETO token signed with a freshly generated Ed25519
+/// keypair; see `acp token create --help`.
+pub async fn create(
+    server_url: &str,
+    name: &str,
+    token_type: &str,
+    expires_at: Option<&str>,
+    allowed_plugins: &[String],
+) -> Result<()> {
+    let client = ApiClient::new(server_url);
+
+    let mut body = json!({
+        "name": name,
+        "token_type": token_type,
+    });
+    if let Some(exp) = expires_at {
+        body.as_object_mut().unwrap().insert("expires_at".to_string(), json!(exp));
+    }
+    if !allowed_plugins.is_empty() {
+        body.as_object_mut()
+            .unwrap()
+            .insert("allowed_plugins".to_string(), json!(allowed_plugins));
+    }
+
+    let response: crate::client::TokenResponse = client.post_auth("/tokens/create", "", body).await?;
+
+    println!("Token created: {}", response.name);
+    if let Some(token) = response.token {
+        println!("{}", token);
+        println!();
+        println!("This value is only shown once - store it securely.");
+    }
+
+    Ok(())
+}
+
+/// Verify a PASETO agent token against the server's stored public key
+pub async fn verify(server_url: &str, token: &str) -> Result<()> {
+    let client = ApiClient::new(server_url);
+    let body = json!({ "token": token });
+    let claims: serde_json::Value = client.post_auth("/tokens/verify", "", body).await?;
+
+    println!("Token is valid.");
+    println!("{}", serde_json::to_string_pretty(&claims)?);
+
+    Ok(())
+}
@@ -5,5 +5,6 @@ pub mod credentials;
 pub mod init;
 pub mod new_management_cert;
 pub mod plugins;
+pub mod renew_management_cert;
 pub mod status;
 pub mod tokens;
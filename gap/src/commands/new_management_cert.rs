@@ -1,30 +1,457 @@
 //! New management certificate command implementation
 
 use crate::auth::{hash_password, read_password};
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::Serialize;
 use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use std::str::FromStr;
+use std::time::Duration;
 
-pub async fn run(server_url: &str, sans: &str) -> Result<()> {
+/// `--acme` mode: provision a CA-signed certificate via ACME v2 instead of
+/// rotating to a server-generated self-signed one.
+pub struct AcmeOptions {
+    pub directory_url: String,
+    pub email: String,
+    /// The issuer domain CAA records must authorize (e.g. `letsencrypt.org`),
+    /// checked against every `DNS:` SAN before issuance when `--require-caa`
+    /// or a plain CAA pre-flight warning is in effect.
+    pub ca_domain: String,
+}
+
+/// A single Subject Alternative Name, as recognized from the `DNS:`, `IP:`,
+/// `email:`, and `URI:` prefixed forms accepted by `--sans`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum SanEntry {
+    Dns(String),
+    IpV4(Ipv4Addr),
+    IpV6(Ipv6Addr),
+    Email(String),
+    Uri(String),
+}
+
+impl FromStr for SanEntry {
+    type Err = anyhow::Error;
+
+    fn from_str(entry: &str) -> Result<Self> {
+        let entry = entry.trim();
+        if let Some(rest) = entry.strip_prefix("DNS:") {
+            return Ok(SanEntry::Dns(rest.to_string()));
+        }
+        if let Some(rest) = entry.strip_prefix("IP:") {
+            return match parse_ip_literal(rest)? {
+                IpAddr::V4(addr) => Ok(SanEntry::IpV4(addr)),
+                IpAddr::V6(addr) => Ok(SanEntry::IpV6(addr)),
+            };
+        }
+        if let Some(rest) = entry.strip_prefix("email:") {
+            return Ok(SanEntry::Email(rest.to_string()));
+        }
+        if let Some(rest) = entry.strip_prefix("URI:") {
+            return Ok(SanEntry::Uri(rest.to_string()));
+        }
+        anyhow::bail!(
+            "invalid SAN entry {:?}: expected a DNS:, IP:, email:, or URI: prefix",
+            entry
+        )
+    }
+}
+
+/// Parse an IP literal following the `IP:` prefix, accepting the bracketed
+/// IPv6 form (`[::1]`) the same way host:port parsing does for socket
+/// addresses.
+fn parse_ip_literal(literal: &str) -> Result<IpAddr> {
+    let literal = literal
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .unwrap_or(literal);
+    IpAddr::from_str(literal).with_context(|| format!("invalid IP literal {:?}", literal))
+}
+
+/// Parse a comma-separated `--sans` string into validated entries, rejecting
+/// anything that doesn't match a recognized prefix before any network call.
+pub(crate) fn parse_sans(sans: &str) -> Result<Vec<SanEntry>> {
+    sans.split(',').map(|entry| entry.parse()).collect()
+}
+
+/// The `DNS:` entries among `sans`, as the plain names ACME certificate
+/// identifiers and CSRs require.
+pub(crate) fn dns_names(sans: &[SanEntry]) -> Vec<String> {
+    sans.iter()
+        .filter_map(|san| match san {
+            SanEntry::Dns(name) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A host pattern from one `ACP_AUTH_TOKENS` entry: either an exact IP
+/// literal or a domain that also matches its subdomains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum HostPattern {
+    Ip(IpAddr),
+    Domain(String),
+}
+
+/// One `token@host[:port]` entry from `ACP_AUTH_TOKENS`.
+#[derive(Debug, Clone)]
+struct AuthTokenEntry {
+    token: String,
+    host: HostPattern,
+    port: Option<u16>,
+}
+
+impl AuthTokenEntry {
+    /// Whether this entry applies to `host`/`port`: an IP pattern must match
+    /// exactly, a domain pattern matches itself and any subdomain, and a
+    /// port on the entry (if any) must match exactly.
+    fn matches(&self, host: &str, port: Option<u16>) -> bool {
+        let host_matches = match &self.host {
+            HostPattern::Ip(ip) => host.parse::<IpAddr>().map(|parsed| parsed == *ip).unwrap_or(false),
+            HostPattern::Domain(domain) => {
+                let host = host.trim_end_matches('.').to_lowercase();
+                host == *domain || host.ends_with(&format!(".{}", domain))
+            }
+        };
+        host_matches && self.port.map_or(true, |entry_port| Some(entry_port) == port)
+    }
+}
+
+/// Parse a bare `host`, `host:port`, or bracketed `[ipv6]`/`[ipv6]:port`
+/// literal (no scheme) into its host and optional port.
+fn parse_host_port_literal(literal: &str) -> Result<(String, Option<u16>)> {
+    if let Some(rest) = literal.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').context("unterminated IPv6 literal")?;
+        let port = rest
+            .strip_prefix(':')
+            .map(|p| p.parse::<u16>().context("invalid port"))
+            .transpose()?;
+        return Ok((host.to_string(), port));
+    }
+
+    match literal.rsplit_once(':') {
+        Some((host, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => {
+            Ok((host.to_string(), Some(port.parse().context("invalid port")?)))
+        }
+        _ => Ok((literal.to_string(), None)),
+    }
+}
+
+/// Parse the scheme, host, and optional port out of a `server_url` like
+/// `https://example.com:8443/` or a bare `example.com`.
+fn parse_authority(server_url: &str) -> Result<(String, Option<u16>)> {
+    let without_scheme = server_url.split("://").nth(1).unwrap_or(server_url);
+    let authority = without_scheme.split(['/', '?', '#']).next().unwrap_or(without_scheme);
+    parse_host_port_literal(authority)
+}
+
+/// Parse one `token@host[:port]` entry, rejecting the host/port part with
+/// the same rules as [`parse_authority`]. A leading dot on a domain host
+/// (`.example.com`) is equivalent to no dot.
+fn parse_auth_token_entry(entry: &str) -> Result<AuthTokenEntry> {
+    let (token, host_part) = entry
+        .rsplit_once('@')
+        .with_context(|| format!("auth token entry {:?} is missing '@host'", entry))?;
+    let (host, port) = parse_host_port_literal(host_part)?;
+
+    let host = if let Ok(ip) = host.parse::<IpAddr>() {
+        HostPattern::Ip(ip)
+    } else {
+        HostPattern::Domain(host.trim_start_matches('.').to_lowercase())
+    };
+
+    Ok(AuthTokenEntry {
+        token: token.to_string(),
+        host,
+        port,
+    })
+}
+
+/// Look up a pre-hashed token for `server_url` from the `ACP_AUTH_TOKENS`
+/// environment variable, so scripted rotation across a fleet doesn't block
+/// on an interactive password prompt. Entries are whitespace-separated
+/// `token@host[:port]` pairs: `token@example.com` applies to `example.com`
+/// and its subdomains, `token@1.1.1.1` matches only that exact IP, and
+/// `token@[::1]:8443` matches that IPv6 host and port.
+pub(crate) fn lookup_auth_token(server_url: &str) -> Result<Option<String>> {
+    let Ok(raw) = std::env::var("ACP_AUTH_TOKENS") else {
+        return Ok(None);
+    };
+
+    let (host, port) = parse_authority(server_url)?;
+    for entry in raw.split_whitespace() {
+        let entry = parse_auth_token_entry(entry)?;
+        if entry.matches(&host, port) {
+            return Ok(Some(entry.token));
+        }
+    }
+
+    Ok(None)
+}
+
+/// A parsed CAA (RFC 8659) resource record: an authorization flag, a tag
+/// (`issue`, `issuewild`, or `iodef`), and its value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CaaRecord {
+    critical: bool,
+    tag: String,
+    value: String,
+}
+
+/// Query CAA for every `DNS:` SAN and confirm `ca_domain` is authorized to
+/// issue for it. Violations are a blocking error when `require_caa` is set
+/// and a warning otherwise, so operators don't rotate to a cert the CA would
+/// refuse to sign.
+fn check_caa(sans: &[SanEntry], ca_domain: &str, require_caa: bool) -> Result<()> {
+    for hostname in dns_names(sans) {
+        let records = lookup_caa_rrset(&hostname)
+            .with_context(|| format!("CAA lookup for {} failed", hostname))?;
+
+        if let Err(e) = evaluate_caa(&records, ca_domain) {
+            if require_caa {
+                anyhow::bail!("CAA pre-flight check failed for {}: {}", hostname, e);
+            }
+            eprintln!("warning: CAA pre-flight check failed for {}: {}", hostname, e);
+        }
+    }
+    Ok(())
+}
+
+/// Evaluate a hostname's CAA RRset against the intended issuer's domain, per
+/// RFC 8659 section 5.3: if any `issue`/`issuewild` record is present,
+/// `ca_domain` must appear in one of them (an empty, or `;`-only, value
+/// forbids all issuance); a critical flag set on a tag this resolver doesn't
+/// recognize is a hard failure regardless of `ca_domain`.
+fn evaluate_caa(records: &[CaaRecord], ca_domain: &str) -> Result<()> {
+    let mut issue_records = Vec::new();
+    for record in records {
+        match record.tag.as_str() {
+            "issue" | "issuewild" => issue_records.push(record),
+            "iodef" => {}
+            _ if record.critical => {
+                anyhow::bail!("CAA record has an unrecognized critical tag {:?}", record.tag);
+            }
+            _ => {}
+        }
+    }
+
+    if issue_records.is_empty() {
+        return Ok(());
+    }
+
+    let authorized = issue_records.iter().any(|record| {
+        let domain_field = record.value.split(';').next().unwrap_or("").trim();
+        !domain_field.is_empty() && domain_field == ca_domain
+    });
+
+    if authorized {
+        Ok(())
+    } else {
+        anyhow::bail!("CAA policy does not authorize {} to issue for this hostname", ca_domain)
+    }
+}
+
+/// Walk up `hostname`'s domain tree (RFC 8659 section 3: `a.b.example.com`
+/// -> `b.example.com` -> `example.com`) and return the first non-empty CAA
+/// RRset found, or an empty vec if none of its ancestors publish one.
+fn lookup_caa_rrset(hostname: &str) -> Result<Vec<CaaRecord>> {
+    let resolver = system_resolver().unwrap_or(IpAddr::V4(Ipv4Addr::new(8, 8, 8, 8)));
+
+    let mut labels: Vec<&str> = hostname.split('.').collect();
+    while !labels.is_empty() {
+        let domain = labels.join(".");
+        let records = query_caa(&resolver, &domain)?;
+        if !records.is_empty() {
+            return Ok(records);
+        }
+        labels.remove(0);
+    }
+    Ok(Vec::new())
+}
+
+/// Read the first `nameserver` entry from `/etc/resolv.conf`, if any.
+fn system_resolver() -> Option<IpAddr> {
+    let contents = std::fs::read_to_string("/etc/resolv.conf").ok()?;
+    contents.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("nameserver")?;
+        rest.trim().parse().ok()
+    })
+}
+
+/// Send a single CAA (type 257) query for `domain` to `resolver` over UDP
+/// and parse any CAA records in the response.
+fn query_caa(resolver: &IpAddr, domain: &str) -> Result<Vec<CaaRecord>> {
+    const CAA_TYPE: u16 = 257;
+    const IN_CLASS: u16 = 1;
+
+    let mut query = Vec::new();
+    query.extend_from_slice(&0x1234u16.to_be_bytes()); // id
+    query.extend_from_slice(&0x0100u16.to_be_bytes()); // flags: recursion desired
+    query.extend_from_slice(&1u16.to_be_bytes()); // qdcount
+    query.extend_from_slice(&0u16.to_be_bytes()); // ancount
+    query.extend_from_slice(&0u16.to_be_bytes()); // nscount
+    query.extend_from_slice(&0u16.to_be_bytes()); // arcount
+    encode_name(domain, &mut query);
+    query.extend_from_slice(&CAA_TYPE.to_be_bytes());
+    query.extend_from_slice(&IN_CLASS.to_be_bytes());
+
+    let socket = UdpSocket::bind("0.0.0.0:0").context("failed to open UDP socket for DNS query")?;
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .context("failed to set DNS query timeout")?;
+    socket
+        .connect((*resolver, 53))
+        .context("failed to reach DNS resolver")?;
+    socket.send(&query).context("failed to send CAA query")?;
+
+    let mut buf = [0u8; 4096];
+    let len = socket.recv(&mut buf).context("no response to CAA query")?;
+    parse_caa_response(&buf[..len])
+}
+
+/// Encode `name` as a sequence of length-prefixed DNS labels terminated by a
+/// zero byte.
+fn encode_name(name: &str, out: &mut Vec<u8>) {
+    for label in name.split('.') {
+        out.push(label.len() as u8);
+        out.extend_from_slice(label.as_bytes());
+    }
+    out.push(0);
+}
+
+/// Skip over a (possibly compressed) DNS name starting at `pos`, returning
+/// the offset just past it.
+fn skip_name(buf: &[u8], mut pos: usize) -> Result<usize> {
+    loop {
+        let len = *buf.get(pos).context("truncated DNS name")? as usize;
+        if len == 0 {
+            return Ok(pos + 1);
+        }
+        if len & 0xC0 == 0xC0 {
+            return Ok(pos + 2); // compression pointer: 2 bytes, no further labels here
+        }
+        pos += 1 + len;
+    }
+}
+
+/// Parse a raw DNS response buffer, returning any CAA (type 257) records
+/// found in the answer section.
+fn parse_caa_response(buf: &[u8]) -> Result<Vec<CaaRecord>> {
+    if buf.len() < 12 {
+        anyhow::bail!("truncated DNS response");
+    }
+    let qdcount = u16::from_be_bytes([buf[4], buf[5]]) as usize;
+    let ancount = u16::from_be_bytes([buf[6], buf[7]]) as usize;
+
+    let mut pos = 12;
+    for _ in 0..qdcount {
+        pos = skip_name(buf, pos)?;
+        pos += 4; // qtype + qclass
+    }
+
+    let mut records = Vec::new();
+    for _ in 0..ancount {
+        pos = skip_name(buf, pos)?;
+        let rtype = u16::from_be_bytes([
+            *buf.get(pos).context("truncated answer")?,
+            *buf.get(pos + 1).context("truncated answer")?,
+        ]);
+        pos += 8; // type + class + ttl
+        let rdlength = u16::from_be_bytes([
+            *buf.get(pos).context("truncated answer")?,
+            *buf.get(pos + 1).context("truncated answer")?,
+        ]) as usize;
+        pos += 2;
+        let rdata = buf.get(pos..pos + rdlength).context("truncated answer rdata")?;
+
+        if rtype == CAA_RTYPE {
+            records.push(parse_caa_rdata(rdata)?);
+        }
+        pos += rdlength;
+    }
+
+    Ok(records)
+}
+
+/// The CAA RR type code (RFC 8659 section 3).
+const CAA_RTYPE: u16 = 257;
+
+/// Parse a CAA record's RDATA: a flags byte, a length-prefixed tag, and the
+/// remaining bytes as the value.
+fn parse_caa_rdata(rdata: &[u8]) -> Result<CaaRecord> {
+    if rdata.len() < 2 {
+        anyhow::bail!("truncated CAA record");
+    }
+    let flags = rdata[0];
+    let tag_len = rdata[1] as usize;
+    let tag = rdata.get(2..2 + tag_len).context("truncated CAA tag")?;
+    let value = rdata.get(2 + tag_len..).context("truncated CAA value")?;
+
+    Ok(CaaRecord {
+        critical: flags & 0x80 != 0,
+        tag: String::from_utf8_lossy(tag).to_string(),
+        value: String::from_utf8_lossy(value).to_string(),
+    })
+}
+
+pub async fn run(
+    server_url: &str,
+    sans: &str,
+    acme: Option<AcmeOptions>,
+    require_caa: bool,
+    sni: Option<String>,
+) -> Result<()> {
     println!("Rotating management certificate...");
+    if let Some(hostname) = &sni {
+        println!("Targeting SNI hostname: {} (other hostnames' certs are left untouched)", hostname);
+    }
     println!();
 
-    // Get password from user
-    let password = read_password("Enter ACP password: ")?;
-    let password_hash = hash_password(&password);
+    // Prefer a pre-hashed token from ACP_AUTH_TOKENS so scripted, fleet-wide
+    // rotation doesn't block on an interactive password prompt
+    let password_hash = match lookup_auth_token(server_url)? {
+        Some(token) => token,
+        None => {
+            let password = read_password("Enter ACP password: ")?;
+            hash_password(&password)
+        }
+    };
 
-    // Parse SANs from comma-separated string
-    let sans_vec: Vec<String> = sans
-        .split(',')
-        .map(|san| san.trim().to_string())
-        .collect();
+    // Parse and validate SANs before making any network call
+    let sans_vec = parse_sans(sans)?;
+
+    // CAA pre-flight: confirm the intended issuer is authorized to sign for
+    // every DNS SAN before rotating to a cert it might refuse to issue
+    if let Some(acme_opts) = &acme {
+        check_caa(&sans_vec, &acme_opts.ca_domain, require_caa)?;
+    }
 
     // Create API client
     let client = crate::create_api_client(server_url)?;
 
-    // Call rotate endpoint
-    let body = json!({
-        "sans": sans_vec,
-    });
+    // Call rotate endpoint, optionally installing an ACME-issued chain
+    // instead of asking the server to self-sign one
+    let mut body = if let Some(acme) = &acme {
+        println!("Requesting a certificate from {}...", acme.directory_url);
+        let certificate_chain_pem = issue_via_acme(acme, &dns_names(&sans_vec)).await?;
+        json!({
+            "sans": sans_vec,
+            "certificate_chain_pem": certificate_chain_pem,
+        })
+    } else {
+        json!({
+            "sans": sans_vec,
+        })
+    };
+
+    // `sni` targets the rotation at a single hostname's entry in the
+    // server's per-SNI certificate map, leaving every other hostname alone
+    if let Some(hostname) = &sni {
+        body.as_object_mut()
+            .expect("body is always constructed as a JSON object above")
+            .insert("sni".to_string(), json!(hostname));
+    }
 
     let response: crate::client::RotateManagementCertResponse = client
         .post_auth("/v1/management-cert", &password_hash, body)
@@ -34,6 +461,9 @@ pub async fn run(server_url: &str, sans: &str) -> Result<()> {
     if response.rotated {
         println!("Management certificate rotated successfully!");
         println!("New SANs: {}", response.sans.join(", "));
+        if acme.is_some() {
+            println!("Certificate issued by a publicly trusted CA via ACME.");
+        }
         println!();
         println!("Note: New connections will use the new certificate.");
         println!("Existing connections will continue to work until they reconnect.");
@@ -44,49 +474,374 @@ pub async fn run(server_url: &str, sans: &str) -> Result<()> {
     Ok(())
 }
 
+/// Run the ACME v2 flow for `sans` against `acme.directory_url` and return the
+/// issued PEM certificate chain: register (or reuse) an account, order the
+/// SANs, satisfy each authorization's http-01 challenge with a short-lived
+/// standalone listener on port 80, finalize with a freshly generated CSR, and
+/// download the resulting chain.
+pub(crate) async fn issue_via_acme(acme: &AcmeOptions, sans: &[String]) -> Result<String> {
+    use acp_lib::acme::{generate_csr, AcmeClient};
+
+    let client = AcmeClient::new(&acme.directory_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to fetch ACME directory: {}", e))?;
+    let account = load_or_register_account(&client, &acme.email).await?;
+
+    let (order, order_url) = client
+        .new_order(&account, sans)
+        .await
+        .map_err(|e| anyhow::anyhow!("ACME new-order request failed: {}", e))?;
+
+    for authz_url in &order.authorizations {
+        let authz = client
+            .fetch_authorization(authz_url)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to fetch ACME authorization: {}", e))?;
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|c| c.challenge_type == "http-01")
+            .context("no http-01 challenge offered for this authorization")?;
+
+        let key_authorization = account.http01_response(&challenge.token);
+        let listener = serve_http01_challenge(challenge.token.clone(), key_authorization);
+
+        client
+            .respond_to_challenge(&account, challenge)
+            .await
+            .map_err(|e| anyhow::anyhow!("ACME challenge response failed: {}", e))?;
+
+        let validated = poll_authorization(&client, authz_url).await;
+        listener.abort();
+
+        let authz = validated?;
+        if authz.status != "valid" {
+            anyhow::bail!(
+                "ACME authorization for {} did not become valid (status: {})",
+                authz.identifier.value,
+                authz.status
+            );
+        }
+    }
+
+    let ready_order = client
+        .poll_order(&order_url, 30)
+        .await
+        .map_err(|e| anyhow::anyhow!("ACME order did not become ready: {}", e))?;
+    let (csr_der, _private_key_pem) =
+        generate_csr(sans).map_err(|e| anyhow::anyhow!("failed to build CSR: {}", e))?;
+    let finalized = client
+        .finalize(&account, &ready_order, &csr_der)
+        .await
+        .map_err(|e| anyhow::anyhow!("ACME finalize request failed: {}", e))?;
+
+    let certificate_url = finalized
+        .certificate
+        .context("ACME order finalized without a certificate URL")?;
+
+    client
+        .download_certificate(&certificate_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("failed to download issued certificate: {}", e))
+}
+
+/// Poll an authorization until its status leaves `pending`, up to 30 tries.
+async fn poll_authorization(
+    client: &acp_lib::acme::AcmeClient,
+    authorization_url: &str,
+) -> Result<acp_lib::acme::AcmeAuthorization> {
+    for _ in 0..30 {
+        let authz = client
+            .fetch_authorization(authorization_url)
+            .await
+            .map_err(|e| anyhow::anyhow!("failed to poll ACME authorization: {}", e))?;
+        if authz.status != "pending" {
+            return Ok(authz);
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    }
+
+    anyhow::bail!("timed out waiting for ACME authorization to leave pending")
+}
+
+/// Binds a throwaway HTTP server on port 80 serving the key authorization at
+/// the well-known ACME path, for the lifetime of one challenge validation.
+/// The returned handle's `abort()` tears the listener down once the CA has
+/// finished checking it.
+fn serve_http01_challenge(token: String, key_authorization: String) -> tokio::task::JoinHandle<()> {
+    use axum::{routing::get, Router};
+
+    let path = format!("/.well-known/acme-challenge/{}", token);
+    let app = Router::new().route(&path, get(move || async move { key_authorization.clone() }));
+
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind("0.0.0.0:80").await {
+            Ok(listener) => listener,
+            Err(e) => {
+                eprintln!("failed to bind port 80 for http-01 challenge: {}", e);
+                return;
+            }
+        };
+        let _ = axum::serve(listener, app).await;
+    })
+}
+
+/// Load the persisted ACME account from a prior rotation, or register a new
+/// one and persist it to `~/.config/acp/acme_account_key.pem` so renewals
+/// reuse the same account instead of registering a fresh one each time.
+async fn load_or_register_account(
+    client: &acp_lib::acme::AcmeClient,
+    email: &str,
+) -> Result<acp_lib::acme::AcmeAccount> {
+    use acp_lib::acme::AcmeAccount;
+
+    let home = std::env::var("HOME").context("HOME env var not set")?;
+    let key_path = format!("{}/.config/acp/acme_account_key.pem", home);
+    let kid_path = format!("{}/.config/acp/acme_account_kid", home);
+
+    if let (Ok(pem), Ok(kid)) = (std::fs::read_to_string(&key_path), std::fs::read_to_string(&kid_path)) {
+        return AcmeAccount::from_pkcs8_pem(&pem, kid)
+            .map_err(|e| anyhow::anyhow!("failed to restore persisted ACME account: {}", e));
+    }
+
+    let account = client
+        .new_account(email)
+        .await
+        .map_err(|e| anyhow::anyhow!("ACME new-account request failed: {}", e))?;
+
+    let dir = format!("{}/.config/acp", home);
+    std::fs::create_dir_all(&dir).context("failed to create config directory")?;
+    std::fs::write(
+        &key_path,
+        account.to_pkcs8_pem().map_err(|e| anyhow::anyhow!("{}", e))?,
+    )
+    .context("failed to persist ACME account key")?;
+    std::fs::write(&kid_path, &account.kid).context("failed to persist ACME account id")?;
+
+    Ok(account)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    fn issue(critical: bool, value: &str) -> CaaRecord {
+        CaaRecord {
+            critical,
+            tag: "issue".to_string(),
+            value: value.to_string(),
+        }
+    }
+
     #[test]
-    fn test_parse_sans() {
-        // Test parsing comma-separated SANs
-        let input = "DNS:localhost,IP:127.0.0.1";
-        let result: Vec<String> = input.split(',')
-            .map(|san| san.trim().to_string())
-            .collect();
+    fn test_evaluate_caa_no_records_is_unrestricted() {
+        assert!(evaluate_caa(&[], "letsencrypt.org").is_ok());
+    }
 
-        assert_eq!(result, vec!["DNS:localhost", "IP:127.0.0.1"]);
+    #[test]
+    fn test_evaluate_caa_authorizes_matching_issuer() {
+        let records = vec![issue(false, "letsencrypt.org")];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_ok());
     }
 
     #[test]
-    fn test_parse_sans_with_spaces() {
-        // Test parsing with extra whitespace
-        let input = " DNS:localhost , IP:127.0.0.1 , DNS:example.com ";
-        let result: Vec<String> = input.split(',')
-            .map(|san| san.trim().to_string())
-            .collect();
+    fn test_evaluate_caa_rejects_unlisted_issuer() {
+        let records = vec![issue(false, "digicert.com")];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_err());
+    }
 
-        assert_eq!(result, vec!["DNS:localhost", "IP:127.0.0.1", "DNS:example.com"]);
+    #[test]
+    fn test_evaluate_caa_empty_value_forbids_all_issuance() {
+        let records = vec![issue(false, ";")];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_err());
     }
 
     #[test]
-    fn test_parse_sans_single() {
-        // Test single SAN
-        let input = "DNS:localhost";
-        let result: Vec<String> = input.split(',')
-            .map(|san| san.trim().to_string())
-            .collect();
+    fn test_evaluate_caa_ignores_iodef_tag() {
+        let records = vec![CaaRecord {
+            critical: false,
+            tag: "iodef".to_string(),
+            value: "mailto:security@example.com".to_string(),
+        }];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_ok());
+    }
+
+    #[test]
+    fn test_evaluate_caa_critical_unknown_tag_hard_fails() {
+        let records = vec![CaaRecord {
+            critical: true,
+            tag: "unknowntag".to_string(),
+            value: "anything".to_string(),
+        }];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_caa_noncritical_unknown_tag_is_ignored() {
+        let records = vec![CaaRecord {
+            critical: false,
+            tag: "unknowntag".to_string(),
+            value: "anything".to_string(),
+        }];
+        assert!(evaluate_caa(&records, "letsencrypt.org").is_ok());
+    }
+
+    #[test]
+    fn test_parse_caa_rdata_splits_flags_tag_and_value() {
+        let mut rdata = vec![0x80u8, 5];
+        rdata.extend_from_slice(b"issue");
+        rdata.extend_from_slice(b"letsencrypt.org");
+
+        let record = parse_caa_rdata(&rdata).unwrap();
+        assert!(record.critical);
+        assert_eq!(record.tag, "issue");
+        assert_eq!(record.value, "letsencrypt.org");
+    }
 
-        assert_eq!(result, vec!["DNS:localhost"]);
+    #[test]
+    fn test_parse_sans_dns_and_ipv4() {
+        let result = parse_sans("DNS:localhost,IP:127.0.0.1").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                SanEntry::Dns("localhost".to_string()),
+                SanEntry::IpV4(Ipv4Addr::new(127, 0, 0, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sans_with_spaces() {
+        let result = parse_sans(" DNS:localhost , IP:127.0.0.1 , DNS:example.com ").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                SanEntry::Dns("localhost".to_string()),
+                SanEntry::IpV4(Ipv4Addr::new(127, 0, 0, 1)),
+                SanEntry::Dns("example.com".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sans_single() {
+        let result = parse_sans("DNS:localhost").unwrap();
+        assert_eq!(result, vec![SanEntry::Dns("localhost".to_string())]);
     }
 
     #[test]
     fn test_parse_sans_multiple() {
-        // Test multiple SANs
-        let input = "DNS:localhost,DNS:example.com,IP:127.0.0.1,IP:192.168.1.1";
-        let result: Vec<String> = input.split(',')
-            .map(|san| san.trim().to_string())
-            .collect();
+        let result = parse_sans("DNS:localhost,DNS:example.com,IP:127.0.0.1,IP:192.168.1.1").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                SanEntry::Dns("localhost".to_string()),
+                SanEntry::Dns("example.com".to_string()),
+                SanEntry::IpV4(Ipv4Addr::new(127, 0, 0, 1)),
+                SanEntry::IpV4(Ipv4Addr::new(192, 168, 1, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sans_bracketed_ipv6() {
+        let result = parse_sans("IP:[::1]").unwrap();
+        assert_eq!(result, vec![SanEntry::IpV6(Ipv6Addr::LOCALHOST)]);
+    }
+
+    #[test]
+    fn test_parse_sans_email_and_uri() {
+        let result = parse_sans("email:admin@example.com,URI:spiffe://example.com/agent").unwrap();
+        assert_eq!(
+            result,
+            vec![
+                SanEntry::Email("admin@example.com".to_string()),
+                SanEntry::Uri("spiffe://example.com/agent".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sans_rejects_unknown_prefix() {
+        let err = parse_sans("DSN:localhost").unwrap_err();
+        assert!(err.to_string().contains("invalid SAN entry"));
+    }
+
+    #[test]
+    fn test_parse_sans_rejects_malformed_ip() {
+        assert!(parse_sans("IP:not-an-ip").is_err());
+    }
 
-        assert_eq!(result, vec!["DNS:localhost", "DNS:example.com", "IP:127.0.0.1", "IP:192.168.1.1"]);
+    #[test]
+    fn test_dns_names_filters_non_dns_entries() {
+        let sans = parse_sans("DNS:a.example.com,IP:127.0.0.1,DNS:b.example.com").unwrap();
+        assert_eq!(dns_names(&sans), vec!["a.example.com", "b.example.com"]);
+    }
+
+    #[test]
+    fn test_auth_token_entry_matches_domain_and_subdomain() {
+        let entry = parse_auth_token_entry("secret@example.com").unwrap();
+        assert!(entry.matches("example.com", None));
+        assert!(entry.matches("a.example.com", None));
+        assert!(!entry.matches("notexample.com", None));
+    }
+
+    #[test]
+    fn test_auth_token_entry_leading_dot_equals_no_dot() {
+        let entry = parse_auth_token_entry("secret@.example.com").unwrap();
+        assert!(entry.matches("example.com", None));
+        assert!(entry.matches("a.example.com", None));
+    }
+
+    #[test]
+    fn test_auth_token_entry_ip_matches_only_exact_ip() {
+        let entry = parse_auth_token_entry("secret@1.1.1.1").unwrap();
+        assert!(entry.matches("1.1.1.1", None));
+        assert!(!entry.matches("1.1.1.2", None));
+    }
+
+    #[test]
+    fn test_auth_token_entry_bracketed_ipv6_with_port() {
+        let entry = parse_auth_token_entry("secret@[::1]:8443").unwrap();
+        assert!(entry.matches("::1", Some(8443)));
+        assert!(!entry.matches("::1", Some(9000)));
+        assert!(!entry.matches("::1", None));
+    }
+
+    #[test]
+    fn test_auth_token_entry_no_port_matches_any_port() {
+        let entry = parse_auth_token_entry("secret@example.com").unwrap();
+        assert!(entry.matches("example.com", Some(443)));
+        assert!(entry.matches("example.com", None));
+    }
+
+    #[test]
+    fn test_parse_authority_strips_scheme_and_path() {
+        assert_eq!(
+            parse_authority("https://example.com:8443/v1/management-cert").unwrap(),
+            ("example.com".to_string(), Some(8443))
+        );
+        assert_eq!(
+            parse_authority("example.com").unwrap(),
+            ("example.com".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn test_lookup_auth_token_picks_matching_entry() {
+        let raw = "other@other.com tokenval@example.com:8443 third@1.1.1.1";
+        let (host, port) = parse_authority("https://example.com:8443").unwrap();
+        let found = raw
+            .split_whitespace()
+            .map(|entry| parse_auth_token_entry(entry).unwrap())
+            .find(|entry| entry.matches(&host, port))
+            .map(|entry| entry.token);
+        assert_eq!(found, Some("tokenval".to_string()));
+    }
+
+    #[test]
+    fn test_lookup_auth_token_returns_none_when_env_unset() {
+        std::env::remove_var("ACP_AUTH_TOKENS");
+        assert_eq!(lookup_auth_token("https://example.com").unwrap(), None);
     }
 }
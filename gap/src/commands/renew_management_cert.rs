@@ -0,0 +1,106 @@
+//! Background auto-renewal daemon for management certificates
+//!
+//! Long-running counterpart to `new_management_cert`: periodically checks
+//! the most recently installed certificate's expiry and re-runs the ACME
+//! issuance/rotation path when it falls within the renewal window, so
+//! operators don't need to script this around a cron job.
+
+use super::new_management_cert::{dns_names, issue_via_acme, parse_sans, AcmeOptions};
+use crate::auth::{hash_password, read_password};
+use anyhow::{Context, Result};
+use rand::Rng;
+use serde_json::json;
+use std::time::Duration;
+
+/// How often to re-check the certificate's expiry between renewal attempts.
+const CHECK_INTERVAL: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// Renewal checks fire at most this much later than `CHECK_INTERVAL`, so a
+/// fleet of these daemons doesn't all wake and re-issue at once.
+const MAX_JITTER: Duration = Duration::from_secs(30 * 60);
+
+pub async fn run(server_url: &str, sans: &str, acme: AcmeOptions, renew_within_days: i64) -> Result<()> {
+    println!("Starting management certificate auto-renewal daemon...");
+    println!(
+        "Checking every {} hours, renewing within {} days of expiry.",
+        CHECK_INTERVAL.as_secs() / 3600,
+        renew_within_days
+    );
+    println!();
+
+    let password = read_password("Enter ACP password: ")?;
+    let password_hash = hash_password(&password);
+    let sans_vec = parse_sans(sans)?;
+    let cache_path = cert_cache_path()?;
+    let client = crate::create_api_client(server_url)?;
+
+    loop {
+        let due = std::fs::read_to_string(&cache_path)
+            .ok()
+            .map(|cached_pem| acp_lib::acme::needs_renewal(&cached_pem, renew_within_days).unwrap_or(true))
+            .unwrap_or(true);
+
+        if due {
+            let outcome: Result<()> = async {
+                let certificate_chain_pem = issue_via_acme(&acme, &dns_names(&sans_vec)).await?;
+
+                let body = json!({
+                    "sans": sans_vec,
+                    "certificate_chain_pem": certificate_chain_pem,
+                });
+
+                let response: crate::client::RotateManagementCertResponse = client
+                    .post_auth("/v1/management-cert", &password_hash, body)
+                    .await?;
+
+                if !response.rotated {
+                    anyhow::bail!("server reported the rotation did not apply");
+                }
+
+                std::fs::write(&cache_path, &certificate_chain_pem).context("failed to cache renewed certificate")?;
+                Ok(())
+            }
+            .await;
+
+            match outcome {
+                Ok(()) => println!("[{}] management certificate renewed", now_iso8601()),
+                Err(e) => eprintln!("[{}] management certificate renewal failed: {}", now_iso8601(), e),
+            }
+        }
+
+        tokio::time::sleep(sleep_with_jitter()).await;
+    }
+}
+
+/// Local cache of the most recently installed certificate chain, used to
+/// check `notAfter` without contacting the server on every tick.
+fn cert_cache_path() -> Result<String> {
+    let home = std::env::var("HOME").context("HOME env var not set")?;
+    let dir = format!("{}/.config/acp", home);
+    std::fs::create_dir_all(&dir).context("failed to create config directory")?;
+    Ok(format!("{}/management-cert-chain.pem", dir))
+}
+
+/// `CHECK_INTERVAL` plus a random jitter in `[0, MAX_JITTER)`.
+fn sleep_with_jitter() -> Duration {
+    let jitter_secs = rand::thread_rng().gen_range(0..MAX_JITTER.as_secs());
+    CHECK_INTERVAL + Duration::from_secs(jitter_secs)
+}
+
+fn now_iso8601() -> String {
+    chrono::Utc::now().to_rfc3339()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_with_jitter_stays_within_bounds() {
+        for _ in 0..50 {
+            let delay = sleep_with_jitter();
+            assert!(delay >= CHECK_INTERVAL);
+            assert!(delay < CHECK_INTERVAL + MAX_JITTER);
+        }
+    }
+}